@@ -0,0 +1,246 @@
+//! Tests of reading all events of a store, across streams.
+//!
+//! Each test needs a store of its own, to which no other test commits.
+
+use std::pin::pin;
+
+use futures::{future, StreamExt as _, TryStreamExt as _};
+use occur::store::all::{self, GlobalPosition, Subscribe as _};
+use occur::store::{read, AllStream as _, GlobalStore, WriteStream as _};
+use occur::ErrorWithKind as _;
+use uuid::Uuid;
+
+use crate::{deposited, events, new_stream_id, Event};
+
+/// The number of commits that are made concurrently.
+const CONCURRENT_COMMITS: u64 = 8;
+
+/// Commits events interleaved between two streams, and returns their IDs.
+async fn interleave<S: GlobalStore<Event = Event>>(store: &S) -> (Uuid, Uuid) {
+    let (alice, bob) = (new_stream_id(), new_stream_id());
+    let [opened, deposited, withdrawn] = events();
+    store.write_stream(alice).commit_unconditionally(&opened).await.unwrap();
+    store.write_stream(bob).commit_unconditionally(&opened).await.unwrap();
+    store
+        .write_stream(alice)
+        .commit_many_unconditionally([&deposited, &withdrawn])
+        .await
+        .unwrap();
+    (alice, bob)
+}
+
+async fn read<S: GlobalStore<Event = Event>>(
+    store: &S,
+    options: all::Options,
+) -> Vec<(Uuid, u32, GlobalPosition)> {
+    store
+        .all_stream()
+        .read(options)
+        .await
+        .unwrap()
+        .map_ok(|item| {
+            (item.stream_id, item.commit_number, item.global_position)
+        })
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+pub async fn read_all_in_commit_order<S: GlobalStore<Event = Event>>(store: S) {
+    let (alice, bob) = interleave(&store).await;
+    let [opened, deposited, withdrawn] = events();
+
+    let items: Vec<_> = store
+        .all_stream()
+        .read_all()
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    let item = |stream_id, commit_number, global_position, event| all::Item {
+        stream_id,
+        commit_number,
+        global_position,
+        event,
+    };
+    assert_eq!(items, [
+        item(alice, 0, 0, opened.clone()),
+        item(bob, 0, 1, opened),
+        item(alice, 1, 2, deposited),
+        item(alice, 2, 3, withdrawn),
+    ]);
+}
+
+pub async fn read_forward_from_position<S: GlobalStore<Event = Event>>(
+    store: S,
+) {
+    let (alice, bob) = interleave(&store).await;
+
+    let items = read(&store, all::Options {
+        position: all::Position::GlobalPosition(1),
+        direction: read::Direction::Forward,
+        limit: Some(2),
+    })
+    .await;
+
+    assert_eq!(items, [(bob, 0, 1), (alice, 1, 2)]);
+}
+
+pub async fn read_backward<S: GlobalStore<Event = Event>>(store: S) {
+    let (alice, bob) = interleave(&store).await;
+
+    let all = read(&store, all::Options {
+        position: all::Position::Last,
+        direction: read::Direction::Backward,
+        limit: None,
+    })
+    .await;
+    let limited = read(&store, all::Options {
+        position: all::Position::GlobalPosition(1),
+        direction: read::Direction::Backward,
+        limit: Some(1),
+    })
+    .await;
+
+    assert_eq!(all, [(alice, 2, 3), (alice, 1, 2), (bob, 0, 1), (alice, 0, 0)]);
+    assert_eq!(limited, [(bob, 0, 1)]);
+}
+
+pub async fn read_missing_position<S: GlobalStore<Event = Event>>(store: S) {
+    interleave(&store).await;
+    let mut all_stream = store.all_stream();
+
+    let err = all_stream
+        .read(all::Options {
+            position: all::Position::GlobalPosition(4),
+            direction: read::Direction::Forward,
+            limit: None,
+        })
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(err.kind(), read::ErrorKind::CommitNotFound);
+}
+
+pub async fn read_empty_store<S: GlobalStore<Event = Event>>(store: S) {
+    let mut all_stream = store.all_stream();
+
+    for position in [all::Position::First, all::Position::Last] {
+        let err = all_stream
+            .read(all::Options {
+                position,
+                direction: read::Direction::Backward,
+                limit: None,
+            })
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), read::ErrorKind::CommitNotFound);
+    }
+}
+
+pub async fn reads_do_not_block_commits<S: GlobalStore<Event = Event>>(
+    store: S,
+) {
+    let id = new_stream_id();
+    let mut write_stream = store.write_stream(id);
+    for amount in 0..5 {
+        write_stream.commit_unconditionally(&deposited(amount)).await.unwrap();
+    }
+    let mut all_stream = store.all_stream();
+
+    let mut events = pin!(all_stream
+        .read(all::Options {
+            position: all::Position::Last,
+            direction: read::Direction::Backward,
+            limit: None,
+        })
+        .await
+        .unwrap());
+    let first = events.try_next().await.unwrap().unwrap();
+    assert_eq!(first.event, deposited(4));
+    write_stream.commit_unconditionally(&deposited(5)).await.unwrap();
+    // the read started before the commit, so it doesn't include it
+    let rest: Vec<_> =
+        events.map_ok(|item| item.event).try_collect().await.unwrap();
+    assert_eq!(rest, (0..4).rev().map(deposited).collect::<Vec<_>>());
+}
+
+pub async fn concurrent_commits_are_assigned_consecutive_global_positions<S>(
+    store: S,
+) where
+    S: GlobalStore<Event = Event>,
+{
+    let events: Vec<_> = (0..CONCURRENT_COMMITS)
+        .map(|amount| [deposited(amount), deposited(amount)])
+        .collect();
+    let mut streams: Vec<_> =
+        events.iter().map(|_| store.write_stream(new_stream_id())).collect();
+
+    let results =
+        future::join_all(streams.iter_mut().zip(&events).map(
+            |(stream, events)| stream.commit_many_unconditionally(events),
+        ))
+        .await;
+    for result in results {
+        result.unwrap();
+    }
+
+    let items: Vec<_> = store
+        .all_stream()
+        .read_all()
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let global_positions: Vec<_> =
+        items.iter().map(|item| item.global_position).collect();
+    assert_eq!(
+        global_positions,
+        (0..2 * CONCURRENT_COMMITS).collect::<Vec<_>>()
+    );
+    // the events of each commit are adjacent
+    for commit in items.chunks(2) {
+        assert_eq!(commit[0].stream_id, commit[1].stream_id);
+        assert_eq!((commit[0].commit_number, commit[1].commit_number), (0, 1));
+    }
+}
+
+pub async fn subscribe_from_position<S>(store: S)
+where
+    S: GlobalStore<Event = Event>,
+    S::AllStream: all::Subscribe,
+{
+    let (alice, bob) = (new_stream_id(), new_stream_id());
+    let [opened, deposited, _] = events();
+    let mut all_stream = store.all_stream();
+
+    // a global position that wasn't committed yet can be subscribed to
+    let mut subscription = all_stream
+        .subscribe(all::Position::GlobalPosition(1))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+    let next = subscription.next();
+    let commit = async {
+        store.write_stream(alice).commit_unconditionally(&opened).await?;
+        store.write_stream(bob).commit_unconditionally(&deposited).await
+    };
+    let (next, commit) = futures::join!(next, commit);
+
+    commit.unwrap();
+    assert_eq!(
+        next,
+        Some(all::Item {
+            stream_id: bob,
+            commit_number: 0,
+            global_position: 1,
+            event: deposited,
+        }),
+    );
+}
@@ -0,0 +1,90 @@
+//! Tests of committing and reading the metadata of events.
+
+use std::time::SystemTime;
+
+use futures::TryStreamExt as _;
+use occur::envelope::{Envelope, Metadata, Recorded};
+use occur::store::{read, write, ReadStream as _, Store, WriteStream as _};
+use uuid::Uuid;
+
+use crate::{events, new_stream_id, Event};
+
+async fn read_recorded<S: Store<Event = Event>>(
+    store: &S,
+    id: Uuid,
+) -> Vec<Recorded<Event>> {
+    let mut stream = store.read_stream(id);
+    stream
+        .read_recorded(read::Options {
+            position: read::Position::First,
+            direction: read::Direction::Forward,
+            limit: None,
+        })
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+pub async fn commit_and_read_envelopes<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let [opened, deposited, _] = events();
+    let mut stream = store.write_stream(id);
+
+    let request_id = Uuid::now_v7();
+    let metadata = Metadata::new()
+        .with_correlation_id(request_id)
+        .with_custom("user_agent", "test");
+    let before = SystemTime::now();
+    stream
+        .commit_envelope(
+            Envelope::new(&opened).with_metadata(metadata.clone()),
+            write::Condition::None,
+        )
+        .await
+        .unwrap();
+    let deposited_metadata = Metadata::new().caused_by(&metadata);
+    stream
+        .commit_many_envelopes(
+            [Envelope::new(&deposited)
+                .with_metadata(deposited_metadata.clone())],
+            write::Condition::AssignCommitNumber(1),
+        )
+        .await
+        .unwrap();
+    let after = SystemTime::now();
+
+    let recorded = read_recorded(&store, id).await;
+    assert_eq!(
+        recorded
+            .iter()
+            .map(|recorded| (&recorded.event, &recorded.metadata))
+            .collect::<Vec<_>>(),
+        [(&opened, &metadata), (&deposited, &deposited_metadata)],
+    );
+    for recorded in &recorded {
+        assert!((before..=after).contains(&recorded.timestamp));
+    }
+    assert_eq!(deposited_metadata.correlation_id, Some(request_id));
+    assert_eq!(deposited_metadata.causation_id, Some(metadata.id.0));
+}
+
+pub async fn bare_commits_are_given_metadata<S: Store<Event = Event>>(
+    store: S,
+) {
+    let id = new_stream_id();
+    let [opened, deposited, _] = events();
+
+    store
+        .write_stream(id)
+        .commit_many_unconditionally([&opened, &deposited])
+        .await
+        .unwrap();
+
+    let recorded = read_recorded(&store, id).await;
+    assert_eq!(recorded.len(), 2);
+    assert_ne!(recorded[0].metadata.id, recorded[1].metadata.id);
+    assert_eq!(recorded[0].metadata.correlation_id, None);
+    assert!(recorded[0].metadata.custom.is_empty());
+}
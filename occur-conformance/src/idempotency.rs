@@ -0,0 +1,144 @@
+//! Tests of recommitting events that were already committed.
+
+use occur::envelope::Envelope;
+use occur::store::{write, Store, WriteStream as _};
+use occur::ErrorWithKind as _;
+
+use crate::{
+    deposited,
+    events,
+    new_stream_id,
+    read_all,
+    Event,
+    IDEMPOTENCY_WINDOW,
+};
+
+pub async fn recommit_returns_original_commit_number<S>(store: S)
+where
+    S: Store<Event = Event>,
+{
+    let id = new_stream_id();
+    let [opened, deposited, _] = events();
+    let mut stream = store.write_stream(id);
+    let envelope = Envelope::new(&opened);
+
+    for _ in 0..2 {
+        assert_eq!(
+            stream
+                .commit_envelope(envelope.clone(), write::Condition::None)
+                .await
+                .unwrap(),
+            0,
+        );
+    }
+    stream.commit_unconditionally(&deposited).await.unwrap();
+
+    // the original commit number is returned, regardless of the condition
+    assert_eq!(
+        stream
+            .commit_envelope(envelope, write::Condition::AssignCommitNumber(1))
+            .await
+            .unwrap(),
+        0,
+    );
+    assert_eq!(read_all(&store, id).await, [opened, deposited]);
+}
+
+pub async fn recommit_batch<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let [opened, deposited, withdrawn] = events();
+    let mut stream = store.write_stream(id);
+    let batch = [
+        Envelope::new(&opened),
+        Envelope::new(&deposited),
+        Envelope::new(&withdrawn),
+    ];
+
+    stream.commit_unconditionally(&opened).await.unwrap();
+    for _ in 0..2 {
+        assert_eq!(
+            stream
+                .commit_many_envelopes(batch.clone(), write::Condition::None)
+                .await
+                .unwrap(),
+            Some(1),
+        );
+    }
+
+    // a batch that was only partially committed is rejected as a whole
+    let partial = [batch[2].clone(), Envelope::new(&opened)];
+    let reordered = [batch[1].clone(), batch[0].clone()];
+    for envelopes in [partial, reordered] {
+        let err = stream
+            .commit_many_envelopes(envelopes, write::Condition::None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), write::ErrorKind::PartiallyCommitted);
+    }
+    assert_eq!(read_all(&store, id).await.len(), 4);
+}
+
+pub async fn forgotten_ids_are_committed_again<S: Store<Event = Event>>(
+    store: S,
+) {
+    let id = new_stream_id();
+    let [opened, ..] = events();
+    let mut stream = store.write_stream(id);
+    let envelope = Envelope::new(&opened);
+    // commit numbers of the stream are the amounts of its deposits
+    let window = IDEMPOTENCY_WINDOW as u64;
+
+    stream
+        .commit_envelope(envelope.clone(), write::Condition::None)
+        .await
+        .unwrap();
+    for amount in 1..window {
+        stream.commit_unconditionally(&deposited(amount)).await.unwrap();
+    }
+    assert_eq!(
+        stream
+            .commit_envelope(envelope.clone(), write::Condition::None)
+            .await
+            .unwrap(),
+        0,
+    );
+
+    stream.commit_unconditionally(&deposited(window)).await.unwrap();
+    assert_eq!(
+        u64::from(
+            stream
+                .commit_envelope(envelope, write::Condition::None)
+                .await
+                .unwrap()
+        ),
+        window + 1,
+    );
+}
+
+pub async fn batch_with_duplicate_ids_is_rejected<S: Store<Event = Event>>(
+    store: S,
+) {
+    let id = new_stream_id();
+    let [opened, deposited, _] = events();
+    let mut stream = store.write_stream(id);
+    let envelope = Envelope::new(&opened);
+
+    let err = stream
+        .commit_many_envelopes(
+            [envelope.clone(), Envelope::new(&deposited), envelope.clone()],
+            write::Condition::None,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), write::ErrorKind::DuplicateEventId);
+
+    // nothing was committed
+    assert_eq!(
+        stream
+            .commit_envelope(envelope, write::Condition::NoStream)
+            .await
+            .unwrap(),
+        0,
+    );
+    assert_eq!(read_all(&store, id).await, [opened]);
+}
@@ -0,0 +1,210 @@
+//! A conformance test suite for [`occur::Store`] implementations.
+//!
+//! Each module is a suite of tests that exercise one aspect of a store, and
+//! each test is an async function that takes a new store to run against.
+//! Rather than calling them one by one, a store's crate defines all tests of
+//! the suites it supports with [`tests!`], and keeps only its
+//! backend-specific tests to itself.
+//!
+//! Tests are run against stores of [`Event`]s, which must be configured with
+//! a read page size of [`READ_PAGE_SIZE`] and an idempotency window of
+//! [`IDEMPOTENCY_WINDOW`], so that reading across pages and forgetting
+//! committed event IDs are exercised. Stores may be shared by tests (e.g., a
+//! single database), as each test only commits to streams of its own, except
+//! for the tests of [`all`], which need a store of their own.
+
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+// the tests panic when the store doesn't conform, which is their purpose
+#![allow(clippy::missing_panics_doc)]
+
+use futures::TryStreamExt as _;
+use occur::revision;
+use occur::store::{ReadStream as _, Store};
+use uuid::Uuid;
+
+pub mod all;
+pub mod envelope;
+pub mod idempotency;
+pub mod read;
+pub mod subscribe;
+pub mod write;
+
+/// The read page size with which stores must be configured.
+pub const READ_PAGE_SIZE: usize = 3;
+
+/// The idempotency window with which stores must be configured.
+pub const IDEMPOTENCY_WINDOW: usize = 4;
+
+/// The events of the streams that the tests commit to.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    occur::Revision,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Event {
+    Opened { owner: String },
+    Deposited { amount: u64 },
+    Withdrawn { amount: u64 },
+}
+
+impl occur::Event for Event {
+    type StreamId = Uuid;
+    type OldRevision = revision::Empty<Self>;
+}
+
+/// Returns the ID of a stream that no other test commits to.
+#[must_use]
+pub fn new_stream_id() -> Uuid { Uuid::now_v7() }
+
+/// Returns a deposit of `amount`, for tests that need many distinct events.
+#[must_use]
+pub const fn deposited(amount: u64) -> Event { Event::Deposited { amount } }
+
+fn events() -> [Event; 3] {
+    [
+        Event::Opened { owner: "alice".to_owned() },
+        Event::Deposited { amount: 100 },
+        Event::Withdrawn { amount: 30 },
+    ]
+}
+
+async fn read_all<S: Store<Event = Event>>(store: &S, id: Uuid) -> Vec<Event> {
+    let mut stream = store.read_stream(id);
+    stream.read_all().await.unwrap().try_collect().await.unwrap()
+}
+
+/// Defines a test for each test of the given suites, each run against the
+/// store returned by the given expression.
+///
+/// By default, each test is a `#[test]` that blocks on the suite's test:
+///
+/// ```ignore
+/// occur_conformance::tests!(store(), [write, read, idempotency, envelope]);
+/// ```
+///
+/// When the store needs an async runtime, the test attributes are given
+/// instead, along with an `async` expression that's evaluated within the test:
+///
+/// ```ignore
+/// occur_conformance::tests!(
+///     #[tokio::test]
+///     async store().await,
+///     [write, read, idempotency, envelope, all, subscribe]
+/// );
+/// ```
+///
+/// The tests are defined within a `conformance` module, with a module for
+/// each suite (e.g., `conformance::write::no_stream_condition`).
+#[macro_export]
+macro_rules! tests {
+    (
+        $(#[$attr:meta])+
+        async $store:expr,
+        [$($suite:ident),+ $(,)?] $(,)?
+    ) => {
+        $crate::tests!(@module async [$(#[$attr])+] $store, [$($suite),+]);
+    };
+    ($store:expr, [$($suite:ident),+ $(,)?] $(,)?) => {
+        $crate::tests!(@module blocking [] $store, [$($suite),+]);
+    };
+
+    (
+        @module $runner:ident $attrs:tt $store:expr,
+        [$($suite:ident),+]
+    ) => {
+        mod conformance {
+            #[allow(unused_imports)]
+            use super::*;
+            $($crate::tests!(@suite $suite $runner $attrs $store);)+
+        }
+    };
+
+    (@suite write $($args:tt)*) => {
+        $crate::tests!(@tests write [
+            no_stream_condition,
+            stream_exists_condition,
+            last_commit_number_condition,
+            assign_commit_number_condition,
+            concurrent_commits_to_the_same_number,
+            concurrent_unconditional_commits_are_all_committed,
+        ] $($args)*);
+    };
+    (@suite read $($args:tt)*) => {
+        $crate::tests!(@tests read [
+            commit_and_read,
+            reads_across_pages,
+            reads_do_not_block_commits,
+            read_out_of_bounds,
+        ] $($args)*);
+    };
+    (@suite idempotency $($args:tt)*) => {
+        $crate::tests!(@tests idempotency [
+            recommit_returns_original_commit_number,
+            recommit_batch,
+            forgotten_ids_are_committed_again,
+            batch_with_duplicate_ids_is_rejected,
+        ] $($args)*);
+    };
+    (@suite envelope $($args:tt)*) => {
+        $crate::tests!(@tests envelope [
+            commit_and_read_envelopes,
+            bare_commits_are_given_metadata,
+        ] $($args)*);
+    };
+    (@suite all $($args:tt)*) => {
+        $crate::tests!(@tests all [
+            read_all_in_commit_order,
+            read_forward_from_position,
+            read_backward,
+            read_missing_position,
+            read_empty_store,
+            reads_do_not_block_commits,
+            concurrent_commits_are_assigned_consecutive_global_positions,
+            subscribe_from_position,
+        ] $($args)*);
+    };
+    (@suite subscribe $($args:tt)*) => {
+        $crate::tests!(@tests subscribe [
+            catch_up_then_tail,
+            subscribe_from_position,
+            commits_to_other_streams_are_not_received,
+            subscriber_is_woken_by_concurrent_commit,
+            dropped_subscription,
+        ] $($args)*);
+    };
+
+    (
+        @tests $suite:ident [$($test:ident),+ $(,)?]
+        $runner:ident $attrs:tt $store:expr
+    ) => {
+        mod $suite {
+            #[allow(unused_imports)]
+            use super::*;
+            $($crate::tests!(@test $suite $test $runner $attrs $store);)+
+        }
+    };
+
+    (@test $suite:ident $test:ident blocking [] $store:expr) => {
+        #[test]
+        fn $test() {
+            $crate::__private::block_on($crate::$suite::$test($store));
+        }
+    };
+    (
+        @test $suite:ident $test:ident
+        async [$(#[$attr:meta])+] $store:expr
+    ) => {
+        $(#[$attr])+
+        async fn $test() { $crate::$suite::$test($store).await; }
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use futures::executor::block_on;
+}
@@ -0,0 +1,124 @@
+//! Tests of reading a stream.
+
+use std::ops::Range;
+use std::pin::pin;
+
+use futures::TryStreamExt as _;
+use occur::store::read::Direction::{Backward, Forward};
+use occur::store::read::Position::{CommitNumber, First, Last};
+use occur::store::read::{self, Options};
+use occur::store::{ReadStream as _, Store, WriteStream as _};
+use occur::ErrorWithKind as _;
+use uuid::Uuid;
+
+use crate::{deposited, events, new_stream_id, read_all, Event};
+
+async fn read<S: Store<Event = Event>>(
+    store: &S,
+    id: Uuid,
+    options: Options,
+) -> Vec<Event> {
+    let mut stream = store.read_stream(id);
+    stream.read(options).await.unwrap().try_collect().await.unwrap()
+}
+
+pub async fn commit_and_read<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let [opened, deposited, withdrawn] = events();
+    let mut stream = store.write_stream(id);
+
+    assert_eq!(stream.commit_unconditionally(&opened).await.unwrap(), 0);
+    assert_eq!(
+        stream
+            .commit_many_unconditionally([&deposited, &withdrawn])
+            .await
+            .unwrap(),
+        Some(1),
+    );
+
+    let backward = read(&store, id, Options {
+        position: Last,
+        direction: Backward,
+        limit: Some(2),
+    })
+    .await;
+    let from_second = read(&store, id, Options {
+        position: CommitNumber(1),
+        direction: Forward,
+        limit: Some(1),
+    })
+    .await;
+
+    assert_eq!(read_all(&store, id).await, events());
+    assert_eq!(backward, [withdrawn, deposited.clone()]);
+    assert_eq!(from_second, [deposited]);
+}
+
+pub async fn reads_across_pages<S: Store<Event = Event>>(store: S) {
+    let cases: [(read::Position, read::Direction, Option<usize>, Range<u64>);
+        6] = [
+        (First, Forward, None, 0..10),
+        (CommitNumber(2), Forward, Some(5), 2..7),
+        (CommitNumber(8), Forward, Some(5), 8..10),
+        (Last, Backward, None, 0..10),
+        (CommitNumber(7), Backward, Some(4), 4..8),
+        (CommitNumber(1), Backward, Some(5), 0..2),
+    ];
+    let id = new_stream_id();
+    let mut stream = store.write_stream(id);
+    for amount in 0..10 {
+        stream.commit_unconditionally(&deposited(amount)).await.unwrap();
+    }
+
+    for (position, direction, limit, expected) in cases {
+        let events =
+            read(&store, id, Options { position, direction, limit }).await;
+
+        let mut expected: Vec<_> = expected.map(deposited).collect();
+        if direction == Backward {
+            expected.reverse();
+        }
+        assert_eq!(events, expected, "{position:?} {direction:?} {limit:?}");
+    }
+}
+
+pub async fn reads_do_not_block_commits<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let mut write_stream = store.write_stream(id);
+    for amount in 0..5 {
+        write_stream.commit_unconditionally(&deposited(amount)).await.unwrap();
+    }
+    let mut read_stream = store.read_stream(id);
+
+    let mut events = pin!(read_stream.read_all().await.unwrap());
+    assert_eq!(events.try_next().await.unwrap(), Some(deposited(0)));
+    write_stream.commit_unconditionally(&deposited(5)).await.unwrap();
+    // the read started before the commit, so it doesn't include it
+    let rest: Vec<_> = events.try_collect().await.unwrap();
+    assert_eq!(rest, (1..5).map(deposited).collect::<Vec<_>>());
+}
+
+pub async fn read_out_of_bounds<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let mut read_stream = store.read_stream(id);
+
+    for position in [First, Last] {
+        let empty = read_stream
+            .read(Options { position, direction: Backward, limit: None })
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(empty.kind(), read::ErrorKind::CommitNotFound);
+    }
+    store.write_stream(id).commit_unconditionally(&events()[0]).await.unwrap();
+    let out_of_bounds = read_stream
+        .read(Options {
+            position: CommitNumber(1),
+            direction: Forward,
+            limit: None,
+        })
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(out_of_bounds.kind(), read::ErrorKind::CommitNotFound);
+}
@@ -0,0 +1,150 @@
+//! Tests of subscribing to a stream.
+
+use futures::{FutureExt as _, StreamExt as _};
+use occur::store::read::{self, Subscribe};
+use occur::store::{Store, WriteStream as _};
+
+use crate::{events, new_stream_id, Event};
+
+pub async fn catch_up_then_tail<S>(store: S)
+where
+    S: Store<Event = Event>,
+    S::ReadStream: Subscribe,
+{
+    let id = new_stream_id();
+    let [opened, deposited, withdrawn] = events();
+    let mut write_stream = store.write_stream(id);
+    let mut read_stream = store.read_stream(id);
+    write_stream.commit_unconditionally(&opened).await.unwrap();
+
+    let mut subscription = read_stream
+        .subscribe(read::Position::First)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+
+    // replays history
+    assert_eq!(subscription.next().await, Some((0, opened)));
+    // waits for new events
+    assert_eq!(subscription.next().now_or_never(), None);
+
+    write_stream
+        .commit_many_unconditionally([&deposited, &withdrawn])
+        .await
+        .unwrap();
+
+    // then yields new events
+    assert_eq!(subscription.next().await, Some((1, deposited)));
+    assert_eq!(subscription.next().await, Some((2, withdrawn)));
+}
+
+pub async fn subscribe_from_position<S>(store: S)
+where
+    S: Store<Event = Event>,
+    S::ReadStream: Subscribe,
+{
+    let id = new_stream_id();
+    let [opened, deposited, withdrawn] = events();
+    let mut write_stream = store.write_stream(id);
+    let (mut read_stream, mut other_read_stream) =
+        (store.read_stream(id), store.read_stream(id));
+
+    // an empty stream can be subscribed to from its last position
+    let mut from_last = read_stream
+        .subscribe(read::Position::Last)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+    // a commit number that wasn't committed yet can be subscribed to
+    let mut from_future = other_read_stream
+        .subscribe(read::Position::CommitNumber(2))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+
+    write_stream
+        .commit_many_unconditionally([&opened, &deposited, &withdrawn])
+        .await
+        .unwrap();
+
+    assert_eq!(from_last.next().await, Some((0, opened)));
+    assert_eq!(from_future.next().await, Some((2, withdrawn.clone())));
+
+    drop(from_last);
+    let mut from_last = read_stream
+        .subscribe(read::Position::Last)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+    assert_eq!(from_last.next().await, Some((2, withdrawn)));
+}
+
+pub async fn commits_to_other_streams_are_not_received<S>(store: S)
+where
+    S: Store<Event = Event>,
+    S::ReadStream: Subscribe,
+{
+    let (id, other_id) = (new_stream_id(), new_stream_id());
+    let [opened, deposited, _] = events();
+    let mut read_stream = store.read_stream(id);
+    let mut subscription = read_stream
+        .subscribe(read::Position::First)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+
+    store.write_stream(other_id).commit_unconditionally(&opened).await.unwrap();
+    store.write_stream(id).commit_unconditionally(&deposited).await.unwrap();
+
+    assert_eq!(subscription.next().await, Some((0, deposited)));
+}
+
+pub async fn subscriber_is_woken_by_concurrent_commit<S>(store: S)
+where
+    S: Store<Event = Event>,
+    S::ReadStream: Subscribe,
+{
+    let id = new_stream_id();
+    let [opened, deposited, _] = events();
+    let mut write_stream = store.write_stream(id);
+    let mut read_stream = store.read_stream(id);
+    let subscription =
+        read_stream.subscribe(read::Position::First).await.unwrap();
+
+    // the subscription is waiting for events by the time they're committed
+    let (events, commit): (Vec<_>, _) = futures::join!(
+        subscription.map(Result::unwrap).take(2).collect(),
+        write_stream.commit_many_unconditionally([&opened, &deposited]),
+    );
+
+    commit.unwrap();
+    assert_eq!(events, [(0, opened), (1, deposited)]);
+}
+
+pub async fn dropped_subscription<S>(store: S)
+where
+    S: Store<Event = Event>,
+    S::ReadStream: Subscribe,
+{
+    let id = new_stream_id();
+    let [opened, ..] = events();
+    let mut write_stream = store.write_stream(id);
+    let mut read_stream = store.read_stream(id);
+    let mut subscription = read_stream
+        .subscribe(read::Position::First)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .boxed();
+
+    // start waiting for an event, then unsubscribe
+    assert_eq!(subscription.next().now_or_never(), None);
+    drop(subscription);
+
+    write_stream.commit_unconditionally(&opened).await.unwrap();
+}
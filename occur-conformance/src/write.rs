@@ -0,0 +1,164 @@
+//! Tests of commit conditions.
+
+use futures::future;
+use occur::store::write::{self, Condition, Error as _};
+use occur::store::{Store, WriteStream as _};
+use occur::ErrorWithKind as _;
+
+use crate::{deposited, events, new_stream_id, read_all, Event};
+
+/// The number of commits that are made concurrently.
+const CONCURRENT_COMMITS: u32 = 8;
+
+/// Asserts that the given commit failed due to an unmet condition, while the
+/// last commit number of the stream was `last_commit_number`.
+fn assert_condition_not_met<T, E: write::Error>(
+    result: Result<T, E>,
+    last_commit_number: Option<u32>,
+) {
+    let err = result.err().expect("commit should fail");
+    assert_eq!(err.kind(), write::ErrorKind::ConditionNotMet);
+    assert_eq!(err.last_commit_number(), last_commit_number);
+}
+
+pub async fn no_stream_condition<S: Store<Event = Event>>(store: S) {
+    let [opened, ..] = events();
+    let mut stream = store.write_stream(new_stream_id());
+
+    assert_eq!(stream.commit(&opened, Condition::NoStream).await.unwrap(), 0);
+    assert_condition_not_met(
+        stream.commit(&opened, Condition::NoStream).await,
+        Some(0),
+    );
+}
+
+pub async fn stream_exists_condition<S: Store<Event = Event>>(store: S) {
+    let [opened, deposited, _] = events();
+    let mut stream = store.write_stream(new_stream_id());
+
+    assert_condition_not_met(
+        stream.commit(&deposited, Condition::StreamExists).await,
+        None,
+    );
+    stream.commit_unconditionally(&opened).await.unwrap();
+    assert_eq!(
+        stream.commit(&deposited, Condition::StreamExists).await.unwrap(),
+        1,
+    );
+}
+
+pub async fn last_commit_number_condition<S: Store<Event = Event>>(store: S) {
+    let [opened, deposited, withdrawn] = events();
+    let mut stream = store.write_stream(new_stream_id());
+
+    assert_condition_not_met(
+        stream.commit(&opened, Condition::LastCommitNumber(0)).await,
+        None,
+    );
+    stream.commit_unconditionally(&opened).await.unwrap();
+
+    let events = [&deposited, &withdrawn];
+    assert_eq!(
+        stream
+            .commit_many(events, Condition::LastCommitNumber(0))
+            .await
+            .unwrap(),
+        Some(1),
+    );
+
+    // the reported last commit number can be used to retry
+    let result =
+        stream.commit_many(events, Condition::LastCommitNumber(0)).await;
+    let last_commit_number = result.unwrap_err().last_commit_number().unwrap();
+    assert_eq!(last_commit_number, 2);
+    assert_eq!(
+        stream
+            .commit_many(
+                events,
+                Condition::LastCommitNumber(last_commit_number)
+            )
+            .await
+            .unwrap(),
+        Some(3),
+    );
+}
+
+pub async fn assign_commit_number_condition<S: Store<Event = Event>>(store: S) {
+    let id = new_stream_id();
+    let [opened, deposited, withdrawn] = events();
+    let mut stream = store.write_stream(id);
+
+    assert_condition_not_met(stream.commit_as_number(&opened, 1).await, None);
+    assert_eq!(stream.commit_as_number(&opened, 0).await.unwrap(), 0);
+    assert_condition_not_met(
+        stream.commit_as_number(&deposited, 0).await,
+        Some(0),
+    );
+    assert_condition_not_met(
+        stream
+            .commit_many(
+                [&deposited, &withdrawn],
+                Condition::AssignCommitNumber(2),
+            )
+            .await,
+        Some(0),
+    );
+
+    assert_eq!(read_all(&store, id).await, [opened]);
+}
+
+pub async fn concurrent_commits_to_the_same_number<S: Store<Event = Event>>(
+    store: S,
+) {
+    let id = new_stream_id();
+    let deposited = deposited(1);
+    let mut streams: Vec<_> =
+        (0..CONCURRENT_COMMITS).map(|_| store.write_stream(id)).collect();
+
+    let results = future::join_all(
+        streams.iter_mut().map(|stream| stream.commit_as_number(&deposited, 0)),
+    )
+    .await;
+
+    let mut committed = 0;
+    for result in results {
+        match result {
+            Ok(commit_number) => {
+                assert_eq!(commit_number, 0);
+                committed += 1;
+            }
+            Err(err) => {
+                assert_eq!(err.kind(), write::ErrorKind::ConditionNotMet);
+                assert_eq!(err.last_commit_number(), Some(0));
+            }
+        }
+    }
+    assert_eq!(committed, 1);
+    assert_eq!(read_all(&store, id).await, [deposited]);
+}
+
+pub async fn concurrent_unconditional_commits_are_all_committed<S>(store: S)
+where
+    S: Store<Event = Event>,
+{
+    let id = new_stream_id();
+    let events: Vec<_> = (0..CONCURRENT_COMMITS)
+        .map(|amount| deposited(amount.into()))
+        .collect();
+    let mut streams: Vec<_> =
+        events.iter().map(|_| store.write_stream(id)).collect();
+
+    let results = future::join_all(
+        streams
+            .iter_mut()
+            .zip(&events)
+            .map(|(stream, event)| stream.commit_unconditionally(event)),
+    )
+    .await;
+
+    let mut commit_numbers: Vec<_> =
+        results.into_iter().map(Result::unwrap).collect();
+    commit_numbers.sort_unstable();
+    assert_eq!(commit_numbers, (0..CONCURRENT_COMMITS).collect::<Vec<_>>());
+    assert_eq!(read_all(&store, id).await.len(), events.len());
+}
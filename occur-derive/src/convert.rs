@@ -0,0 +1,236 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{DeriveInput, Path, Type, Variant};
+
+use crate::revision::{self, VariantRevision};
+
+/// The parsed `#[convert(...)]` attribute of a single enum variant.
+struct Conversion {
+    into: Type,
+    /// Whether `into` is the enum itself (i.e., a newer old revision), rather
+    /// than the event type.
+    into_self: bool,
+    with: Path,
+    span: Span,
+}
+
+fn is_self(input: &DeriveInput, ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else { return false };
+    type_path.qself.is_none()
+        && (type_path.path.is_ident("Self")
+            || type_path.path.is_ident(&input.ident))
+}
+
+fn parse_variant(
+    input: &DeriveInput,
+    variant: &Variant,
+) -> syn::Result<Conversion> {
+    let mut into = None;
+    let mut with = None;
+    let mut span = None;
+
+    for attr in variant.attrs.iter().filter(|a| a.path().is_ident("convert")) {
+        span = Some(attr.span());
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("into") {
+                into = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                with = Some(meta.value()?.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `into` or `with`"))
+            }
+        })?;
+    }
+
+    let Some(span) = span else {
+        return Err(syn::Error::new(
+            variant.ident.span(),
+            "missing `#[convert(into = ..., with = ...)]` attribute",
+        ));
+    };
+    let (Some(into), Some(with)) = (into, with) else {
+        return Err(syn::Error::new(
+            span,
+            "both `into` and `with` must be specified",
+        ));
+    };
+
+    let into_self = is_self(input, &into);
+    Ok(Conversion { into, into_self, with, span })
+}
+
+/// Returns the event type, which all variants not converting into `Self` must
+/// agree on.
+fn event_type(conversions: &[Conversion]) -> syn::Result<&Type> {
+    let mut event: Option<&Type> = None;
+    for conversion in conversions.iter().filter(|c| !c.into_self) {
+        let ty = &conversion.into;
+        match event {
+            None => event = Some(ty),
+            Some(event) => {
+                let (a, b) = (event.to_token_stream(), ty.to_token_stream());
+                if a.to_string() != b.to_string() {
+                    return Err(syn::Error::new(
+                        ty.span(),
+                        format!(
+                            "all variants must convert into either `Self` or \
+                             the same event type (`{a}`)",
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    event.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "at least one variant must convert into the event type",
+        )
+    })
+}
+
+/// Ensures that a variant converting into `Self` targets a strictly newer
+/// revision of the same name, so conversions can't loop forever.
+fn check_old_revision_target(
+    revisions: &[VariantRevision],
+    revision: &VariantRevision,
+    conversion: &Conversion,
+) -> syn::Result<()> {
+    let has_newer = revisions
+        .iter()
+        .any(|r| r.name == revision.name && r.version > revision.version);
+    if has_newer {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            conversion.span,
+            format!(
+                "`{}` converts into `Self`, which has no revision of \"{}\" \
+                 newer than {}",
+                revision.ident, revision.name, revision.version,
+            ),
+        ))
+    }
+}
+
+/// Generates compile-time assertions that a variant's revision isn't defined
+/// by the event type, and that variants converting into the event type target
+/// a strictly newer revision of the same name.
+fn event_assertions(
+    event: &Type,
+    revision: &VariantRevision,
+    conversion: &Conversion,
+) -> TokenStream {
+    let VariantRevision { ident, name, version } = revision;
+    let event_name = event.to_token_stream().to_string();
+    let revisions = quote! {
+        <#event as ::occur::__private::Revisions>::REVISIONS
+    };
+
+    let conflict_msg = format!(
+        "`{ident}` has the revision (\"{name}\", {version}), which is also \
+         defined by `{event_name}`",
+    );
+    let conflict = quote! {
+        ::std::assert!(
+            !::occur::__private::contains(#revisions, #name, #version),
+            #conflict_msg,
+        );
+    };
+
+    let newer = if conversion.into_self {
+        quote! {}
+    } else {
+        let newer_msg = format!(
+            "`{ident}` converts into `{event_name}`, which has no revision of \
+             \"{name}\" newer than {version}",
+        );
+        quote! {
+            ::std::assert!(
+                ::occur::__private::contains_newer(#revisions, #name, #version),
+                #newer_msg,
+            );
+        }
+    };
+
+    quote! { #conflict #newer }
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "`Convert` cannot be derived for generic enums",
+        ));
+    }
+
+    let data = revision::enum_data(input)?;
+    let revisions = revision::parse(data)?;
+    let conversions = data
+        .variants
+        .iter()
+        .map(|variant| parse_variant(input, variant))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let event = event_type(&conversions)?;
+
+    let mut match_arms = Vec::with_capacity(revisions.len());
+    let mut assertions = Vec::with_capacity(revisions.len());
+    for (revision, conversion) in revisions.iter().zip(&conversions) {
+        let ident = &revision.ident;
+        let with = &conversion.with;
+        match_arms.push(if conversion.into_self {
+            check_old_revision_target(&revisions, revision, conversion)?;
+            quote! {
+                Self::#ident { .. } =>
+                    ::occur::revision::OldOrNew::Old(#with(self))
+            }
+        } else {
+            quote! {
+                Self::#ident { .. } =>
+                    ::occur::revision::OldOrNew::New(#with(self))
+            }
+        });
+        assertions.push(event_assertions(event, revision, conversion));
+    }
+
+    let ident = &input.ident;
+    let revision_impl = revision::impl_revision(input, &revisions);
+
+    Ok(quote! {
+        #revision_impl
+
+        impl ::occur::revision::Convert for #ident {
+            type Event = #event;
+
+            fn convert(self) -> ::occur::revision::OldOrNew<Self::Event> {
+                let (name, version) = ::occur::Revision::revision(&self);
+                let converted = match self {
+                    #(#match_arms,)*
+                };
+                ::std::assert!(
+                    {
+                        let (new_name, new_version) = match &converted {
+                            ::occur::revision::OldOrNew::Old(old) => {
+                                ::occur::Revision::revision(old)
+                            }
+                            ::occur::revision::OldOrNew::New(new) => {
+                                ::occur::Revision::revision(new)
+                            }
+                        };
+                        new_name == name && new_version > version
+                    },
+                    "converting ({name:?}, {version}) did not produce a \
+                     newer revision of the same name",
+                );
+                converted
+            }
+        }
+
+        const _: () = {
+            #(#assertions)*
+        };
+    })
+}
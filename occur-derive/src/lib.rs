@@ -0,0 +1,77 @@
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+//! Derive macros for the `occur` crate.
+//!
+//! Don't depend on this crate directly; use the macros re-exported by `occur`
+//! instead (enabled by its `derive` feature).
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod convert;
+mod revision;
+
+/// Derives `occur::Revision` for an enum.
+///
+/// Each variant is assigned a revision value of `(name, version)`, which can
+/// be set using the `#[revision(name = "...", version = N)]` attribute. When
+/// omitted, `name` defaults to the variant's identifier, and `version`
+/// defaults to 0.
+///
+/// ```ignore
+/// #[derive(occur::Revision)]
+/// enum Event {
+///     Created { name: String },
+///     #[revision(version = 1)]
+///     Renamed { new_name: String },
+/// }
+/// ```
+///
+/// Assigning the same revision value to two variants is a compile error.
+#[proc_macro_derive(Revision, attributes(revision))]
+pub fn derive_revision(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    revision::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `occur::revision::Convert` (and `occur::Revision`) for an enum of
+/// old event revisions.
+///
+/// Revision values are assigned the same way as when deriving `Revision`, so
+/// don't derive both. Each variant must also specify how it's converted using
+/// the `#[convert(into = ..., with = ...)]` attribute:
+///
+/// - `into` is either `Self`, when converting into a newer old revision, or the
+///   event type. All variants must agree on the event type.
+/// - `with` is a path to a function that takes `Self` and returns an instance
+///   of the `into` type.
+///
+/// ```ignore
+/// #[derive(occur::revision::Convert)]
+/// enum OldEvent {
+///     #[revision(name = "Renamed", version = 0)]
+///     #[convert(into = Self, with = renamed_v0)]
+///     Renamed_V0 { name: String },
+///
+///     #[revision(name = "Renamed", version = 1)]
+///     #[convert(into = Event, with = renamed_v1)]
+///     Renamed_V1 { first_name: String, last_name: String },
+/// }
+/// ```
+///
+/// A variant must convert into a strictly newer revision of the same name, so
+/// that conversions can't loop. That a newer revision exists in the `into`
+/// type is checked at compile time, which requires the event type to derive
+/// `Revision` as well. The value returned by the `with` function is checked
+/// when converting, which panics if it's not a newer revision of the same
+/// name. It's also a compile error for a revision value to be defined by both
+/// the event type and the derived enum.
+#[proc_macro_derive(Convert, attributes(revision, convert))]
+pub fn derive_convert(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    convert::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
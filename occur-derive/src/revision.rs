@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DataEnum, DeriveInput, Ident, LitInt, LitStr, Variant};
+
+/// The revision value assigned to a single enum variant.
+pub struct VariantRevision {
+    pub ident: Ident,
+    pub name: String,
+    pub version: u8,
+}
+
+/// Returns the enum data of `input`, or an error if it's not an enum.
+pub fn enum_data(input: &DeriveInput) -> syn::Result<&DataEnum> {
+    match &input.data {
+        Data::Enum(data) => Ok(data),
+        Data::Struct(_) | Data::Union(_) => Err(syn::Error::new(
+            Span::call_site(),
+            "revisions can only be derived for enums",
+        )),
+    }
+}
+
+/// Parses the revision values of all variants of the given enum.
+///
+/// Fails if two variants are assigned the same revision value.
+pub fn parse(data: &DataEnum) -> syn::Result<Vec<VariantRevision>> {
+    let mut revisions = Vec::with_capacity(data.variants.len());
+    let mut used_by: HashMap<(String, u8), &Ident> = HashMap::new();
+    let mut errors: Option<syn::Error> = None;
+
+    for variant in &data.variants {
+        let (revision, span) = parse_variant(variant)?;
+        let key = (revision.name.clone(), revision.version);
+        if let Some(other) = used_by.get(&key) {
+            let error = syn::Error::new(
+                span,
+                format!(
+                    "duplicate revision (\"{}\", {}), already assigned to \
+                     variant `{other}`",
+                    revision.name, revision.version,
+                ),
+            );
+            match &mut errors {
+                Some(errors) => errors.combine(error),
+                None => errors = Some(error),
+            }
+        } else {
+            used_by.insert(key, &variant.ident);
+        }
+        revisions.push(revision);
+    }
+
+    errors.map_or(Ok(revisions), Err)
+}
+
+fn parse_variant(variant: &Variant) -> syn::Result<(VariantRevision, Span)> {
+    let mut name = None;
+    let mut version = None;
+    let mut span = variant.ident.span();
+
+    for attr in variant.attrs.iter().filter(|a| a.path().is_ident("revision")) {
+        span = attr.span();
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("version") {
+                let lit = meta.value()?.parse::<LitInt>()?;
+                version = Some(lit.base10_parse::<u8>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `version`"))
+            }
+        })?;
+    }
+
+    let revision = VariantRevision {
+        ident: variant.ident.clone(),
+        name: name.unwrap_or_else(|| variant.ident.to_string()),
+        version: version.unwrap_or(0),
+    };
+    Ok((revision, span))
+}
+
+/// Generates the `occur::Revision` impl for the given revisions.
+pub fn impl_revision(
+    input: &DeriveInput,
+    revisions: &[VariantRevision],
+) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let match_arms = revisions.iter().map(|r| {
+        let VariantRevision { ident, name, version } = r;
+        quote! { Self::#ident { .. } => (#name, #version) }
+    });
+    let values: Vec<_> = revisions
+        .iter()
+        .map(|r| {
+            let VariantRevision { name, version, .. } = r;
+            quote! { (#name, #version) }
+        })
+        .collect();
+
+    quote! {
+        impl #impl_generics ::occur::Revision for #ident #ty_generics
+        #where_clause
+        {
+            type Value = (&'static str, u8);
+
+            fn revision(&self) -> Self::Value {
+                match self {
+                    #(#match_arms,)*
+                }
+            }
+
+            fn revision_set() -> ::std::collections::HashSet<Self::Value> {
+                ::std::collections::HashSet::from([#(#values),*])
+            }
+        }
+
+        impl #impl_generics ::occur::__private::Revisions
+        for #ident #ty_generics
+        #where_clause
+        {
+            const REVISIONS: &'static [(&'static str, u8)] = &[#(#values),*];
+        }
+    }
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let revisions = parse(enum_data(input)?)?;
+    Ok(impl_revision(input, &revisions))
+}
@@ -0,0 +1,227 @@
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use futures::{future, Stream, StreamExt as _};
+use occur::store::all::{self, AllStream, UnconvertedItem};
+use occur::store::{read, CommitNumber, Deserializer};
+use occur::Event;
+
+use crate::page;
+use crate::read::ReadError;
+use crate::schema::EVENTS_TABLE;
+use crate::store::BoxError;
+use crate::tail::{self, Notifications};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct PostgresAllStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) pool: Pool,
+    pub(super) notifications: Arc<Notifications>,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+    pub(super) _event: PhantomData<T>,
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+/// An event as read from the events table, before it's deserialized.
+struct Row {
+    global_position: i64,
+    stream_id: String,
+    commit_number: i64,
+    payload: Vec<u8>,
+}
+
+impl<T, D> AllStream for PostgresAllStream<T, D>
+where
+    T: Event,
+    T::StreamId: FromStr,
+    <T::StreamId as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted(
+        &mut self,
+        options: all::Options,
+    ) -> ReadResult<impl Stream<Item = ReadResult<UnconvertedItem<T>>>> {
+        // same as stream reads, bounded by the last event at the time the
+        // read started
+        let last_global_position =
+            last_global_position(&self.pool).await.map_err(ReadError::other)?;
+        let last_global_position = last_global_position
+            .map(all::GlobalPosition::try_from)
+            .transpose()
+            .map_err(ReadError::other)?;
+        let Some(range) = options.range(last_global_position) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let pool = self.pool.clone();
+        let direction = options.direction;
+        let rows =
+            page::read(range, direction, self.page_size, move |bounds| {
+                read_page(pool.clone(), bounds, direction)
+            });
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(rows.filter_map(move |row| {
+            future::ready(item(&deserializer, &policy, row))
+        }))
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+impl<T, D> all::Subscribe for PostgresAllStream<T, D>
+where
+    T: Event,
+    T::StreamId: FromStr,
+    <T::StreamId as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    async fn subscribe_unconverted(
+        &mut self,
+        position: all::Position,
+    ) -> ReadResult<impl Stream<Item = ReadResult<UnconvertedItem<T>>> + Send>
+    {
+        let start = match position {
+            all::Position::First => 0,
+            all::Position::Last => last_global_position(&self.pool)
+                .await
+                .map_err(ReadError::other)?
+                .unwrap_or(0),
+            all::Position::GlobalPosition(position) => {
+                i64::try_from(position).unwrap_or(i64::MAX)
+            }
+        };
+        let pool = self.pool.clone();
+        let rows = tail::tail(
+            self.notifications.clone(),
+            None,
+            start,
+            self.page_size,
+            move |cursor, limit| read_tail(pool.clone(), cursor, limit),
+        );
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(rows.filter_map(move |row| {
+            future::ready(item(&deserializer, &policy, row))
+        }))
+    }
+}
+
+/// Deserializes an event read from the events table, unless it's skipped by
+/// the given policy.
+fn item<T, D>(
+    deserializer: &D,
+    policy: &read::UnknownRevisionPolicy,
+    row: ReadResult<Row>,
+) -> Option<ReadResult<UnconvertedItem<T>>>
+where
+    T: Event,
+    T::StreamId: FromStr,
+    <T::StreamId as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    let row = match row {
+        Ok(row) => row,
+        Err(err) => return Some(Err(err)),
+    };
+    let recorded = match policy.deserialize(deserializer, row.payload)? {
+        Ok(recorded) => recorded,
+        Err(err) => return Some(Err(err.into())),
+    };
+    let item = || -> Result<_, BoxError> {
+        Ok(all::Item {
+            stream_id: row.stream_id.parse()?,
+            commit_number: CommitNumber::try_from(row.commit_number)?,
+            global_position: all::GlobalPosition::try_from(
+                row.global_position,
+            )?,
+            event: recorded.map(|recorded| recorded.event),
+        })
+    };
+    Some(item().map_err(ReadError::other))
+}
+
+/// Returns the global position of the last event of the store, or [`None`] if
+/// the store is empty.
+async fn last_global_position(pool: &Pool) -> Result<Option<i64>, BoxError> {
+    let client = pool.get().await?;
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT MAX(global_position) FROM {EVENTS_TABLE}"
+        ))
+        .await?;
+    Ok(client.query_one(&statement, &[]).await?.try_get(0)?)
+}
+
+/// Returns the events within the given (inclusive) bounds of global
+/// positions, ordered by `direction`.
+async fn read_page(
+    pool: Pool,
+    (first, last): (i64, i64),
+    direction: read::Direction,
+) -> Result<Vec<Row>, BoxError> {
+    let client = pool.get().await?;
+    let order = page::order(direction);
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT global_position, stream_id, commit_number, payload \
+             FROM {EVENTS_TABLE} WHERE global_position BETWEEN $1 AND $2 \
+             ORDER BY global_position {order}"
+        ))
+        .await?;
+    let rows = client.query(&statement, &[&first, &last]).await?;
+    rows.iter().map(row).collect()
+}
+
+/// Returns up to `limit` events, starting at the given global position, along
+/// with their global positions.
+async fn read_tail(
+    pool: Pool,
+    global_position: i64,
+    limit: i64,
+) -> Result<Vec<(i64, Row)>, BoxError> {
+    let client = pool.get().await?;
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT global_position, stream_id, commit_number, payload \
+             FROM {EVENTS_TABLE} WHERE global_position >= $1 \
+             ORDER BY global_position ASC LIMIT $2"
+        ))
+        .await?;
+    let rows = client.query(&statement, &[&global_position, &limit]).await?;
+    rows.iter()
+        .map(|it| row(it).map(|row| (row.global_position, row)))
+        .collect()
+}
+
+fn row(row: &tokio_postgres::Row) -> Result<Row, BoxError> {
+    Ok(Row {
+        global_position: row.try_get(0)?,
+        stream_id: row.try_get(1)?,
+        commit_number: row.try_get(2)?,
+        payload: row.try_get(3)?,
+    })
+}
@@ -0,0 +1,31 @@
+//! A [PostgreSQL](https://www.postgresql.org) backed event store.
+//!
+//! All events are kept in a single table (see [`schema`]), in which each event
+//! is also assigned a global position, so that all events of the store can be
+//! read in a single order (see [`occur::store::GlobalStore`]).
+//!
+//! Streams, as well as all events of the store, can be subscribed to. Commits
+//! notify subscribers using `LISTEN`/`NOTIFY`, so subscriptions don't poll the
+//! database while waiting for new events.
+//!
+//! See [`PostgresStore`].
+
+#![feature(error_generic_member_access)]
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+pub use read::ReadError;
+pub use store::{
+    ConnectError,
+    PostgresStore,
+    DEFAULT_IDEMPOTENCY_WINDOW,
+    DEFAULT_READ_PAGE_SIZE,
+};
+pub use write::WriteError;
+
+mod all;
+mod page;
+mod read;
+pub mod schema;
+mod store;
+mod tail;
+mod write;
@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::Range;
+
+use futures::Stream;
+use occur::store::read;
+
+use crate::read::ReadError;
+use crate::store::BoxError;
+
+/// Returns a stream of the events within `range` (see [`read::range`]),
+/// ordered by the given `direction`.
+///
+/// Events are read lazily, in pages of up to `page_size` events, using
+/// `read_page`, which must return the events within the (inclusive) range it
+/// is given, ordered by `direction`. A page that fails to be read ends the
+/// stream with an error.
+pub fn read<R, F, Fut>(
+    range: Range<u64>,
+    direction: read::Direction,
+    page_size: usize,
+    read_page: F,
+) -> impl Stream<Item = Result<R, ReadError>> + Send
+where
+    R: Send,
+    F: Fn((i64, i64)) -> Fut + Send,
+    Fut: Future<Output = Result<Vec<R>, BoxError>> + Send,
+{
+    let page_size = i64::try_from(page_size.max(1)).unwrap_or(i64::MAX);
+    let range = bounds(range);
+    let state = (read_page, Some(range), VecDeque::new());
+    // boxed, so that reads can be consumed without pinning them first
+    Box::pin(futures::stream::unfold(
+        state,
+        move |(read_page, mut range, mut page)| async move {
+            while page.is_empty() {
+                let (first, last) =
+                    range.filter(|(first, last)| first <= last)?;
+                let (bounds, rest) = match direction {
+                    read::Direction::Forward => {
+                        let page_last =
+                            first.saturating_add(page_size - 1).min(last);
+                        ((first, page_last), (page_last + 1, last))
+                    }
+                    read::Direction::Backward => {
+                        let page_first =
+                            last.saturating_sub(page_size - 1).max(first);
+                        ((page_first, last), (first, page_first - 1))
+                    }
+                };
+                range = Some(rest);
+                match read_page(bounds).await {
+                    Ok(events) => page.extend(events),
+                    Err(err) => {
+                        let err = Err(ReadError::other(err));
+                        return Some((err, (read_page, None, page)));
+                    }
+                }
+            }
+            let event = page.pop_front()?;
+            Some((Ok(event), (read_page, range, page)))
+        },
+    ))
+}
+
+/// Returns the (inclusive) bounds of `range`, as they're bound to statements,
+/// which also allows expressing an empty range.
+fn bounds(range: Range<u64>) -> (i64, i64) {
+    let bound = |position| i64::try_from(position).unwrap_or(i64::MAX);
+    (bound(range.start), bound(range.end) - 1)
+}
+
+/// Returns the SQL sort order that reads events in the given `direction`.
+pub const fn order(direction: read::Direction) -> &'static str {
+    match direction {
+        read::Direction::Forward => "ASC",
+        read::Direction::Backward => "DESC",
+    }
+}
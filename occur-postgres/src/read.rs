@@ -0,0 +1,264 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use futures::{future, Stream, StreamExt as _};
+use occur::envelope::Recorded;
+use occur::store::read::{self, MaybeUnknown, Subscribed};
+use occur::store::{
+    serialization,
+    CommitNumber,
+    Deserializer,
+    ReadStream,
+    Subscribe,
+};
+use occur::{revision, ErrorWithKind, Event};
+
+use crate::page;
+use crate::schema::EVENTS_TABLE;
+use crate::store::BoxError;
+use crate::tail::{self, Notifications};
+
+pub struct PostgresReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) pool: Pool,
+    pub(super) notifications: Arc<Notifications>,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct ReadError {
+    kind: read::ErrorKind,
+    source: Option<BoxError>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl ErrorWithKind for ReadError {
+    type Kind = read::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl read::Error for ReadError {}
+
+impl ReadError {
+    #[must_use]
+    pub fn new(kind: read::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    pub fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(read::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for ReadError {
+    fn from(source: serialization::Error) -> Self {
+        let kind = match source.kind() {
+            serialization::ErrorKind::Format => read::ErrorKind::Serialization,
+            serialization::ErrorKind::UnknownRevision => {
+                read::ErrorKind::UnknownRevision
+            }
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+impl<T, D> ReadStream for PostgresReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted_recorded(
+        &mut self,
+        options: read::Options,
+    ) -> ReadResult<
+        impl Stream<
+            Item = ReadResult<MaybeUnknown<Recorded<revision::OldOrNew<T>>>>,
+        >,
+    > {
+        // reads are bounded by the last event at the time the read started,
+        // so that events committed while the read is consumed aren't read
+        let last_commit_number = last_commit_number(&self.pool, &self.id)
+            .await
+            .map_err(ReadError::other)?;
+        let Some(range) = options.range(last_commit_number) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let (pool, id) = (self.pool.clone(), self.id.clone());
+        let direction = options.direction;
+        let events =
+            page::read(range, direction, self.page_size, move |bounds| {
+                read_page(pool.clone(), id.clone(), bounds, direction)
+            });
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(events.filter_map(move |event| {
+            let recorded = match event {
+                Ok(event) => policy
+                    .deserialize(&deserializer, event)
+                    .map(|recorded| recorded.map_err(ReadError::from)),
+                Err(err) => Some(Err(err)),
+            };
+            future::ready(recorded)
+        }))
+    }
+
+    fn unknown_revision_policy(&self) -> &read::UnknownRevisionPolicy {
+        &self.unknown_revision_policy
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+impl<T, D> Subscribe for PostgresReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    async fn subscribe_unconverted(
+        &mut self,
+        position: read::Position,
+    ) -> ReadResult<
+        impl futures::Stream<
+                Item = ReadResult<
+                    Subscribed<MaybeUnknown<revision::OldOrNew<T>>>,
+                >,
+            > + Send,
+    > {
+        let start = match position {
+            read::Position::First => 0,
+            read::Position::Last => last_commit_number(&self.pool, &self.id)
+                .await
+                .map_err(ReadError::other)?
+                .map_or(0, i64::from),
+            read::Position::CommitNumber(commit_number) => {
+                i64::from(commit_number)
+            }
+        };
+        let (pool, id) = (self.pool.clone(), self.id.clone());
+        let events = tail::tail(
+            self.notifications.clone(),
+            Some(self.id.clone()),
+            start,
+            self.page_size,
+            move |cursor, limit| {
+                read_tail(pool.clone(), id.clone(), cursor, limit)
+            },
+        );
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(events.filter_map(move |event| {
+            let subscribed = match event {
+                Ok((commit_number, event)) => {
+                    policy.deserialize(&deserializer, event).map(|recorded| {
+                        Ok((
+                            commit_number,
+                            recorded?.map(|recorded| recorded.event),
+                        ))
+                    })
+                }
+                Err(err) => Some(Err(err)),
+            };
+            future::ready(subscribed)
+        }))
+    }
+}
+
+/// Returns the commit number of the last event of a stream, or [`None`] if the
+/// stream is empty.
+pub async fn last_commit_number(
+    pool: &Pool,
+    stream_id: &str,
+) -> Result<Option<CommitNumber>, BoxError> {
+    let client = pool.get().await?;
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT MAX(commit_number) FROM {EVENTS_TABLE} WHERE stream_id = $1"
+        ))
+        .await?;
+    let last_commit_number: Option<i64> =
+        client.query_one(&statement, &[&stream_id]).await?.try_get(0)?;
+    Ok(last_commit_number.map(CommitNumber::try_from).transpose()?)
+}
+
+/// Returns the serialized events of a stream within the given (inclusive)
+/// bounds of commit numbers, ordered by `direction`.
+async fn read_page(
+    pool: Pool,
+    stream_id: String,
+    (first, last): (i64, i64),
+    direction: read::Direction,
+) -> Result<Vec<Vec<u8>>, BoxError> {
+    let client = pool.get().await?;
+    let order = page::order(direction);
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT payload FROM {EVENTS_TABLE} WHERE stream_id = $1 \
+             AND commit_number BETWEEN $2 AND $3 \
+             ORDER BY commit_number {order}"
+        ))
+        .await?;
+    let rows = client.query(&statement, &[&stream_id, &first, &last]).await?;
+    Ok(rows.iter().map(|row| row.try_get(0)).collect::<Result<_, _>>()?)
+}
+
+/// Returns up to `limit` serialized events of a stream, starting at the given
+/// commit number, along with their commit numbers.
+async fn read_tail(
+    pool: Pool,
+    stream_id: String,
+    commit_number: i64,
+    limit: i64,
+) -> Result<Vec<(i64, (CommitNumber, Vec<u8>))>, BoxError> {
+    let client = pool.get().await?;
+    let statement = client
+        .prepare_cached(&format!(
+            "SELECT commit_number, payload FROM {EVENTS_TABLE} \
+             WHERE stream_id = $1 AND commit_number >= $2 \
+             ORDER BY commit_number ASC LIMIT $3"
+        ))
+        .await?;
+    let rows =
+        client.query(&statement, &[&stream_id, &commit_number, &limit]).await?;
+    rows.iter()
+        .map(|row| {
+            let commit_number: i64 = row.try_get(0)?;
+            let payload = row.try_get(1)?;
+            Ok((commit_number, (commit_number.try_into()?, payload)))
+        })
+        .collect()
+}
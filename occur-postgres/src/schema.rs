@@ -0,0 +1,78 @@
+//! The tables in which a [`PostgresStore`](crate::PostgresStore) keeps events.
+//!
+//! ```sql
+//! CREATE TABLE events (
+//!     global_position BIGINT PRIMARY KEY,
+//!     stream_id       TEXT   NOT NULL,
+//!     commit_number   BIGINT NOT NULL,
+//!     event_id        UUID   NOT NULL,
+//!     payload         BYTEA  NOT NULL,
+//!     UNIQUE (stream_id, commit_number)
+//! );
+//!
+//! CREATE TABLE global_positions (
+//!     id   BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+//!     next BIGINT  NOT NULL
+//! );
+//! ```
+//!
+//! `payload` holds the event as serialized by the store's serializer (along
+//! with its metadata and timestamp), and is all that's needed to read the
+//! event back. The ID of the event is also kept in its own column, so that
+//! commits can be made idempotent without deserializing events.
+//!
+//! Global positions are taken from the single row of `global_positions`
+//! rather than from a sequence. A commit holds the lock of that row from the
+//! moment it takes its positions until it ends, so commits are assigned
+//! consecutive positions in the order in which they become visible, and a
+//! commit that's rolled back gives its positions back. Readers therefore never
+//! skip a gap that an in-flight commit fills later, and aren't blocked by
+//! commits. Commits only take turns once they take their positions, which
+//! is their last step before inserting events; reading the tail of their
+//! stream and checking their condition happen concurrently.
+
+use tokio_postgres::Client;
+
+/// The name of the table holding the events of all streams.
+pub const EVENTS_TABLE: &str = "events";
+
+/// The name of the table holding the next global position to be assigned.
+pub const GLOBAL_POSITIONS_TABLE: &str = "global_positions";
+
+/// The channel on which commits are notified, with the ID of the stream that
+/// was committed to as payload.
+pub const CHANNEL: &str = "occur_events";
+
+/// Creates the tables, unless they already exist.
+///
+/// Called when a [`PostgresStore`](crate::PostgresStore) connects, so there's
+/// usually no need to call it directly.
+///
+/// # Errors
+///
+/// When a statement fails to execute.
+pub async fn create(client: &Client) -> Result<(), tokio_postgres::Error> {
+    // concurrent `CREATE TABLE IF NOT EXISTS` statements might still conflict
+    // with one another, so stores that connect at once take turns
+    client
+        .batch_execute(&format!(
+            "BEGIN;
+            SELECT pg_advisory_xact_lock(hashtext('{EVENTS_TABLE}'));
+            CREATE TABLE IF NOT EXISTS {EVENTS_TABLE} (
+                global_position BIGINT PRIMARY KEY,
+                stream_id TEXT NOT NULL,
+                commit_number BIGINT NOT NULL,
+                event_id UUID NOT NULL,
+                payload BYTEA NOT NULL,
+                UNIQUE (stream_id, commit_number)
+            );
+            CREATE TABLE IF NOT EXISTS {GLOBAL_POSITIONS_TABLE} (
+                id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+                next BIGINT NOT NULL
+            );
+            INSERT INTO {GLOBAL_POSITIONS_TABLE} (next) VALUES (0)
+                ON CONFLICT DO NOTHING;
+            COMMIT;"
+        ))
+        .await
+}
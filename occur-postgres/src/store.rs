@@ -0,0 +1,241 @@
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use deadpool_postgres::{BuildError, Manager, Pool};
+use occur::store::clock::{Clock, SystemClock};
+use occur::store::read::UnknownRevisionPolicy;
+use occur::store::serialization::Serialization;
+use occur::store::{Deserializer, GlobalStore, Serializer};
+use occur::{Event, Store};
+use tokio_postgres::{Config, NoTls};
+
+use crate::all::PostgresAllStream;
+use crate::read::PostgresReadStream;
+use crate::schema::{self, CHANNEL};
+use crate::tail::{self, Notifications};
+use crate::write::PostgresWriteStream;
+
+/// The default number of recently committed event IDs that are checked when
+/// committing to a stream.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: usize = 64;
+
+/// The default number of events that are fetched at once by a read.
+pub const DEFAULT_READ_PAGE_SIZE: usize = 256;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An error that might occur when a [`PostgresStore`] connects.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+    /// Connecting, creating the events table, or listening for commits
+    /// failed.
+    #[error(transparent)]
+    Postgres(#[from] tokio_postgres::Error),
+
+    /// The connection pool failed to be built.
+    #[error(transparent)]
+    Pool(#[from] BuildError),
+}
+
+/// An event store that holds events in a PostgreSQL database.
+///
+/// The store creates its table when it connects (see [`schema`]). It keeps a
+/// pool of connections, which is shared by all clones of the store, and an
+/// additional connection that listens for commits on behalf of all
+/// subscriptions. Connections aren't encrypted.
+///
+/// Commits take their global positions last, from a single row that stays
+/// locked until they end, so that positions have no gaps and events become
+/// visible in their global order (see [`schema`]). Commit conditions are
+/// checked before positions are taken; a commit that loses a race for a
+/// commit number fails on the table's unique constraint, after which its
+/// condition is checked again.
+///
+/// Stream IDs are stored as text, using their [`Display`] implementation,
+/// which must therefore uniquely identify a stream. Reading all events of the
+/// store also requires parsing them back (see [`FromStr`]).
+///
+/// Commits are idempotent within a window of the last events of the stream
+/// (see [`Self::with_idempotency_window`]).
+#[allow(clippy::module_name_repetitions)]
+pub struct PostgresStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pool: Pool,
+    notifications: Arc<Notifications>,
+    serializer: S,
+    deserializer: D,
+    clock: Arc<dyn Clock>,
+    idempotency_window: usize,
+    read_page_size: usize,
+    unknown_revision_policy: UnknownRevisionPolicy,
+    _event: PhantomData<T>,
+}
+
+impl<T, S, D> PostgresStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    /// Connects to the database of the given configuration, creating the
+    /// events table unless it already exists, and starts listening for
+    /// commits.
+    ///
+    /// Must be called within a Tokio runtime, which drives the store's
+    /// connections.
+    ///
+    /// # Errors
+    ///
+    /// When connecting or executing a statement fails.
+    pub async fn connect(
+        config: Config,
+        serialization: Serialization<S, D>,
+    ) -> Result<Self, ConnectError> {
+        let (client, connection) = config.connect(NoTls).await?;
+        let receiver = tail::forward_notifications(connection);
+        schema::create(&client).await?;
+        client.batch_execute(&format!("LISTEN {CHANNEL}")).await?;
+        let pool = Pool::builder(Manager::new(config, NoTls)).build()?;
+        let Serialization { serializer, deserializer } = serialization;
+        Ok(Self {
+            pool,
+            notifications: Arc::new(Notifications::new(client, receiver)),
+            serializer,
+            deserializer,
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            read_page_size: DEFAULT_READ_PAGE_SIZE,
+            unknown_revision_policy: UnknownRevisionPolicy::default(),
+            _event: PhantomData,
+        })
+    }
+
+    /// Replaces the clock used to timestamp committed events (which is
+    /// [`SystemClock`] by default).
+    #[must_use]
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Sets the number of last events of a stream whose IDs are checked when
+    /// committing to it, for the purpose of making commits idempotent (which
+    /// is [`DEFAULT_IDEMPOTENCY_WINDOW`] by default).
+    ///
+    /// Re-committing an event that is older than the window commits it again.
+    /// A window of 0 disables idempotency altogether.
+    #[must_use]
+    pub fn with_idempotency_window(self, idempotency_window: usize) -> Self {
+        Self { idempotency_window, ..self }
+    }
+
+    /// Sets the maximum number of events that are fetched at once by a read
+    /// or a subscription (which is [`DEFAULT_READ_PAGE_SIZE`] by default).
+    #[must_use]
+    pub fn with_read_page_size(self, read_page_size: usize) -> Self {
+        Self { read_page_size, ..self }
+    }
+
+    /// Sets how read streams opened by the store handle events that were
+    /// committed with an unknown revision (which is
+    /// [`UnknownRevisionPolicy::Fail`] by default).
+    #[must_use]
+    pub fn with_unknown_revision_policy(
+        self,
+        unknown_revision_policy: UnknownRevisionPolicy,
+    ) -> Self {
+        Self { unknown_revision_policy, ..self }
+    }
+}
+
+impl<T, S, D> Clone for PostgresStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            notifications: self.notifications.clone(),
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            read_page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<T, S, D> Store for PostgresStore<T, S, D>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone + Send + Sync,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type WriteStream = PostgresWriteStream<T, S>;
+    type ReadStream = PostgresReadStream<T, D>;
+
+    fn write_stream(&self, id: T::StreamId) -> Self::WriteStream {
+        PostgresWriteStream {
+            id: id.to_string(),
+            pool: self.pool.clone(),
+            serializer: self.serializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            _event: PhantomData,
+        }
+    }
+
+    fn read_stream(&self, id: T::StreamId) -> Self::ReadStream {
+        PostgresReadStream {
+            id: id.to_string(),
+            pool: self.pool.clone(),
+            notifications: self.notifications.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<T, S, D> GlobalStore for PostgresStore<T, S, D>
+where
+    T: Event,
+    T::StreamId: Display + FromStr,
+    <T::StreamId as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone + Send + Sync,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type AllStream = PostgresAllStream<T, D>;
+
+    fn all_stream(&self) -> Self::AllStream {
+        PostgresAllStream {
+            pool: self.pool.clone(),
+            notifications: self.notifications.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt as _};
+use tokio::sync::broadcast;
+use tokio_postgres::{AsyncMessage, Client, Connection, Socket};
+
+use crate::read::ReadError;
+use crate::store::BoxError;
+
+/// The number of notifications that are buffered for each subscription.
+///
+/// A subscription that falls further behind reads its stream again, as if it
+/// was notified of a commit to it.
+const CAPACITY: usize = 1024;
+
+/// Commit notifications, as received by the connection that listens for them
+/// on behalf of a store.
+///
+/// Each notification is the ID of the stream that was committed to.
+pub struct Notifications {
+    /// Keeps the listening connection open for as long as the store (or any
+    /// of its subscriptions) is alive.
+    _client: Client,
+    receiver: broadcast::Receiver<Arc<str>>,
+}
+
+impl Notifications {
+    pub const fn new(
+        client: Client,
+        receiver: broadcast::Receiver<Arc<str>>,
+    ) -> Self {
+        Self { _client: client, receiver }
+    }
+
+    /// Returns a receiver of all notifications received from now on.
+    fn subscribe(&self) -> broadcast::Receiver<Arc<str>> {
+        self.receiver.resubscribe()
+    }
+}
+
+/// Spawns a task that drives the given connection, and forwards the
+/// notifications it receives to the returned receiver.
+///
+/// Once the connection is closed (or fails), the task ends, and all receivers
+/// are closed.
+pub fn forward_notifications<T>(
+    mut connection: Connection<Socket, T>,
+) -> broadcast::Receiver<Arc<str>>
+where
+    T: tokio_postgres::tls::TlsStream + Unpin + Send + 'static,
+{
+    let (sender, receiver) = broadcast::channel(CAPACITY);
+    let mut messages =
+        futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+    tokio::spawn(async move {
+        while let Some(Ok(message)) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message {
+                // there might be no subscriptions
+                let _ = sender.send(notification.payload().into());
+            }
+        }
+    });
+    receiver
+}
+
+/// Returns a never-ending stream of events, starting at position `start`, and
+/// then of every event committed afterwards.
+///
+/// Events are read in pages of up to `page_size` events, using `read_page`,
+/// which must return the events at or after the position it's given (along
+/// with their positions), ordered by position. When there are no new events,
+/// the stream waits to be notified of a commit, to the stream with the given
+/// ID, or to any stream if `stream_id` is [`None`].
+///
+/// A page that fails to be read, or losing the connection that listens for
+/// commits, ends the stream with an error.
+pub fn tail<R, F, Fut>(
+    notifications: Arc<Notifications>,
+    stream_id: Option<String>,
+    start: i64,
+    page_size: usize,
+    read_page: F,
+) -> impl Stream<Item = Result<R, ReadError>> + Send
+where
+    R: Send,
+    F: Fn(i64, i64) -> Fut + Send,
+    Fut: Future<Output = Result<Vec<(i64, R)>, BoxError>> + Send,
+{
+    let page_size = i64::try_from(page_size.max(1)).unwrap_or(i64::MAX);
+    let state = Tail {
+        read_page,
+        // subscribed to before the first page is read, so that commits which
+        // happen after a page is read aren't missed
+        receiver: Some(notifications.subscribe()),
+        _notifications: notifications,
+        cursor: start,
+        page: VecDeque::new(),
+    };
+    Box::pin(futures::stream::unfold(state, move |mut state| {
+        let stream_id = stream_id.clone();
+        async move {
+            while state.page.is_empty() {
+                // the stream ends after an error
+                state.receiver.as_ref()?;
+                let events =
+                    match (state.read_page)(state.cursor, page_size).await {
+                        Ok(events) => events,
+                        Err(err) => return Some(state.fail(err)),
+                    };
+                if let Some((position, _)) = events.last() {
+                    state.cursor = position + 1;
+                    state
+                        .page
+                        .extend(events.into_iter().map(|(_, event)| event));
+                    break;
+                }
+                let receiver = state.receiver.as_mut()?;
+                if let Err(err) =
+                    committed(receiver, stream_id.as_deref()).await
+                {
+                    return Some(state.fail(err));
+                }
+            }
+            let event = state.page.pop_front()?;
+            Some((Ok(event), state))
+        }
+    }))
+}
+
+/// The state of a [`tail`] stream.
+struct Tail<F, R> {
+    read_page: F,
+    /// Receives commit notifications, until the stream fails.
+    receiver: Option<broadcast::Receiver<Arc<str>>>,
+    /// Keeps the listening connection open for as long as the stream is
+    /// alive.
+    _notifications: Arc<Notifications>,
+    /// The position of the next event to read.
+    cursor: i64,
+    page: VecDeque<R>,
+}
+
+impl<F, R> Tail<F, R> {
+    /// Yields the given error, ending the stream.
+    fn fail(mut self, err: BoxError) -> (Result<R, ReadError>, Self) {
+        self.receiver = None;
+        (Err(ReadError::other(err)), self)
+    }
+}
+
+/// Waits to be notified of a commit to the stream with the given ID (or to
+/// any stream, if `stream_id` is [`None`]).
+///
+/// Also returns when notifications might have been missed (as the
+/// subscription fell behind), in which case the stream should be read again
+/// anyway.
+async fn committed(
+    receiver: &mut broadcast::Receiver<Arc<str>>,
+    stream_id: Option<&str>,
+) -> Result<(), BoxError> {
+    loop {
+        match receiver.recv().await {
+            Ok(committed) => {
+                if stream_id.is_none_or(|stream_id| *committed == *stream_id) {
+                    return Ok(());
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => return Ok(()),
+            Err(broadcast::error::RecvError::Closed) => {
+                return Err(
+                    "the connection listening for commits was closed".into()
+                );
+            }
+        }
+    }
+}
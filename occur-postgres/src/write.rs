@@ -0,0 +1,318 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use deadpool_postgres::{Pool, Transaction};
+use occur::envelope::{Envelope, EventId, Recorded};
+use occur::store::clock::Clock;
+use occur::store::{
+    serialization,
+    write,
+    CommitNumber,
+    Serializer,
+    WriteStream,
+};
+use occur::{revision, ErrorWithKind, Event};
+use tokio_postgres::error::SqlState;
+
+use crate::schema::{CHANNEL, EVENTS_TABLE, GLOBAL_POSITIONS_TABLE};
+use crate::store::BoxError;
+
+pub struct PostgresWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) pool: Pool,
+    pub(super) serializer: S,
+    pub(super) clock: Arc<dyn Clock>,
+    pub(super) idempotency_window: usize,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct WriteError {
+    kind: write::ErrorKind,
+    source: Option<BoxError>,
+    last_commit_number: Option<CommitNumber>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl WriteError {
+    fn new(kind: write::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            last_commit_number: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(write::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for WriteError {
+    fn from(source: serialization::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Serialization)
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for WriteError {
+    fn from(source: tokio_postgres::Error) -> Self {
+        // only possible if another commit took the commit number first
+        let kind = if source.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+            write::ErrorKind::ConditionNotMet
+        } else {
+            write::ErrorKind::Other
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+impl From<write::CheckError> for WriteError {
+    fn from(err: write::CheckError) -> Self {
+        match err {
+            write::CheckError::StreamFull => {
+                Self::new(write::ErrorKind::StreamFull)
+            }
+            write::CheckError::ConditionNotMet { last_commit_number } => Self {
+                last_commit_number,
+                ..Self::new(write::ErrorKind::ConditionNotMet)
+            },
+        }
+    }
+}
+
+impl ErrorWithKind for WriteError {
+    type Kind = write::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl write::Error for WriteError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        self.last_commit_number
+    }
+}
+
+type CommitResult<T> = Result<T, WriteError>;
+
+impl<T, S> WriteStream for PostgresWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    type Event = T;
+    type Error = WriteError;
+
+    async fn commit_old_or_new_envelope(
+        &mut self,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
+        condition: write::Condition,
+    ) -> CommitResult<CommitNumber> {
+        let commit_number = self.append(vec![envelope], condition).await?;
+        Ok(commit_number.expect("a single event was committed"))
+    }
+
+    fn commit_many_envelopes<'a>(
+        &mut self,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
+        condition: write::Condition,
+    ) -> impl Future<Output = CommitResult<Option<CommitNumber>>> + Send {
+        let envelopes: Vec<_> = envelopes
+            .into_iter()
+            .map(|envelope| envelope.map(revision::OldOrNewRef::New))
+            .collect();
+        self.append(envelopes, condition)
+    }
+}
+
+impl<T, S> PostgresWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    /// Timestamps, serializes and appends the given events to the stream,
+    /// given the provided condition holds.
+    ///
+    /// All events are appended within a single transaction, which also
+    /// notifies subscribers of the commit.
+    async fn append(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        if envelopes.is_empty() {
+            return Ok(None);
+        }
+        let ids: Vec<_> =
+            envelopes.iter().map(|envelope| envelope.metadata.id).collect();
+        if write::has_duplicate_ids(&ids) {
+            return Err(WriteError::new(write::ErrorKind::DuplicateEventId));
+        }
+        let mut client = self.pool.get().await.map_err(WriteError::other)?;
+        loop {
+            let transaction = client.transaction().await?;
+            // the last event is always fetched, as the next commit number
+            // follows it
+            let tail =
+                tail(&transaction, &self.id, self.idempotency_window.max(1))
+                    .await
+                    .map_err(WriteError::other)?;
+            let window = &tail[..self.idempotency_window.min(tail.len())];
+            match write::lookup_in_window(window, &ids) {
+                write::Lookup::New => {}
+                write::Lookup::Committed(commit_number) => {
+                    return Ok(Some(commit_number));
+                }
+                write::Lookup::Partial => {
+                    return Err(WriteError::new(
+                        write::ErrorKind::PartiallyCommitted,
+                    ));
+                }
+            }
+            let last_commit_number = tail.first().map(|(number, _)| *number);
+            let commit_number =
+                condition.check(last_commit_number, ids.len())?;
+            // taking positions locks their row until the transaction ends,
+            // so that commits are assigned consecutive positions in the order
+            // in which they become visible (see `crate::schema`)
+            let global_position: i64 = transaction
+                .query_one(
+                    &format!(
+                        "UPDATE {GLOBAL_POSITIONS_TABLE} SET next = next + $1 \
+                         RETURNING next - $1"
+                    ),
+                    &[&i64::try_from(ids.len()).map_err(WriteError::other)?],
+                )
+                .await?
+                .try_get(0)?;
+            // timestamps are taken while the row of positions is locked, so
+            // that they're ordered the same way as global positions (and thus
+            // as commit numbers)
+            let serialized_events =
+                self.serialize(&envelopes, self.clock.now())?;
+            let inserted = insert(
+                &transaction,
+                &self.id,
+                global_position,
+                commit_number,
+                &ids,
+                &serialized_events,
+            )
+            .await;
+            match inserted {
+                Ok(()) => {}
+                Err(err) if err.code() == Some(&SqlState::UNIQUE_VIOLATION) => {
+                    // another commit took the commit number first, so the
+                    // condition is checked again against the new last event
+                    // of the stream
+                    transaction.rollback().await?;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            }
+            transaction
+                .execute("SELECT pg_notify($1, $2)", &[&CHANNEL, &self.id])
+                .await?;
+            transaction.commit().await?;
+            return Ok(Some(commit_number));
+        }
+    }
+
+    /// Serializes the given events, recording them with the given timestamp.
+    fn serialize(
+        &self,
+        envelopes: &[Envelope<revision::OldOrNewRef<'_, T>>],
+        timestamp: SystemTime,
+    ) -> CommitResult<Vec<Vec<u8>>> {
+        envelopes
+            .iter()
+            .map(|Envelope { event, metadata }| {
+                self.serializer
+                    .serialize(Recorded {
+                        event: event.clone(),
+                        metadata: metadata.clone(),
+                        timestamp,
+                    })
+                    .map_err(WriteError::from)
+            })
+            .collect()
+    }
+}
+
+/// Returns the commit numbers and IDs of the last `limit` events of a stream
+/// (or less, if it holds less events), from last to first.
+async fn tail(
+    transaction: &Transaction<'_>,
+    stream_id: &str,
+    limit: usize,
+) -> Result<Vec<(CommitNumber, EventId)>, BoxError> {
+    let statement = transaction
+        .prepare_cached(&format!(
+            "SELECT commit_number, event_id FROM {EVENTS_TABLE} \
+             WHERE stream_id = $1 ORDER BY commit_number DESC LIMIT $2"
+        ))
+        .await?;
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = transaction.query(&statement, &[&stream_id, &limit]).await?;
+    rows.iter()
+        .map(|row| {
+            let commit_number: i64 = row.try_get(0)?;
+            Ok((
+                CommitNumber::try_from(commit_number)?,
+                EventId(row.try_get(1)?),
+            ))
+        })
+        .collect()
+}
+
+/// Inserts serialized events at consecutive global positions and commit
+/// numbers, starting at the given ones.
+///
+/// Fails with [`SqlState::UNIQUE_VIOLATION`] if any of the commit numbers is
+/// already taken.
+async fn insert(
+    transaction: &Transaction<'_>,
+    stream_id: &str,
+    global_position: i64,
+    commit_number: CommitNumber,
+    ids: &[EventId],
+    serialized_events: &[Vec<u8>],
+) -> Result<(), tokio_postgres::Error> {
+    let statement = transaction
+        .prepare_cached(&format!(
+            "INSERT INTO {EVENTS_TABLE} \
+             (global_position, stream_id, commit_number, event_id, payload) \
+             SELECT global_position, $1, commit_number, event_id, payload \
+             FROM UNNEST($2::bigint[], $3::bigint[], $4::uuid[], $5::bytea[]) \
+             AS e (global_position, commit_number, event_id, payload)"
+        ))
+        .await?;
+    let global_positions: Vec<_> =
+        (global_position..).take(ids.len()).collect();
+    let commit_numbers: Vec<_> =
+        (i64::from(commit_number)..).take(ids.len()).collect();
+    let ids: Vec<_> = ids.iter().map(|id| id.0).collect();
+    transaction
+        .execute(&statement, &[
+            &stream_id,
+            &global_positions,
+            &commit_numbers,
+            &ids,
+            &serialized_events,
+        ])
+        .await?;
+    Ok(())
+}
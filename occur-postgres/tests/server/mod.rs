@@ -0,0 +1,120 @@
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tempfile::TempDir;
+use tokio_postgres::{Client, Config, NoTls};
+use uuid::Uuid;
+
+/// A PostgreSQL server that is shared by all tests of a test binary.
+struct Server {
+    config: Config,
+    /// The data directory of the server, if it was spawned by the tests.
+    _dir: Option<TempDir>,
+    /// Stops the spawned server once the test binary exits (see [`spawn`]).
+    _watchdog: Option<Child>,
+}
+
+/// Returns the configuration of a new, empty database.
+///
+/// The database is created on the server at `POSTGRES_URL` when it's set, or
+/// otherwise on a server that is spawned using the local `initdb` and
+/// `postgres` binaries (which refuse to run as root).
+///
+/// Tests that need a server are ignored by default, and are run using
+/// `cargo test -- --ignored`.
+///
+/// # Panics
+///
+/// When no server is available.
+pub async fn database() -> Config {
+    static SERVER: OnceLock<Result<Server, String>> = OnceLock::new();
+    let server = match SERVER.get_or_init(server) {
+        Ok(server) => server,
+        Err(err) => panic!("no PostgreSQL server is available: {err}"),
+    };
+    let mut config = server.config.clone();
+    let client = connect(&config).await;
+    let dbname = format!("test_{}", Uuid::now_v7().simple());
+    client.batch_execute(&format!("CREATE DATABASE {dbname}")).await.unwrap();
+    config.dbname(&dbname);
+    config
+}
+
+/// Connects to the given server, retrying while it starts up.
+async fn connect(config: &Config) -> Client {
+    let mut attempts = 0;
+    loop {
+        match config.connect(NoTls).await {
+            Ok((client, connection)) => {
+                tokio::spawn(connection);
+                return client;
+            }
+            Err(_) if attempts < 50 => attempts += 1,
+            Err(err) => panic!("failed to connect: {err}"),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+fn server() -> Result<Server, String> {
+    if let Ok(url) = std::env::var("POSTGRES_URL") {
+        let config = Config::from_str(&url).map_err(|err| err.to_string())?;
+        return Ok(Server { config, _dir: None, _watchdog: None });
+    }
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let watchdog = spawn(dir.path())?;
+    let mut config = Config::new();
+    config.host_path(dir.path()).port(PORT).user("postgres").dbname("postgres");
+    Ok(Server { config, _dir: Some(dir), _watchdog: Some(watchdog) })
+}
+
+/// The port of a spawned server, which only names its Unix socket (as the
+/// server doesn't listen on TCP).
+const PORT: u16 = 5432;
+
+/// Initializes a data directory within `dir`, and spawns a server that
+/// listens on a Unix socket within it.
+///
+/// The server is spawned by a shell that stops it once its stdin is closed,
+/// which happens when the test binary exits (as statics are never dropped).
+fn spawn(dir: &Path) -> Result<Child, String> {
+    let data = dir.join("data");
+    let initdb = Command::new("initdb")
+        .args(["--username=postgres", "--auth=trust", "--no-sync", "-D"])
+        .arg(&data)
+        .output()
+        .map_err(|err| format!("initdb: {err}"))?;
+    if !initdb.status.success() {
+        return Err(String::from_utf8_lossy(&initdb.stderr).into_owned());
+    }
+    let watchdog = Command::new("sh")
+        .args([
+            "-c",
+            r#"postgres -D "$1" -k "$2" -p "$3" -h '' -F & cat > /dev/null; kill $!"#,
+            "sh",
+        ])
+        .arg(&data)
+        .arg(dir)
+        .arg(PORT.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("postgres: {err}"))?;
+    let socket = dir.join(format!(".s.PGSQL.{PORT}"));
+    wait_for(&socket)?;
+    Ok(watchdog)
+}
+
+fn wait_for(socket: &Path) -> Result<(), String> {
+    for _ in 0..100 {
+        if socket.exists() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Err("postgres didn't start in time".to_owned())
+}
@@ -0,0 +1,61 @@
+use futures::{future, TryStreamExt as _};
+use occur::store::serialization::cbor::{
+    cbor_serialization,
+    CborDeserializer,
+    CborSerializer,
+};
+use occur::store::{
+    AllStream as _,
+    GlobalStore as _,
+    Store as _,
+    WriteStream as _,
+};
+use occur_conformance::{deposited, new_stream_id, Event};
+use occur_postgres::PostgresStore;
+
+mod server;
+
+type Store =
+    PostgresStore<Event, CborSerializer<Event>, CborDeserializer<Event>>;
+
+/// Returns a store in a new database.
+async fn store() -> Store {
+    PostgresStore::connect(server::database().await, cbor_serialization())
+        .await
+        .unwrap()
+        .with_read_page_size(occur_conformance::READ_PAGE_SIZE)
+        .with_idempotency_window(occur_conformance::IDEMPOTENCY_WINDOW)
+}
+
+async fn global_positions(store: &Store) -> Vec<u64> {
+    let mut all_stream = store.all_stream();
+    let items = all_stream.read_all().await.unwrap();
+    items.map_ok(|item| item.global_position).try_collect().await.unwrap()
+}
+
+occur_conformance::tests!(
+    #[tokio::test]
+    #[ignore = "requires a PostgreSQL server"]
+    async store().await,
+    [write, read, idempotency, envelope, all, subscribe]
+);
+
+#[tokio::test]
+#[ignore = "requires a PostgreSQL server"]
+async fn retried_commits_leave_no_gaps() {
+    let store = store().await;
+    let id = new_stream_id();
+    let mut streams: Vec<_> = (0..8).map(|_| store.write_stream(id)).collect();
+
+    // commits race for the same commit numbers, and the losers are retried
+    let events: Vec<_> = (0..8).map(deposited).collect();
+    let commits = streams
+        .iter_mut()
+        .zip(&events)
+        .map(|(stream, event)| stream.commit_unconditionally(event));
+    for result in future::join_all(commits).await {
+        result.unwrap();
+    }
+
+    assert_eq!(global_positions(&store).await, (0..8).collect::<Vec<_>>());
+}
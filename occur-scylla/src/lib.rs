@@ -0,0 +1,24 @@
+//! A [ScyllaDB](https://www.scylladb.com) backed event store.
+//!
+//! Each event stream is stored in its own partition, with events clustered by
+//! their commit number. Commits are lightweight transactions (LWT), so commit
+//! conditions are enforced by the database, even when many processes commit to
+//! the same stream.
+//!
+//! See [`ScyllaStore`].
+
+#![feature(error_generic_member_access)]
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+pub use read::ReadError;
+pub use store::{
+    ScyllaStore,
+    DEFAULT_IDEMPOTENCY_WINDOW,
+    DEFAULT_READ_PAGE_SIZE,
+};
+pub use write::WriteError;
+
+mod read;
+pub mod schema;
+mod store;
+mod write;
@@ -1,11 +1,134 @@
+//! Manages the schema of a keyspace in which events are kept.
+//!
+//! ```text
+//! occur-scylla [OPTIONS] <COMMAND>
+//!
+//! Commands:
+//!   migrate  Creates the keyspace, and applies pending migrations to it
+//!   status   Lists applied and pending migrations
+//!
+//! Options:
+//!   --node <ADDRESS>                A node to connect to, which can be given
+//!                                   many times [default: 127.0.0.1:9042]
+//!   --keyspace <NAME>               [default: occur]
+//!   --replication-factor <N>        Replicates the keyspace using
+//!                                   SimpleStrategy [default: 1]
+//!   --datacenter <NAME>:<N>         Replicates the keyspace to N nodes of the
+//!                                   datacenter, using NetworkTopologyStrategy
+//!                                   (can be given many times)
+//! ```
+
+use anyhow::{bail, Context as _};
+use occur_scylla::schema::{Replication, Schema};
+
+enum Command {
+    Migrate,
+    Status,
+}
+
+struct Args {
+    command: Command,
+    nodes: Vec<String>,
+    keyspace: String,
+    replication: Replication,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut command = None;
+    let mut nodes = Vec::new();
+    let mut keyspace = "occur".to_owned();
+    let mut replication_factor = None;
+    let mut replication_factors = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value =
+            || args.next().with_context(|| format!("{arg} expects a value"));
+        match arg.as_str() {
+            "migrate" => command = Some(Command::Migrate),
+            "status" => command = Some(Command::Status),
+            "--node" => nodes.push(value()?),
+            "--keyspace" => keyspace = value()?,
+            "--replication-factor" => {
+                replication_factor = Some(value()?.parse()?);
+            }
+            "--datacenter" => {
+                let value = value()?;
+                let (datacenter, factor) = value
+                    .rsplit_once(':')
+                    .context("--datacenter expects <NAME>:<N>")?;
+                replication_factors
+                    .push((datacenter.to_owned(), factor.parse()?));
+            }
+            _ => bail!("unexpected argument: {arg}"),
+        }
+    }
+    let replication = match (replication_factor, replication_factors.is_empty())
+    {
+        (None, true) => Replication::default(),
+        (Some(replication_factor), true) => {
+            Replication::Simple { replication_factor }
+        }
+        (None, false) => Replication::NetworkTopology { replication_factors },
+        (Some(_), false) => {
+            bail!(
+                "--replication-factor and --datacenter are mutually exclusive"
+            )
+        }
+    };
+    if nodes.is_empty() {
+        nodes.push("127.0.0.1:9042".to_owned());
+    }
+    Ok(Args {
+        command: command.context("expected a command: migrate or status")?,
+        nodes,
+        keyspace,
+        replication,
+    })
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     color_backtrace::install();
 
-    let _ = scylla::SessionBuilder::new()
-        .known_node("127.0.0.1:9042")
-        .build()
-        .await?;
+    let args = parse_args(std::env::args().skip(1))?;
+    let schema = Schema::new(args.keyspace)?.with_replication(args.replication);
+    let session =
+        scylla::SessionBuilder::new().known_nodes(&args.nodes).build().await?;
+
+    match args.command {
+        Command::Migrate => {
+            let applied = schema.ensure(&session).await?;
+            if applied.is_empty() {
+                println!("{} is up to date", schema.keyspace());
+            }
+            for migration in applied {
+                println!(
+                    "applied {}: {}",
+                    migration.version, migration.description,
+                );
+            }
+        }
+        Command::Status => {
+            let status = schema.status(&session).await?;
+            for migration in status.applied {
+                let applied_at = migration
+                    .applied_at
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                println!(
+                    "applied {}: {} (at {}s since the epoch)",
+                    migration.version,
+                    migration.description,
+                    applied_at.as_secs(),
+                );
+            }
+            for migration in status.pending {
+                println!(
+                    "pending {}: {}",
+                    migration.version, migration.description,
+                );
+            }
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,145 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use futures::{future, Stream, StreamExt as _};
+use occur::envelope::Recorded;
+use occur::store::read::MaybeUnknown;
+use occur::store::{read, serialization, Deserializer, ReadStream};
+use occur::{revision, ErrorWithKind, Event};
+use scylla::Session;
+
+use crate::store::{tail, BoxError, Statements};
+
+pub struct ScyllaReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) session: Arc<Session>,
+    pub(super) statements: Arc<Statements>,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct ReadError {
+    kind: read::ErrorKind,
+    source: Option<BoxError>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl ErrorWithKind for ReadError {
+    type Kind = read::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl read::Error for ReadError {}
+
+impl ReadError {
+    fn new(kind: read::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(read::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for ReadError {
+    fn from(source: serialization::Error) -> Self {
+        let kind = match source.kind() {
+            serialization::ErrorKind::Format => read::ErrorKind::Serialization,
+            serialization::ErrorKind::UnknownRevision => {
+                read::ErrorKind::UnknownRevision
+            }
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+impl<T, D> ReadStream for ScyllaReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted_recorded(
+        &mut self,
+        options: read::Options,
+    ) -> ReadResult<
+        impl Stream<
+            Item = ReadResult<MaybeUnknown<Recorded<revision::OldOrNew<T>>>>,
+        >,
+    > {
+        // reads are bounded by the last event at the time the read started,
+        // so that events committed while the read is consumed aren't read
+        let last_commit_number =
+            tail(&self.session, &self.statements, &self.id, 1)
+                .await
+                .map_err(ReadError::other)?
+                .first()
+                .map(|(commit_number, _)| *commit_number);
+        let Some(range) = options.range(last_commit_number) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        // bounds are bound to statements as they are (inclusive), which also
+        // allows expressing an empty range
+        let bound = |position| i64::try_from(position).unwrap_or(i64::MAX);
+        let (first, last) = (bound(range.start), bound(range.end) - 1);
+        let mut statement = match options.direction {
+            read::Direction::Forward => self.statements.read_forward.clone(),
+            read::Direction::Backward => self.statements.read_backward.clone(),
+        };
+        statement.set_page_size(
+            i32::try_from(self.page_size.max(1)).unwrap_or(i32::MAX),
+        );
+        let rows = self
+            .session
+            .execute_iter(statement, (self.id.as_str(), first, last))
+            .await
+            .map_err(ReadError::other)?
+            .into_typed::<(Vec<u8>,)>();
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(rows.filter_map(move |row| {
+            let recorded = match row {
+                Ok((event,)) => policy
+                    .deserialize(&deserializer, event)
+                    .map(|recorded| recorded.map_err(ReadError::from)),
+                Err(err) => Some(Err(ReadError::other(err))),
+            };
+            future::ready(recorded)
+        }))
+    }
+
+    fn unknown_revision_policy(&self) -> &read::UnknownRevisionPolicy {
+        &self.unknown_revision_policy
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
@@ -0,0 +1,332 @@
+//! The keyspace and tables in which a [`ScyllaStore`](crate::ScyllaStore)
+//! keeps events.
+//!
+//! The tables are defined by versioned [`MIGRATIONS`], which are applied in
+//! order by [`Schema::ensure`]. Every applied migration is recorded in the
+//! keyspace (in the [`MIGRATIONS_TABLE`] table), so ensuring the schema is
+//! idempotent, and is meant to be done whenever a service starts.
+//!
+//! Migrations are never changed once released; changes to the tables are made
+//! by appending new migrations. All statements of a migration must be
+//! idempotent themselves (e.g., `CREATE TABLE IF NOT EXISTS`), as many
+//! processes might ensure the schema at once.
+
+use std::time::{Duration, SystemTime};
+
+use scylla::frame::value::CqlTimestamp;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+
+use crate::store::BoxError;
+
+/// The name of the table holding the events of all streams.
+pub const EVENTS_TABLE: &str = "events";
+
+/// The name of the table recording the migrations applied to a keyspace.
+pub const MIGRATIONS_TABLE: &str = "schema_migrations";
+
+/// The version of a [`Migration`].
+pub type Version = u32;
+
+/// A versioned change to the tables of a keyspace.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Migration {
+    /// Orders the migration among all [`MIGRATIONS`].
+    pub version: Version,
+
+    /// Describes the migration to humans.
+    pub description: &'static str,
+
+    /// The CQL statements of the migration, in which `{keyspace}` stands for
+    /// the name of the keyspace the migration is applied to.
+    pub statements: &'static [&'static str],
+}
+
+/// Stands for the name of the keyspace in the statements of a [`Migration`].
+const KEYSPACE: &str = "{keyspace}";
+
+/// All migrations, ordered by version.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "create the events table",
+    // a partition per stream, so that a commit only involves a single
+    // partition (as lightweight transactions require)
+    statements: &["CREATE TABLE IF NOT EXISTS {keyspace}.events (
+        stream_id text,
+        commit_number bigint,
+        event_id uuid,
+        event blob,
+        PRIMARY KEY (stream_id, commit_number)
+    ) WITH CLUSTERING ORDER BY (commit_number ASC)"],
+}];
+
+/// An error that might occur when managing the schema of a keyspace.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The keyspace name isn't a valid CQL identifier.
+    #[error("invalid keyspace name: {0:?}")]
+    InvalidKeyspace(String),
+
+    /// A statement failed to execute.
+    #[error(transparent)]
+    Query(#[from] QueryError),
+
+    /// A statement returned an unexpected result (e.g., when the migrations
+    /// table was modified by hand).
+    #[error("unexpected result")]
+    UnexpectedResult(#[source] BoxError),
+}
+
+/// How a keyspace is replicated across the cluster.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Replication {
+    /// Replicates the keyspace to the given number of nodes, regardless of
+    /// their datacenter.
+    ///
+    /// Only suitable for development, or for single datacenter clusters.
+    Simple { replication_factor: u32 },
+
+    /// Replicates the keyspace to the given number of nodes in each of the
+    /// given datacenters.
+    NetworkTopology { replication_factors: Vec<(String, u32)> },
+}
+
+impl Default for Replication {
+    /// Replicates the keyspace to a single node.
+    fn default() -> Self { Self::Simple { replication_factor: 1 } }
+}
+
+impl Replication {
+    /// Returns the replication map of the keyspace, as CQL.
+    fn to_cql(&self) -> String {
+        let options = match self {
+            Self::Simple { replication_factor } => vec![
+                ("class".to_owned(), quote("SimpleStrategy")),
+                (
+                    "replication_factor".to_owned(),
+                    replication_factor.to_string(),
+                ),
+            ],
+            Self::NetworkTopology { replication_factors } => {
+                let class =
+                    ("class".to_owned(), quote("NetworkTopologyStrategy"));
+                let factors =
+                    replication_factors.iter().map(|(datacenter, factor)| {
+                        (datacenter.clone(), factor.to_string())
+                    });
+                std::iter::once(class).chain(factors).collect()
+            }
+        };
+        let options: Vec<_> = options
+            .into_iter()
+            .map(|(key, value)| format!("{}: {value}", quote(&key)))
+            .collect();
+        format!("{{{}}}", options.join(", "))
+    }
+}
+
+/// Quotes a CQL string literal.
+fn quote(s: &str) -> String { format!("'{}'", s.replace('\'', "''")) }
+
+/// A migration that was applied to a keyspace.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AppliedMigration {
+    pub version: Version,
+    pub description: String,
+    pub applied_at: SystemTime,
+}
+
+/// The migrations that were and weren't yet applied to a keyspace.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Status {
+    /// Applied migrations, ordered by version.
+    ///
+    /// Might include migrations that aren't in [`MIGRATIONS`], when they were
+    /// applied by a newer version of this crate.
+    pub applied: Vec<AppliedMigration>,
+
+    /// Migrations that weren't applied yet, ordered by version.
+    pub pending: Vec<&'static Migration>,
+}
+
+/// The schema of a keyspace in which a [`ScyllaStore`](crate::ScyllaStore)
+/// keeps events.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Schema {
+    keyspace: String,
+    replication: Replication,
+}
+
+impl Schema {
+    /// Creates the schema of the given keyspace, which is replicated to a
+    /// single node by default (see [`Self::with_replication`]).
+    ///
+    /// # Errors
+    ///
+    /// When the keyspace name isn't a valid CQL identifier: up to 48
+    /// alphanumeric characters or underscores, starting with a letter.
+    pub fn new(keyspace: impl Into<String>) -> Result<Self, Error> {
+        let keyspace = keyspace.into();
+        let mut chars = keyspace.chars();
+        let is_valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && keyspace.len() <= 48;
+        if !is_valid {
+            return Err(Error::InvalidKeyspace(keyspace));
+        }
+        Ok(Self { keyspace, replication: Replication::default() })
+    }
+
+    /// Sets how the keyspace is replicated when it's created.
+    ///
+    /// The replication of an existing keyspace is never altered.
+    #[must_use]
+    pub fn with_replication(self, replication: Replication) -> Self {
+        Self { replication, ..self }
+    }
+
+    /// Returns the name of the keyspace.
+    #[must_use]
+    pub fn keyspace(&self) -> &str { &self.keyspace }
+
+    /// Creates the keyspace unless it already exists, and applies all pending
+    /// migrations to it.
+    ///
+    /// Returns the applied migrations, which are none when the schema is
+    /// already up to date.
+    ///
+    /// # Errors
+    ///
+    /// When a statement fails to execute, in which case the migrations that
+    /// were applied before it remain applied.
+    pub async fn ensure(
+        &self,
+        session: &Session,
+    ) -> Result<Vec<&'static Migration>, Error> {
+        let keyspace = &self.keyspace;
+        session
+            .query(
+                format!(
+                    "CREATE KEYSPACE IF NOT EXISTS {keyspace} \
+                     WITH replication = {}",
+                    self.replication.to_cql(),
+                ),
+                (),
+            )
+            .await?;
+        session
+            .query(
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {keyspace}.{MIGRATIONS_TABLE} (
+                        version int PRIMARY KEY,
+                        description text,
+                        applied_at timestamp
+                    )"
+                ),
+                (),
+            )
+            .await?;
+        session.await_schema_agreement().await?;
+        let pending = self.status(session).await?.pending;
+        for migration in &pending {
+            self.apply(session, migration).await?;
+        }
+        Ok(pending)
+    }
+
+    /// Returns the migrations that were and weren't yet applied to the
+    /// keyspace (which are all of them, if the keyspace doesn't exist).
+    ///
+    /// # Errors
+    ///
+    /// When a statement fails to execute.
+    pub async fn status(&self, session: &Session) -> Result<Status, Error> {
+        let applied = if self.is_migrated(session).await? {
+            self.applied(session).await?
+        } else {
+            Vec::new()
+        };
+        let pending = MIGRATIONS
+            .iter()
+            .filter(|migration| {
+                !applied.iter().any(|it| it.version == migration.version)
+            })
+            .collect();
+        Ok(Status { applied, pending })
+    }
+
+    /// Returns whether the keyspace holds the migrations table.
+    async fn is_migrated(&self, session: &Session) -> Result<bool, Error> {
+        let tables = session
+            .query(
+                "SELECT table_name FROM system_schema.tables \
+                 WHERE keyspace_name = ? AND table_name = ?",
+                (&self.keyspace, MIGRATIONS_TABLE),
+            )
+            .await?
+            .rows_num()
+            .map_err(|err| Error::UnexpectedResult(err.into()))?;
+        Ok(tables > 0)
+    }
+
+    async fn applied(
+        &self,
+        session: &Session,
+    ) -> Result<Vec<AppliedMigration>, Error> {
+        let keyspace = &self.keyspace;
+        let rows = session
+            .query(
+                format!(
+                    "SELECT version, description, applied_at \
+                     FROM {keyspace}.{MIGRATIONS_TABLE}"
+                ),
+                (),
+            )
+            .await?
+            .rows_typed::<(i32, String, CqlTimestamp)>()
+            .map_err(|err| Error::UnexpectedResult(err.into()))?;
+        let mut applied = rows
+            .map(|row| {
+                let (version, description, CqlTimestamp(applied_at)) = row?;
+                Ok(AppliedMigration {
+                    version: Version::try_from(version)?,
+                    description,
+                    applied_at: SystemTime::UNIX_EPOCH
+                        + Duration::from_millis(u64::try_from(applied_at)?),
+                })
+            })
+            .collect::<Result<Vec<_>, BoxError>>()
+            .map_err(Error::UnexpectedResult)?;
+        applied.sort_by_key(|migration| migration.version);
+        Ok(applied)
+    }
+
+    async fn apply(
+        &self,
+        session: &Session,
+        migration: &Migration,
+    ) -> Result<(), Error> {
+        for statement in migration.statements {
+            session
+                .query(statement.replace(KEYSPACE, &self.keyspace), ())
+                .await?;
+        }
+        session.await_schema_agreement().await?;
+        // only recorded once all statements were applied, so that a failed
+        // migration is applied again (from its start) next time
+        let keyspace = &self.keyspace;
+        let version = i32::try_from(migration.version)
+            .map_err(|err| Error::UnexpectedResult(err.into()))?;
+        session
+            .query(
+                format!(
+                    "INSERT INTO {keyspace}.{MIGRATIONS_TABLE} \
+                     (version, description, applied_at) \
+                     VALUES (?, ?, toTimestamp(now()))"
+                ),
+                (version, migration.description),
+            )
+            .await?;
+        Ok(())
+    }
+}
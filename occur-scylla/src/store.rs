@@ -0,0 +1,266 @@
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use occur::envelope::EventId;
+use occur::store::clock::{Clock, SystemClock};
+use occur::store::read::UnknownRevisionPolicy;
+use occur::store::serialization::Serialization;
+use occur::store::{CommitNumber, Deserializer, Serializer};
+use occur::{Event, Store};
+use scylla::prepared_statement::PreparedStatement;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use uuid::Uuid;
+
+use crate::read::ScyllaReadStream;
+use crate::schema::EVENTS_TABLE;
+use crate::write::ScyllaWriteStream;
+
+/// The default number of recently committed event IDs that are checked when
+/// committing to a stream.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: usize = 64;
+
+/// The default number of events that are fetched at once by a read.
+pub const DEFAULT_READ_PAGE_SIZE: usize = 256;
+
+/// The statements used by a [`ScyllaStore`], prepared once when it's created.
+pub struct Statements {
+    /// Inserts a single event, unless its commit number is already taken.
+    pub(super) insert: PreparedStatement,
+    /// Selects the commit numbers and IDs of the last events of a stream.
+    pub(super) tail: PreparedStatement,
+    /// Selects the events of a stream within a range of commit numbers, from
+    /// first to last.
+    pub(super) read_forward: PreparedStatement,
+    /// Selects the events of a stream within a range of commit numbers, from
+    /// last to first.
+    pub(super) read_backward: PreparedStatement,
+}
+
+impl Statements {
+    async fn prepare(
+        session: &Session,
+        keyspace: &str,
+    ) -> Result<Self, QueryError> {
+        let table = format!("{keyspace}.{EVENTS_TABLE}");
+        let read = |order: &str| {
+            format!(
+                "SELECT event FROM {table} WHERE stream_id = ? AND \
+                 commit_number >= ? AND commit_number <= ? \
+                 ORDER BY commit_number {order}"
+            )
+        };
+        Ok(Self {
+            insert: session
+                .prepare(format!(
+                    "INSERT INTO {table} \
+                     (stream_id, commit_number, event_id, event) \
+                     VALUES (?, ?, ?, ?) IF NOT EXISTS"
+                ))
+                .await?,
+            tail: session
+                .prepare(format!(
+                    "SELECT commit_number, event_id FROM {table} \
+                     WHERE stream_id = ? ORDER BY commit_number DESC LIMIT ?"
+                ))
+                .await?,
+            read_forward: session.prepare(read("ASC")).await?,
+            read_backward: session.prepare(read("DESC")).await?,
+        })
+    }
+}
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Returns the commit numbers and IDs of the last `limit` events of a stream
+/// (or less, if it holds less events), from last to first.
+pub async fn tail(
+    session: &Session,
+    statements: &Statements,
+    stream_id: &str,
+    limit: usize,
+) -> Result<Vec<(CommitNumber, EventId)>, BoxError> {
+    let limit = i32::try_from(limit).unwrap_or(i32::MAX);
+    session
+        .execute(&statements.tail, (stream_id, limit))
+        .await?
+        .rows_typed::<(i64, Uuid)>()?
+        .map(|row| {
+            let (commit_number, id) = row?;
+            Ok((CommitNumber::try_from(commit_number)?, EventId(id)))
+        })
+        .collect()
+}
+
+/// An event store that holds events in a ScyllaDB keyspace.
+///
+/// The keyspace must already hold the store's tables (see
+/// [`crate::schema`]). Cloning the store is cheap, and all clones share the
+/// same session.
+///
+/// Stream IDs are stored as text, using their [`Display`] implementation,
+/// which must therefore uniquely identify a stream. Events are stored as the
+/// bytes produced by the store's serializer.
+///
+/// # Commits
+///
+/// Each commit is a lightweight transaction that inserts events at the
+/// commit numbers following the last event of the stream, unless they're
+/// already taken. When another commit takes them first, the stream is read
+/// again and the commit condition is re-checked, so that conditions (e.g.,
+/// [`Condition::AssignCommitNumber`]) hold even when many processes commit to
+/// the same stream.
+///
+/// Commits are idempotent within a window of the last events of the stream
+/// (see [`Self::with_idempotency_window`]).
+///
+/// [`Condition::AssignCommitNumber`]: occur::store::write::Condition::AssignCommitNumber
+#[allow(clippy::module_name_repetitions)]
+pub struct ScyllaStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    session: Arc<Session>,
+    statements: Arc<Statements>,
+    serializer: S,
+    deserializer: D,
+    clock: Arc<dyn Clock>,
+    idempotency_window: usize,
+    read_page_size: usize,
+    unknown_revision_policy: UnknownRevisionPolicy,
+    _event: PhantomData<T>,
+}
+
+impl<T, S, D> ScyllaStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    /// Creates a store that holds events in the given keyspace, preparing the
+    /// statements it uses.
+    ///
+    /// # Errors
+    ///
+    /// When a statement fails to be prepared (e.g., when the keyspace doesn't
+    /// hold the store's tables).
+    pub async fn new(
+        session: Arc<Session>,
+        keyspace: &str,
+        serialization: Serialization<S, D>,
+    ) -> Result<Self, QueryError> {
+        let statements = Statements::prepare(&session, keyspace).await?;
+        let Serialization { serializer, deserializer } = serialization;
+        Ok(Self {
+            session,
+            statements: Arc::new(statements),
+            serializer,
+            deserializer,
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            read_page_size: DEFAULT_READ_PAGE_SIZE,
+            unknown_revision_policy: UnknownRevisionPolicy::default(),
+            _event: PhantomData,
+        })
+    }
+
+    /// Replaces the clock used to timestamp committed events (which is
+    /// [`SystemClock`] by default).
+    #[must_use]
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Sets the number of last events of a stream whose IDs are checked when
+    /// committing to it, for the purpose of making commits idempotent (which
+    /// is [`DEFAULT_IDEMPOTENCY_WINDOW`] by default).
+    ///
+    /// Re-committing an event that is older than the window commits it again.
+    /// A window of 0 disables idempotency altogether.
+    #[must_use]
+    pub fn with_idempotency_window(self, idempotency_window: usize) -> Self {
+        Self { idempotency_window, ..self }
+    }
+
+    /// Sets the maximum number of events that are fetched at once by a read
+    /// (which is [`DEFAULT_READ_PAGE_SIZE`] by default).
+    #[must_use]
+    pub fn with_read_page_size(self, read_page_size: usize) -> Self {
+        Self { read_page_size, ..self }
+    }
+
+    /// Sets how read streams opened by the store handle events that were
+    /// committed with an unknown revision (which is
+    /// [`UnknownRevisionPolicy::Fail`] by default).
+    #[must_use]
+    pub fn with_unknown_revision_policy(
+        self,
+        unknown_revision_policy: UnknownRevisionPolicy,
+    ) -> Self {
+        Self { unknown_revision_policy, ..self }
+    }
+}
+
+impl<T, S, D> Clone for ScyllaStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            session: self.session.clone(),
+            statements: self.statements.clone(),
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            read_page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<T, S, D> Store for ScyllaStore<T, S, D>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone + Send + Sync,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type WriteStream = ScyllaWriteStream<T, S>;
+    type ReadStream = ScyllaReadStream<T, D>;
+
+    fn write_stream(&self, id: T::StreamId) -> Self::WriteStream {
+        ScyllaWriteStream {
+            id: id.to_string(),
+            session: self.session.clone(),
+            statements: self.statements.clone(),
+            serializer: self.serializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            _event: PhantomData,
+        }
+    }
+
+    fn read_stream(&self, id: T::StreamId) -> Self::ReadStream {
+        ScyllaReadStream {
+            id: id.to_string(),
+            session: self.session.clone(),
+            statements: self.statements.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use occur::envelope::{Envelope, EventId, Recorded};
+use occur::store::clock::Clock;
+use occur::store::{
+    serialization,
+    write,
+    CommitNumber,
+    Serializer,
+    WriteStream,
+};
+use occur::{revision, ErrorWithKind, Event};
+use scylla::batch::{Batch, BatchType};
+use scylla::frame::response::result::CqlValue;
+use scylla::{QueryResult, Session};
+
+use crate::store::{tail, BoxError, Statements};
+
+pub struct ScyllaWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) session: Arc<Session>,
+    pub(super) statements: Arc<Statements>,
+    pub(super) serializer: S,
+    pub(super) clock: Arc<dyn Clock>,
+    pub(super) idempotency_window: usize,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct WriteError {
+    kind: write::ErrorKind,
+    source: Option<BoxError>,
+    last_commit_number: Option<CommitNumber>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl WriteError {
+    fn new(kind: write::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            last_commit_number: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(write::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for WriteError {
+    fn from(source: serialization::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Serialization)
+        }
+    }
+}
+
+impl From<write::CheckError> for WriteError {
+    fn from(err: write::CheckError) -> Self {
+        match err {
+            write::CheckError::StreamFull => {
+                Self::new(write::ErrorKind::StreamFull)
+            }
+            write::CheckError::ConditionNotMet { last_commit_number } => Self {
+                last_commit_number,
+                ..Self::new(write::ErrorKind::ConditionNotMet)
+            },
+        }
+    }
+}
+
+impl ErrorWithKind for WriteError {
+    type Kind = write::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl write::Error for WriteError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        self.last_commit_number
+    }
+}
+
+type CommitResult<T> = Result<T, WriteError>;
+
+impl<T, S> WriteStream for ScyllaWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    type Event = T;
+    type Error = WriteError;
+
+    async fn commit_old_or_new_envelope(
+        &mut self,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
+        condition: write::Condition,
+    ) -> CommitResult<CommitNumber> {
+        let commit_number = self.append(vec![envelope], condition).await?;
+        Ok(commit_number.expect("a single event was committed"))
+    }
+
+    fn commit_many_envelopes<'a>(
+        &mut self,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
+        condition: write::Condition,
+    ) -> impl Future<Output = CommitResult<Option<CommitNumber>>> + Send {
+        let envelopes: Vec<_> = envelopes
+            .into_iter()
+            .map(|envelope| envelope.map(revision::OldOrNewRef::New))
+            .collect();
+        self.append(envelopes, condition)
+    }
+}
+
+impl<T, S> ScyllaWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    /// Timestamps, serializes and appends the given events to the stream,
+    /// given the provided condition holds.
+    async fn append(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        if envelopes.is_empty() {
+            return Ok(None);
+        }
+        let ids: Vec<_> =
+            envelopes.iter().map(|envelope| envelope.metadata.id).collect();
+        if write::has_duplicate_ids(&ids) {
+            return Err(WriteError::new(write::ErrorKind::DuplicateEventId));
+        }
+        let serialized_events = self.serialize(envelopes)?;
+        loop {
+            // the last event is always fetched, as the next commit number
+            // follows it
+            let tail = tail(
+                &self.session,
+                &self.statements,
+                &self.id,
+                self.idempotency_window.max(1),
+            )
+            .await
+            .map_err(WriteError::other)?;
+            let window = &tail[..self.idempotency_window.min(tail.len())];
+            match write::lookup_in_window(window, &ids) {
+                write::Lookup::New => {}
+                write::Lookup::Committed(commit_number) => {
+                    return Ok(Some(commit_number));
+                }
+                write::Lookup::Partial => {
+                    return Err(WriteError::new(
+                        write::ErrorKind::PartiallyCommitted,
+                    ));
+                }
+            }
+            let last_commit_number = tail.first().map(|(number, _)| *number);
+            let commit_number =
+                condition.check(last_commit_number, ids.len())?;
+            if self.insert(commit_number, &ids, &serialized_events).await? {
+                return Ok(Some(commit_number));
+            }
+            // another commit took the commit number first, so the condition
+            // is checked again against the new last event of the stream
+        }
+    }
+
+    /// Serializes the given events, recording them with the current time.
+    fn serialize(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+    ) -> CommitResult<Vec<Vec<u8>>> {
+        let timestamp = self.clock.now();
+        envelopes
+            .into_iter()
+            .map(|Envelope { event, metadata }| {
+                self.serializer
+                    .serialize(Recorded { event, metadata, timestamp })
+                    .map_err(WriteError::from)
+            })
+            .collect()
+    }
+
+    /// Inserts serialized events at consecutive commit numbers, starting at
+    /// `commit_number`, unless any of them is already taken.
+    ///
+    /// Returns whether the events were inserted.
+    async fn insert(
+        &self,
+        commit_number: CommitNumber,
+        ids: &[EventId],
+        serialized_events: &[Vec<u8>],
+    ) -> CommitResult<bool> {
+        let rows: Vec<_> = ids
+            .iter()
+            .zip(serialized_events)
+            .zip(commit_number..)
+            .map(|((id, event), commit_number)| {
+                (self.id.as_str(), i64::from(commit_number), id.0, event)
+            })
+            .collect();
+        let result = if let [row] = rows.as_slice() {
+            self.session.execute(&self.statements.insert, row).await
+        } else {
+            // a conditional batch is applied as a whole, as all of its
+            // statements belong to the stream's partition
+            let mut batch = Batch::new(BatchType::Logged);
+            for _ in &rows {
+                batch.append_statement(self.statements.insert.clone());
+            }
+            self.session.batch(&batch, rows).await
+        };
+        is_applied(result.map_err(WriteError::other)?)
+    }
+}
+
+/// Returns whether a conditional statement was applied, as reported by the
+/// `[applied]` column of its result.
+fn is_applied(result: QueryResult) -> CommitResult<bool> {
+    let row = result.first_row().map_err(WriteError::other)?;
+    match row.columns.first() {
+        Some(Some(CqlValue::Boolean(applied))) => Ok(*applied),
+        _ => Err(WriteError::other("missing [applied] column")),
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use scylla::Session;
+
+/// Connects to the Scylla node at `SCYLLA_URI`.
+///
+/// Tests that need a node are ignored by default, and are run using
+/// `SCYLLA_URI=127.0.0.1:9042 cargo test -- --ignored`.
+///
+/// # Panics
+///
+/// When `SCYLLA_URI` isn't set, or no node is available at it.
+pub async fn session() -> Session {
+    let uri = std::env::var("SCYLLA_URI")
+        .expect("SCYLLA_URI should be set to the address of a Scylla node");
+    scylla::SessionBuilder::new()
+        .known_node(&uri)
+        .connection_timeout(Duration::from_secs(1))
+        .build()
+        .await
+        .unwrap_or_else(|err| {
+            panic!("no Scylla node is available at {uri}: {err}")
+        })
+}
@@ -0,0 +1,66 @@
+use occur_scylla::schema::{self, Schema, MIGRATIONS};
+use scylla::Session;
+use uuid::Uuid;
+
+mod node;
+
+/// Returns the schema of a keyspace that no other test uses.
+fn new_schema() -> Schema {
+    Schema::new(format!("occur_test_{}", Uuid::now_v7().simple())).unwrap()
+}
+
+async fn drop_keyspace(session: &Session, schema: &Schema) {
+    session
+        .query(format!("DROP KEYSPACE IF EXISTS {}", schema.keyspace()), ())
+        .await
+        .unwrap();
+}
+
+#[test]
+fn invalid_keyspaces_are_rejected() {
+    for keyspace in ["", "1events", "events; DROP", "x".repeat(49).as_str()] {
+        let err = Schema::new(keyspace).unwrap_err();
+        assert!(matches!(err, schema::Error::InvalidKeyspace(_)));
+    }
+}
+
+#[test]
+fn migrations_are_ordered() {
+    let versions: Vec<_> =
+        MIGRATIONS.iter().map(|migration| migration.version).collect();
+    let expected: Vec<_> = (1..).take(MIGRATIONS.len()).collect();
+    assert_eq!(versions, expected);
+}
+
+#[tokio::test]
+#[ignore = "requires a Scylla node at SCYLLA_URI"]
+async fn status_of_missing_keyspace() {
+    let session = node::session().await;
+    let schema = new_schema();
+
+    let status = schema.status(&session).await.unwrap();
+
+    assert!(status.applied.is_empty());
+    assert_eq!(status.pending.len(), MIGRATIONS.len());
+}
+
+#[tokio::test]
+#[ignore = "requires a Scylla node at SCYLLA_URI"]
+async fn ensure_is_idempotent() {
+    let session = node::session().await;
+    let schema = new_schema();
+
+    let applied = schema.ensure(&session).await.unwrap();
+    let applied_again = schema.ensure(&session).await.unwrap();
+    let status = schema.status(&session).await.unwrap();
+    drop_keyspace(&session, &schema).await;
+
+    assert_eq!(applied.len(), MIGRATIONS.len());
+    assert!(applied_again.is_empty());
+    let versions: Vec<_> =
+        status.applied.iter().map(|migration| migration.version).collect();
+    let expected: Vec<_> =
+        MIGRATIONS.iter().map(|migration| migration.version).collect();
+    assert_eq!(versions, expected);
+    assert!(status.pending.is_empty());
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use occur::store::serialization::cbor::{
+    cbor_serialization,
+    CborDeserializer,
+    CborSerializer,
+};
+use occur_conformance::Event;
+use occur_scylla::schema::Schema;
+use occur_scylla::ScyllaStore;
+
+mod node;
+
+const KEYSPACE: &str = "occur_test";
+
+type Store = ScyllaStore<Event, CborSerializer<Event>, CborDeserializer<Event>>;
+
+/// Returns a store in the test keyspace.
+async fn store() -> Store {
+    let session = node::session().await;
+    let schema = Schema::new(KEYSPACE).unwrap();
+    schema.ensure(&session).await.unwrap();
+    ScyllaStore::new(
+        Arc::new(session),
+        schema.keyspace(),
+        cbor_serialization::<Event>(),
+    )
+    .await
+    .unwrap()
+    .with_read_page_size(occur_conformance::READ_PAGE_SIZE)
+    .with_idempotency_window(occur_conformance::IDEMPOTENCY_WINDOW)
+}
+
+occur_conformance::tests!(
+    #[tokio::test]
+    #[ignore = "requires a Scylla node at SCYLLA_URI"]
+    async store().await,
+    [write, read, idempotency, envelope]
+);
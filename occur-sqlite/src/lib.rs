@@ -0,0 +1,23 @@
+//! A [SQLite](https://sqlite.org) backed event store.
+//!
+//! Meant for single-node services, tools and integration tests, which would
+//! rather not run a database server. All events are kept in a single table
+//! (see [`schema`]), and each commit is a single SQL transaction.
+//!
+//! See [`SqliteStore`].
+
+#![feature(error_generic_member_access)]
+#![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+pub use read::ReadError;
+pub use store::{
+    SqliteStore,
+    DEFAULT_IDEMPOTENCY_WINDOW,
+    DEFAULT_READ_PAGE_SIZE,
+};
+pub use write::WriteError;
+
+mod read;
+pub mod schema;
+mod store;
+mod write;
@@ -0,0 +1,197 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use futures::{future, stream, Stream, StreamExt as _};
+use occur::envelope::Recorded;
+use occur::store::read::MaybeUnknown;
+use occur::store::{read, serialization, Deserializer, ReadStream};
+use occur::{revision, ErrorWithKind, Event};
+use rusqlite::Connection;
+
+use crate::store::{last_commit_number, BoxError, SharedConnection};
+
+pub struct SqliteReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) connection: SharedConnection,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct ReadError {
+    kind: read::ErrorKind,
+    source: Option<BoxError>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl ErrorWithKind for ReadError {
+    type Kind = read::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl read::Error for ReadError {}
+
+impl ReadError {
+    fn new(kind: read::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(read::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for ReadError {
+    fn from(source: serialization::Error) -> Self {
+        let kind = match source.kind() {
+            serialization::ErrorKind::Format => read::ErrorKind::Serialization,
+            serialization::ErrorKind::UnknownRevision => {
+                read::ErrorKind::UnknownRevision
+            }
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+impl<T, D> ReadStream for SqliteReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted_recorded(
+        &mut self,
+        options: read::Options,
+    ) -> ReadResult<
+        impl Stream<
+            Item = ReadResult<MaybeUnknown<Recorded<revision::OldOrNew<T>>>>,
+        >,
+    > {
+        // reads are bounded by the last event at the time the read started,
+        // so that events committed while the read is consumed aren't read
+        let last_commit_number =
+            last_commit_number(&self.connection.lock(), &self.id)
+                .map_err(ReadError::other)?;
+        let Some(range) = options.range(last_commit_number) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let events = pages(
+            self.connection.clone(),
+            self.id.clone(),
+            range,
+            options.direction,
+            self.page_size,
+        );
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        Ok(events.filter_map(move |event| {
+            let recorded = match event {
+                Ok(event) => policy
+                    .deserialize(&deserializer, event)
+                    .map(|recorded| recorded.map_err(ReadError::from)),
+                Err(err) => Some(Err(err)),
+            };
+            future::ready(recorded)
+        }))
+    }
+
+    fn unknown_revision_policy(&self) -> &read::UnknownRevisionPolicy {
+        &self.unknown_revision_policy
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+/// Returns a stream of the serialized events of a stream within `range` (see
+/// [`read::range`]), ordered by the given `direction`.
+///
+/// Events are read lazily, in pages of up to `page_size` events, and the
+/// connection is only locked while a single page is read. A page that fails
+/// to be read ends the stream with an error.
+fn pages(
+    connection: SharedConnection,
+    stream_id: String,
+    range: Range<u64>,
+    direction: read::Direction,
+    page_size: usize,
+) -> impl Stream<Item = ReadResult<Vec<u8>>> + Send {
+    let page_size = i64::try_from(page_size.max(1)).unwrap_or(i64::MAX);
+    // bounds are bound to statements as they are (inclusive), which also
+    // allows expressing an empty range
+    let bound = |position| i64::try_from(position).unwrap_or(i64::MAX);
+    let range = (bound(range.start), bound(range.end) - 1);
+    stream::unfold(Some(range), move |range| {
+        let page =
+            range.filter(|(first, last)| first <= last).map(|(first, last)| {
+                let (page, rest) = match direction {
+                    read::Direction::Forward => {
+                        let page_last =
+                            first.saturating_add(page_size - 1).min(last);
+                        ((first, page_last), (page_last + 1, last))
+                    }
+                    read::Direction::Backward => {
+                        let page_first =
+                            last.saturating_sub(page_size - 1).max(first);
+                        ((page_first, last), (first, page_first - 1))
+                    }
+                };
+                let events =
+                    read_page(&connection.lock(), &stream_id, page, direction);
+                match events {
+                    Ok(events) => {
+                        (events.into_iter().map(Ok).collect(), Some(rest))
+                    }
+                    Err(err) => (vec![Err(ReadError::other(err))], None),
+                }
+            });
+        future::ready(page.map(|(events, rest)| (stream::iter(events), rest)))
+    })
+    .flatten()
+}
+
+fn read_page(
+    connection: &Connection,
+    stream_id: &str,
+    (first, last): (i64, i64),
+    direction: read::Direction,
+) -> rusqlite::Result<Vec<Vec<u8>>> {
+    let order = match direction {
+        read::Direction::Forward => "ASC",
+        read::Direction::Backward => "DESC",
+    };
+    let mut statement = connection.prepare_cached(&format!(
+        "SELECT payload FROM events WHERE stream_id = ?1 \
+         AND commit_number BETWEEN ?2 AND ?3 ORDER BY commit_number {order}"
+    ))?;
+    let rows =
+        statement.query_map((stream_id, first, last), |row| row.get(0))?;
+    rows.collect()
+}
@@ -0,0 +1,48 @@
+//! The table in which a [`SqliteStore`](crate::SqliteStore) keeps events.
+//!
+//! ```sql
+//! CREATE TABLE events (
+//!     stream_id        TEXT    NOT NULL,
+//!     commit_number    INTEGER NOT NULL,
+//!     revision_name    TEXT    NOT NULL,
+//!     revision_version INTEGER NOT NULL,
+//!     payload          BLOB    NOT NULL,
+//!     metadata         TEXT    NOT NULL,
+//!     PRIMARY KEY (stream_id, commit_number)
+//! ) WITHOUT ROWID
+//! ```
+//!
+//! `payload` holds the event as serialized by the store's serializer (along
+//! with its metadata and timestamp), and is all that's needed to read the
+//! event back. The revision of the event and its metadata (as JSON) are also
+//! kept in their own columns, so that events can be queried with SQL (e.g.,
+//! using `json_extract(metadata, '$.correlation_id')`).
+
+use rusqlite::Connection;
+
+/// The name of the table holding the events of all streams.
+pub const EVENTS_TABLE: &str = "events";
+
+/// Creates the events table, unless it already exists.
+///
+/// Called when a [`SqliteStore`](crate::SqliteStore) is created, so there's
+/// usually no need to call it directly.
+///
+/// # Errors
+///
+/// When the statement fails to execute.
+pub fn create(connection: &Connection) -> rusqlite::Result<()> {
+    // the primary key is what makes commit numbers unique within a stream,
+    // and clusters the events of each stream by their commit number
+    connection.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {EVENTS_TABLE} (
+            stream_id TEXT NOT NULL,
+            commit_number INTEGER NOT NULL,
+            revision_name TEXT NOT NULL,
+            revision_version INTEGER NOT NULL,
+            payload BLOB NOT NULL,
+            metadata TEXT NOT NULL,
+            PRIMARY KEY (stream_id, commit_number)
+        ) WITHOUT ROWID"
+    ))
+}
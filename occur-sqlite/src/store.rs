@@ -0,0 +1,236 @@
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use occur::envelope::EventId;
+use occur::store::clock::{Clock, SystemClock};
+use occur::store::read::UnknownRevisionPolicy;
+use occur::store::serialization::Serialization;
+use occur::store::{CommitNumber, Deserializer, Serializer};
+use occur::{Event, Store};
+use rusqlite::{Connection, OptionalExtension as _};
+use uuid::Uuid;
+
+use crate::read::SqliteReadStream;
+use crate::schema;
+use crate::write::SqliteWriteStream;
+
+/// The default number of recently committed event IDs that are checked when
+/// committing to a stream.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: usize = 64;
+
+/// The default number of events that are fetched at once by a read.
+pub const DEFAULT_READ_PAGE_SIZE: usize = 64;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A connection shared by a store and all of its streams.
+#[derive(Clone)]
+pub struct SharedConnection(Arc<Mutex<Connection>>);
+
+impl SharedConnection {
+    /// Locks the connection.
+    ///
+    /// The lock is never held across an await, and only for the duration of
+    /// a single statement or transaction.
+    pub fn lock(&self) -> MutexGuard<'_, Connection> {
+        // a panic while the lock is held leaves no transaction open (as it's
+        // rolled back when dropped), so the connection can still be used
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the commit numbers and IDs of the last `limit` events of a stream
+/// (or less, if it holds less events), from last to first.
+pub fn tail(
+    connection: &Connection,
+    stream_id: &str,
+    limit: usize,
+) -> Result<Vec<(CommitNumber, EventId)>, BoxError> {
+    let mut statement = connection.prepare_cached(
+        "SELECT commit_number, json_extract(metadata, '$.id') FROM events \
+         WHERE stream_id = ?1 ORDER BY commit_number DESC LIMIT ?2",
+    )?;
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = statement.query_map((stream_id, limit), |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    rows.map(|row| {
+        let (commit_number, id) = row?;
+        Ok((
+            CommitNumber::try_from(commit_number)?,
+            EventId(Uuid::parse_str(&id)?),
+        ))
+    })
+    .collect()
+}
+
+/// Returns the commit number of the last event of a stream, or [`None`] if the
+/// stream is empty.
+pub fn last_commit_number(
+    connection: &Connection,
+    stream_id: &str,
+) -> Result<Option<CommitNumber>, BoxError> {
+    let last_commit_number = connection
+        .prepare_cached(
+            "SELECT MAX(commit_number) FROM events WHERE stream_id = ?1",
+        )?
+        .query_row((stream_id,), |row| row.get::<_, Option<i64>>(0))
+        .optional()?
+        .flatten();
+    Ok(last_commit_number.map(CommitNumber::try_from).transpose()?)
+}
+
+/// An event store that holds events in a SQLite database.
+///
+/// The store creates its table when it's created (see [`schema`]). Cloning
+/// the store is cheap, and all clones share the same connection. Many stores
+/// (e.g., of different processes) may use the same database file, as each
+/// commit is a single transaction that locks the database for writing.
+///
+/// Stream IDs are stored as text, using their [`Display`] implementation,
+/// which must therefore uniquely identify a stream.
+///
+/// Commits are idempotent within a window of the last events of the stream
+/// (see [`Self::with_idempotency_window`]).
+#[allow(clippy::module_name_repetitions)]
+pub struct SqliteStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    connection: SharedConnection,
+    serializer: S,
+    deserializer: D,
+    clock: Arc<dyn Clock>,
+    idempotency_window: usize,
+    read_page_size: usize,
+    unknown_revision_policy: UnknownRevisionPolicy,
+    _event: PhantomData<T>,
+}
+
+impl<T, S, D> SqliteStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    /// Creates a store that holds events in the database of the given
+    /// connection, creating its table unless it already exists.
+    ///
+    /// # Errors
+    ///
+    /// When the table fails to be created.
+    pub fn new(
+        connection: Connection,
+        serialization: Serialization<S, D>,
+    ) -> rusqlite::Result<Self> {
+        schema::create(&connection)?;
+        let Serialization { serializer, deserializer } = serialization;
+        Ok(Self {
+            connection: SharedConnection(Arc::new(Mutex::new(connection))),
+            serializer,
+            deserializer,
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            read_page_size: DEFAULT_READ_PAGE_SIZE,
+            unknown_revision_policy: UnknownRevisionPolicy::default(),
+            _event: PhantomData,
+        })
+    }
+
+    /// Replaces the clock used to timestamp committed events (which is
+    /// [`SystemClock`] by default).
+    #[must_use]
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Sets the number of last events of a stream whose IDs are checked when
+    /// committing to it, for the purpose of making commits idempotent (which
+    /// is [`DEFAULT_IDEMPOTENCY_WINDOW`] by default).
+    ///
+    /// Re-committing an event that is older than the window commits it again.
+    /// A window of 0 disables idempotency altogether.
+    #[must_use]
+    pub fn with_idempotency_window(self, idempotency_window: usize) -> Self {
+        Self { idempotency_window, ..self }
+    }
+
+    /// Sets the maximum number of events that are fetched at once by a read
+    /// (which is [`DEFAULT_READ_PAGE_SIZE`] by default).
+    #[must_use]
+    pub fn with_read_page_size(self, read_page_size: usize) -> Self {
+        Self { read_page_size, ..self }
+    }
+
+    /// Sets how read streams opened by the store handle events that were
+    /// committed with an unknown revision (which is
+    /// [`UnknownRevisionPolicy::Fail`] by default).
+    #[must_use]
+    pub fn with_unknown_revision_policy(
+        self,
+        unknown_revision_policy: UnknownRevisionPolicy,
+    ) -> Self {
+        Self { unknown_revision_policy, ..self }
+    }
+}
+
+impl<T, S, D> Clone for SqliteStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            connection: self.connection.clone(),
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            read_page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<T, S, D> Store for SqliteStore<T, S, D>
+where
+    T: Event<Value = (&'static str, u8)>,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Clone + Send + Sync,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Event = T;
+    type WriteStream = SqliteWriteStream<T, S>;
+    type ReadStream = SqliteReadStream<T, D>;
+
+    fn write_stream(&self, id: T::StreamId) -> Self::WriteStream {
+        SqliteWriteStream {
+            id: id.to_string(),
+            connection: self.connection.clone(),
+            serializer: self.serializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            _event: PhantomData,
+        }
+    }
+
+    fn read_stream(&self, id: T::StreamId) -> Self::ReadStream {
+        SqliteReadStream {
+            id: id.to_string(),
+            connection: self.connection.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            _event: PhantomData,
+        }
+    }
+}
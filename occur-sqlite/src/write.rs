@@ -0,0 +1,235 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use occur::envelope::{Envelope, Recorded};
+use occur::store::clock::Clock;
+use occur::store::{
+    serialization,
+    write,
+    CommitNumber,
+    Serializer,
+    WriteStream,
+};
+use occur::{revision, ErrorWithKind, Event};
+use rusqlite::{ffi, Connection, TransactionBehavior};
+
+use crate::store::{tail, BoxError, SharedConnection};
+
+pub struct SqliteWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: String,
+    pub(super) connection: SharedConnection,
+    pub(super) serializer: S,
+    pub(super) clock: Arc<dyn Clock>,
+    pub(super) idempotency_window: usize,
+    pub(super) _event: PhantomData<T>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct WriteError {
+    kind: write::ErrorKind,
+    source: Option<BoxError>,
+    last_commit_number: Option<CommitNumber>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl WriteError {
+    fn new(kind: write::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            last_commit_number: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
+    fn other(source: impl Into<BoxError>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..Self::new(write::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for WriteError {
+    fn from(source: serialization::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Serialization)
+        }
+    }
+}
+
+impl From<rusqlite::Error> for WriteError {
+    fn from(source: rusqlite::Error) -> Self {
+        let kind = match source.sqlite_error() {
+            // only possible if another commit took the commit number first,
+            // which the transaction should prevent to begin with
+            Some(err)
+                if err.extended_code == ffi::SQLITE_CONSTRAINT_PRIMARYKEY =>
+            {
+                write::ErrorKind::ConditionNotMet
+            }
+            _ => write::ErrorKind::Other,
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+impl From<write::CheckError> for WriteError {
+    fn from(err: write::CheckError) -> Self {
+        match err {
+            write::CheckError::StreamFull => {
+                Self::new(write::ErrorKind::StreamFull)
+            }
+            write::CheckError::ConditionNotMet { last_commit_number } => Self {
+                last_commit_number,
+                ..Self::new(write::ErrorKind::ConditionNotMet)
+            },
+        }
+    }
+}
+
+impl ErrorWithKind for WriteError {
+    type Kind = write::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl write::Error for WriteError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        self.last_commit_number
+    }
+}
+
+type CommitResult<T> = Result<T, WriteError>;
+
+impl<T, S> WriteStream for SqliteWriteStream<T, S>
+where
+    T: Event<Value = (&'static str, u8)>,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    type Event = T;
+    type Error = WriteError;
+
+    async fn commit_old_or_new_envelope(
+        &mut self,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
+        condition: write::Condition,
+    ) -> CommitResult<CommitNumber> {
+        let commit_number = self.append(vec![envelope], condition)?;
+        Ok(commit_number.expect("a single event was committed"))
+    }
+
+    fn commit_many_envelopes<'a>(
+        &mut self,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
+        condition: write::Condition,
+    ) -> impl Future<Output = CommitResult<Option<CommitNumber>>> + Send {
+        let envelopes: Vec<_> = envelopes
+            .into_iter()
+            .map(|envelope| envelope.map(revision::OldOrNewRef::New))
+            .collect();
+        async move { self.append(envelopes, condition) }
+    }
+}
+
+impl<T, S> SqliteWriteStream<T, S>
+where
+    T: Event<Value = (&'static str, u8)>,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>> + Send + Sync,
+{
+    /// Timestamps, serializes and appends the given events to the stream,
+    /// given the provided condition holds.
+    ///
+    /// All events are appended within a single transaction, which locks the
+    /// database for writing as it begins, so that the condition still holds
+    /// when the events are inserted.
+    fn append(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        if envelopes.is_empty() {
+            return Ok(None);
+        }
+        self.append_locked(&mut self.connection.lock(), envelopes, condition)
+    }
+
+    fn append_locked(
+        &self,
+        connection: &mut Connection,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        let ids: Vec<_> =
+            envelopes.iter().map(|envelope| envelope.metadata.id).collect();
+        if write::has_duplicate_ids(&ids) {
+            return Err(WriteError::new(write::ErrorKind::DuplicateEventId));
+        }
+        let transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        // the last event is always fetched, as the next commit number follows
+        // it
+        let tail = tail(&transaction, &self.id, self.idempotency_window.max(1))
+            .map_err(WriteError::other)?;
+        let window = &tail[..self.idempotency_window.min(tail.len())];
+        match write::lookup_in_window(window, &ids) {
+            write::Lookup::New => {}
+            write::Lookup::Committed(commit_number) => {
+                return Ok(Some(commit_number))
+            }
+            write::Lookup::Partial => {
+                return Err(WriteError::new(
+                    write::ErrorKind::PartiallyCommitted,
+                ));
+            }
+        }
+        let last_commit_number = tail.first().map(|(number, _)| *number);
+        let commit_number = condition.check(last_commit_number, ids.len())?;
+        // timestamps are taken while the database is locked, so that they're
+        // ordered the same way as commit numbers
+        let timestamp = self.clock.now();
+        {
+            let mut insert = transaction.prepare_cached(
+                "INSERT INTO events (stream_id, commit_number, revision_name, \
+                 revision_version, payload, metadata) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (Envelope { event, metadata }, commit_number) in
+                envelopes.into_iter().zip(commit_number..)
+            {
+                let (revision_name, revision_version) = event.revision();
+                let metadata_json =
+                    serde_json::to_string(&metadata).map_err(|source| {
+                        WriteError {
+                            source: Some(Box::new(source)),
+                            ..WriteError::new(write::ErrorKind::Serialization)
+                        }
+                    })?;
+                let payload = self.serializer.serialize(Recorded {
+                    event,
+                    metadata,
+                    timestamp,
+                })?;
+                insert.execute((
+                    &self.id,
+                    commit_number,
+                    revision_name,
+                    revision_version,
+                    payload,
+                    metadata_json,
+                ))?;
+            }
+        }
+        // dropping the transaction without committing it (e.g., when an event
+        // fails to serialize) rolls it back, so no event is committed
+        transaction.commit()?;
+        Ok(Some(commit_number))
+    }
+}
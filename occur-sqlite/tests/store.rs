@@ -0,0 +1,131 @@
+use std::path::Path;
+use std::thread;
+
+use futures::executor::block_on;
+use futures::TryStreamExt as _;
+use occur::envelope::Envelope;
+use occur::store::serialization::cbor::{
+    cbor_serialization,
+    CborDeserializer,
+    CborSerializer,
+};
+use occur::store::write::Error as _;
+use occur::store::{write, ReadStream as _, Store as _, WriteStream as _};
+use occur::ErrorWithKind as _;
+use occur_conformance::{new_stream_id, Event};
+use occur_sqlite::SqliteStore;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+const THREADS: usize = 8;
+
+type Store = SqliteStore<Event, CborSerializer<Event>, CborDeserializer<Event>>;
+
+fn open(path: &Path) -> Store {
+    let connection = Connection::open(path).unwrap();
+    SqliteStore::new(connection, cbor_serialization::<Event>()).unwrap()
+}
+
+fn store() -> Store {
+    let connection = Connection::open_in_memory().unwrap();
+    SqliteStore::new(connection, cbor_serialization::<Event>())
+        .unwrap()
+        .with_read_page_size(occur_conformance::READ_PAGE_SIZE)
+        .with_idempotency_window(occur_conformance::IDEMPOTENCY_WINDOW)
+}
+
+occur_conformance::tests!(store(), [write, read, idempotency, envelope]);
+
+fn read_all(store: &Store, id: Uuid) -> Vec<Event> {
+    block_on(async {
+        let mut read_stream = store.read_stream(id);
+        read_stream.read_all().await.unwrap().try_collect().await.unwrap()
+    })
+}
+
+#[test]
+fn concurrent_commits_from_separate_connections() {
+    let dir = tempfile::tempdir().unwrap();
+    let id = new_stream_id();
+    let path = dir.path().join("events.db");
+    open(&path);
+    let deposited = Event::Deposited { amount: 1 };
+
+    // each thread uses its own connection, as separate processes would
+    let results: Vec<_> = thread::scope(|scope| {
+        let commits: Vec<_> = (0..THREADS)
+            .map(|_| {
+                scope.spawn(|| {
+                    let store = open(&path);
+                    block_on(
+                        store.write_stream(id).commit_as_number(&deposited, 0),
+                    )
+                })
+            })
+            .collect();
+        commits.into_iter().map(|commit| commit.join().unwrap()).collect()
+    });
+
+    let mut committed = 0;
+    for result in results {
+        match result {
+            Ok(commit_number) => {
+                assert_eq!(commit_number, 0);
+                committed += 1;
+            }
+            Err(err) => {
+                assert_eq!(err.kind(), write::ErrorKind::ConditionNotMet);
+                assert_eq!(err.last_commit_number(), Some(0));
+            }
+        }
+    }
+    assert_eq!(committed, 1);
+    assert_eq!(read_all(&open(&path), id), [deposited]);
+}
+
+#[test]
+fn events_are_persisted() {
+    let dir = tempfile::tempdir().unwrap();
+    let id = new_stream_id();
+    let path = dir.path().join("events.db");
+    let opened = Event::Opened { owner: "alice".to_owned() };
+    let deposited = Event::Deposited { amount: 100 };
+
+    block_on(
+        open(&path)
+            .write_stream(id)
+            .commit_many_unconditionally([&opened, &deposited]),
+    )
+    .unwrap();
+
+    assert_eq!(read_all(&open(&path), id), [opened, deposited]);
+}
+
+#[test]
+fn revisions_and_metadata_are_queryable() {
+    let dir = tempfile::tempdir().unwrap();
+    let id = new_stream_id();
+    let path = dir.path().join("events.db");
+    let envelope = Envelope::new(Event::Deposited { amount: 100 });
+    block_on(
+        open(&path)
+            .write_stream(id)
+            .commit_envelope(envelope.as_ref(), write::Condition::None),
+    )
+    .unwrap();
+
+    let row: (String, u8, String) = Connection::open(&path)
+        .unwrap()
+        .query_row(
+            "SELECT revision_name, revision_version, \
+             json_extract(metadata, '$.id') FROM events WHERE stream_id = ?1",
+            (id.to_string(),),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+
+    assert_eq!(
+        row,
+        ("Deposited".to_owned(), 0, envelope.metadata.id.0.to_string())
+    );
+}
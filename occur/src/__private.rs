@@ -0,0 +1,59 @@
+//! Items used by code generated by `occur-derive`.
+//!
+//! Not part of the public API; may change without notice.
+
+/// Lists the revision values of a type deriving `Revision`, so they can be
+/// inspected at compile time.
+pub trait Revisions {
+    const REVISIONS: &'static [(&'static str, u8)];
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns whether `revisions` contains the revision `(name, version)`.
+#[must_use]
+pub const fn contains(
+    revisions: &[(&str, u8)],
+    name: &str,
+    version: u8,
+) -> bool {
+    let mut i = 0;
+    while i < revisions.len() {
+        if str_eq(revisions[i].0, name) && revisions[i].1 == version {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Returns whether `revisions` contains a revision named `name` whose version
+/// is greater than `version`.
+#[must_use]
+pub const fn contains_newer(
+    revisions: &[(&str, u8)],
+    name: &str,
+    version: u8,
+) -> bool {
+    let mut i = 0;
+    while i < revisions.len() {
+        if str_eq(revisions[i].0, name) && revisions[i].1 > version {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
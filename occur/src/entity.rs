@@ -1,7 +1,19 @@
-use crate::Event;
+use futures::{StreamExt as _, TryStreamExt as _};
+
+use crate::store::{read, CommitNumber, ReadStream};
+use crate::{ErrorWithKind, Event, Store};
 
 /// The result of folding an event stream.
 pub trait Entity<T: Event> {
+    /// The version of the entity's schema.
+    ///
+    /// Snapshots of the entity (see [`crate::store::snapshot`]) record the
+    /// schema version they were taken with, and are discarded when it doesn't
+    /// match this value. Increment it whenever a change to the entity makes
+    /// existing snapshots invalid (e.g., a change to its fields, or to the way
+    /// it folds events).
+    const SCHEMA_VERSION: u32 = 0;
+
     /// Creates a new entity from the provided stream `id` and `event`.
     ///
     /// An event represents a recorded fact and cannot fail to be applied,
@@ -21,3 +33,176 @@ pub trait Entity<T: Event> {
     where
         Self: Sized;
 }
+
+/// An entity rehydrated from an event stream.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Rehydrated<E> {
+    /// The folded entity, or [`None`] if the stream is yet to contain a
+    /// creation event.
+    pub entity: Option<E>,
+
+    /// The commit number of the last event read from the stream, or [`None`]
+    /// if the stream is empty.
+    ///
+    /// Note that this includes events that were ignored while folding (e.g.,
+    /// non-creation events that were committed before a creation event).
+    pub last_commit_number: Option<CommitNumber>,
+}
+
+impl<E> Default for Rehydrated<E> {
+    fn default() -> Self { Self { entity: None, last_commit_number: None } }
+}
+
+impl<E> Rehydrated<E> {
+    /// Returns the commit number that will be assigned to the next event
+    /// committed to the stream.
+    ///
+    /// Use this with [`crate::store::write::Condition::AssignCommitNumber`] to
+    /// commit events that were decided upon using the rehydrated entity.
+    #[must_use]
+    pub fn next_commit_number(&self) -> CommitNumber {
+        self.last_commit_number.map_or(0, |number| number + 1)
+    }
+
+    /// Applies an event that was committed with the given `commit_number`.
+    ///
+    /// Creates the entity if it doesn't exist yet, or folds it otherwise.
+    #[must_use]
+    pub fn apply<T>(
+        self,
+        id: &T::StreamId,
+        commit_number: CommitNumber,
+        event: T,
+    ) -> Self
+    where
+        T: Event,
+        E: Entity<T>,
+    {
+        let entity = match self.entity {
+            None => E::new(id.clone(), event),
+            Some(entity) => Some(entity.fold(event)),
+        };
+        Self { entity, last_commit_number: Some(commit_number) }
+    }
+}
+
+/// Rehydrates an entity by folding all events of the given read stream.
+///
+/// See [`rehydrate_from`] for how events of unknown revisions are handled.
+///
+/// # Errors
+///
+/// When reading from the stream fails.
+pub async fn rehydrate<E, R>(
+    id: <R::Event as Event>::StreamId,
+    stream: &mut R,
+) -> Result<Rehydrated<E>, R::Error>
+where
+    E: Entity<R::Event>,
+    R: ReadStream,
+{
+    rehydrate_from(id, stream, Rehydrated::default()).await
+}
+
+/// Continues rehydrating an entity that was already folded up to (and
+/// including) `rehydrated.last_commit_number`, by folding any events that were
+/// committed afterwards.
+///
+/// Events are read using [`read::UnknownRevisionPolicy::Fail`], regardless of
+/// the stream's policy (which is restored afterwards, even if the returned
+/// future is dropped before completing): an event that isn't folded would
+/// leave the entity, and the commit numbers of the events that follow it, out
+/// of date.
+///
+/// # Errors
+///
+/// When reading from the stream fails, including when an event of an unknown
+/// revision is read.
+pub async fn rehydrate_from<E, R>(
+    id: <R::Event as Event>::StreamId,
+    stream: &mut R,
+    rehydrated: Rehydrated<E>,
+) -> Result<Rehydrated<E>, R::Error>
+where
+    E: Entity<R::Event>,
+    R: ReadStream,
+{
+    let policy = stream.unknown_revision_policy().clone();
+    stream.set_unknown_revision_policy(read::UnknownRevisionPolicy::Fail);
+    let guard = RestorePolicy { stream, policy };
+    fold_from(id, &mut *guard.stream, rehydrated).await
+}
+
+/// Restores the unknown revision policy of a stream when dropped, so that it's
+/// restored even when rehydrating is cancelled.
+struct RestorePolicy<'a, R: ReadStream> {
+    stream: &'a mut R,
+    policy: read::UnknownRevisionPolicy,
+}
+
+impl<R: ReadStream> Drop for RestorePolicy<'_, R> {
+    fn drop(&mut self) {
+        self.stream
+            .set_unknown_revision_policy(std::mem::take(&mut self.policy));
+    }
+}
+
+/// Folds the events committed after `rehydrated.last_commit_number`, numbering
+/// them in the order they're read.
+async fn fold_from<E, R>(
+    id: <R::Event as Event>::StreamId,
+    stream: &mut R,
+    rehydrated: Rehydrated<E>,
+) -> Result<Rehydrated<E>, R::Error>
+where
+    E: Entity<R::Event>,
+    R: ReadStream,
+{
+    let first_commit_number = rehydrated.next_commit_number();
+    let events = stream
+        .read(read::Options {
+            position: read::Position::CommitNumber(first_commit_number),
+            direction: read::Direction::Forward,
+            limit: None,
+        })
+        .await;
+    let events = match events {
+        Ok(events) => events,
+        // there are no events past the given commit number
+        Err(err) if err.kind() == read::ErrorKind::CommitNotFound => {
+            return Ok(rehydrated);
+        }
+        Err(err) => return Err(err),
+    };
+
+    events
+        .zip(futures::stream::iter(first_commit_number..))
+        .map(|(event, commit_number)| event.map(|event| (event, commit_number)))
+        .try_fold(rehydrated, |rehydrated, (event, commit_number)| {
+            futures::future::ready(Ok(rehydrated.apply(
+                &id,
+                commit_number,
+                event,
+            )))
+        })
+        .await
+}
+
+/// Rehydrates an entity by folding all events of the stream with the given ID.
+///
+/// Convenience function for [`rehydrate`].
+///
+/// # Errors
+///
+/// When reading from the stream fails.
+pub async fn load<E, S>(
+    store: &S,
+    id: <S::Event as Event>::StreamId,
+) -> Result<Rehydrated<E>, <S::ReadStream as ReadStream>::Error>
+where
+    E: Entity<S::Event>,
+    S: Store,
+{
+    let mut stream = store.read_stream(id.clone());
+    rehydrate(id, &mut stream).await
+}
@@ -0,0 +1,155 @@
+//! Events along with their metadata.
+//!
+//! An [`Envelope`] wraps an event that is about to be committed together with
+//! its [`Metadata`] (see [`crate::store::WriteStream::commit_envelope`]). Once
+//! committed, the event is read back as [`Recorded`], which also holds the time
+//! at which it was committed (as given by the store's
+//! [`Clock`](crate::store::clock::Clock)).
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use derive_more::Display;
+use uuid::Uuid;
+
+/// A unique identifier of an event.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct EventId(pub Uuid);
+
+impl EventId {
+    /// Generates a new, time-ordered event ID.
+    #[must_use]
+    pub fn new() -> Self { Self(Uuid::now_v7()) }
+}
+
+impl Default for EventId {
+    fn default() -> Self { Self::new() }
+}
+
+/// Metadata that is committed alongside an event.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    /// The unique identifier of the event.
+    pub id: EventId,
+
+    /// Identifies the overall flow (e.g., a request) the event belongs to.
+    pub correlation_id: Option<Uuid>,
+
+    /// Identifies the message (e.g., a command or another event) that caused
+    /// the event.
+    pub causation_id: Option<Uuid>,
+
+    /// Free-form user metadata.
+    pub custom: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Creates metadata with a newly generated event ID, and nothing else.
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Sets the correlation ID.
+    #[must_use]
+    pub const fn with_correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
+    /// Sets the causation ID.
+    #[must_use]
+    pub const fn with_causation_id(mut self, causation_id: Uuid) -> Self {
+        self.causation_id = Some(causation_id);
+        self
+    }
+
+    /// Sets the causation ID to the ID of the given event, and inherits its
+    /// correlation ID (or uses its ID as the correlation ID, if it has none).
+    #[must_use]
+    pub fn caused_by(mut self, cause: &Self) -> Self {
+        self.causation_id = Some(cause.id.0);
+        self.correlation_id = Some(cause.correlation_id.unwrap_or(cause.id.0));
+        self
+    }
+
+    /// Adds a custom metadata entry, replacing any previous value of `key`.
+    #[must_use]
+    pub fn with_custom(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.custom.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// An event along with its metadata, ready to be committed.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Envelope<E> {
+    /// The event itself.
+    pub event: E,
+
+    /// The metadata of the event.
+    pub metadata: Metadata,
+}
+
+impl<E> Envelope<E> {
+    /// Wraps the given event along with newly created metadata (see
+    /// [`Metadata::new`]).
+    pub fn new(event: E) -> Self { Self { event, metadata: Metadata::new() } }
+
+    /// Replaces the metadata of the envelope.
+    #[must_use]
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    /// Returns an envelope that borrows the event of this one.
+    pub fn as_ref(&self) -> Envelope<&E> {
+        Envelope { event: &self.event, metadata: self.metadata.clone() }
+    }
+
+    /// Maps the event of the envelope using the provided function.
+    pub fn map<F>(self, f: impl FnOnce(E) -> F) -> Envelope<F> {
+        Envelope { event: f(self.event), metadata: self.metadata }
+    }
+}
+
+/// A committed event, along with its metadata and commit timestamp.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Recorded<E> {
+    /// The event itself.
+    pub event: E,
+
+    /// The metadata the event was committed with.
+    pub metadata: Metadata,
+
+    /// The time at which the event was committed.
+    pub timestamp: SystemTime,
+}
+
+impl<E> Recorded<E> {
+    /// Returns a recorded event that borrows the event of this one.
+    pub fn as_ref(&self) -> Recorded<&E> {
+        Recorded {
+            event: &self.event,
+            metadata: self.metadata.clone(),
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Maps the event using the provided function.
+    pub fn map<F>(self, f: impl FnOnce(E) -> F) -> Recorded<F> {
+        Recorded {
+            event: f(self.event),
+            metadata: self.metadata,
+            timestamp: self.timestamp,
+        }
+    }
+}
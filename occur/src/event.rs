@@ -14,7 +14,7 @@ pub trait Event: Revision {
     ///
     /// See [`revision`] module documentation for details about event
     /// revisioning.
-    type OldRevision: revision::Convert<Event = Self>;
+    type OldRevision: revision::Convert<Event = Self, Value = Self::Value>;
 
     /// Returns the set of all supported revision values, which is the union of
     /// the revisions defined by `Self` and [`Self::OldRevision`].
@@ -22,7 +22,8 @@ pub trait Event: Revision {
     /// # Panics
     ///
     /// When the same revision is defined by both `Self` and
-    /// [`Self::OldRevision`].
+    /// [`Self::OldRevision`]. Deriving [`revision::Convert`] for
+    /// [`Self::OldRevision`] rejects such conflicts at compile time instead.
     #[must_use]
     fn supported_revisions() -> HashSet<Self::Value>
     where
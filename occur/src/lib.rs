@@ -17,11 +17,17 @@
 pub use entity::Entity;
 pub use error::ErrorWithKind;
 pub use event::Event;
+#[cfg(feature = "derive")] pub use occur_derive::Revision;
 pub use revision::Revision;
 pub use store::Store;
 
-mod entity;
+#[doc(hidden)] pub mod __private;
+pub mod entity;
+pub mod envelope;
 mod error;
 mod event;
+pub mod projection;
+pub mod repository;
 pub mod revision;
 pub mod store;
+pub mod subscription;
@@ -0,0 +1,293 @@
+//! Building read models from all events of a store.
+//!
+//! A [`Projection`] folds events into a read model. A [`ProjectionRunner`]
+//! drives one or more projections from the global order of all events in a
+//! store (see [`crate::store::all`]), keeping a separate checkpoint for each
+//! projection in a [`CheckpointStore`], keyed by [`Projection::name`].
+//!
+//! An event is applied to a projection only if it comes after the projection's
+//! checkpoint, which is saved right after the event is applied. Delivery is
+//! at-least-once: if the runner stops after an event was applied but before
+//! the checkpoint was saved, the event will be applied again.
+
+use futures::{Stream, StreamExt as _};
+
+use crate::store::all::{self, AllStream, ConvertedItem, GlobalPosition};
+use crate::store::{read, CommitNumber};
+use crate::subscription::CheckpointStore;
+use crate::{ErrorWithKind, Event};
+
+/// A read model that is built by applying events from all streams of a store.
+pub trait Projection<T: Event>: Send + Sync {
+    /// The name of the projection, which is used to key its checkpoint.
+    ///
+    /// Must be unique among all projections that share a checkpoint store.
+    fn name(&self) -> &str;
+
+    /// Applies an event that was committed to the stream with the given ID
+    /// and commit number.
+    fn apply(
+        &mut self,
+        stream_id: &T::StreamId,
+        commit_number: CommitNumber,
+        event: T,
+    );
+
+    /// Resets the projection to its initial state, before any event was
+    /// applied to it.
+    ///
+    /// Called by [`ProjectionRunner::rebuild`].
+    fn reset(&mut self);
+}
+
+/// How far behind the store a projection is.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Lag {
+    /// The global position of the last event applied to the projection, or
+    /// [`None`] if no event was applied yet.
+    pub checkpoint: Option<GlobalPosition>,
+
+    /// The global position of the last event in the store, or [`None`] if the
+    /// store is empty.
+    pub head: Option<GlobalPosition>,
+}
+
+impl Lag {
+    /// Returns the number of events that are yet to be applied to the
+    /// projection.
+    #[must_use]
+    pub const fn events_behind(&self) -> u64 {
+        match (self.checkpoint, self.head) {
+            (_, None) => 0,
+            (None, Some(head)) => head + 1,
+            (Some(checkpoint), Some(head)) => head.saturating_sub(checkpoint),
+        }
+    }
+}
+
+/// Errors that might occur when running projections.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<RE, CE> {
+    /// Reading events from the store failed.
+    #[error("failed to read events")]
+    Read(#[source] RE),
+
+    /// Accessing the checkpoint store failed.
+    #[error("failed to access checkpoint")]
+    Checkpoint(#[source] CE),
+}
+
+type RunResult<A, C> =
+    Result<(), Error<<A as AllStream>::Error, <C as CheckpointStore>::Error>>;
+
+/// Drives projections from all events of a store.
+///
+/// See the [module documentation](self) for details.
+#[allow(clippy::module_name_repetitions)]
+pub struct ProjectionRunner<A, C>
+where
+    A: AllStream,
+    C: CheckpointStore,
+{
+    all_stream: A,
+    checkpoints: C,
+}
+
+impl<A, C> ProjectionRunner<A, C>
+where
+    A: AllStream,
+    C: CheckpointStore,
+{
+    /// Creates a runner that reads events using `all_stream`, and keeps the
+    /// checkpoints of projections in `checkpoints`.
+    pub const fn new(all_stream: A, checkpoints: C) -> Self {
+        Self { all_stream, checkpoints }
+    }
+
+    /// Returns the underlying checkpoint store.
+    pub const fn checkpoints(&self) -> &C { &self.checkpoints }
+
+    /// Applies all events that were committed up until now to the given
+    /// projections, each starting right after its own checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// When reading events or accessing the checkpoint store fails.
+    pub async fn catch_up(
+        &mut self,
+        projections: &mut [&mut dyn Projection<A::Event>],
+    ) -> RunResult<A, C> {
+        let mut checkpoints = self.load_checkpoints(projections).await?;
+        let Some(start) = start_position(&checkpoints) else {
+            return Ok(());
+        };
+        let events = self
+            .all_stream
+            .read(all::Options {
+                position: all::Position::GlobalPosition(start),
+                direction: read::Direction::Forward,
+                limit: None,
+            })
+            .await;
+        let events = match events {
+            Ok(events) => events,
+            // there are no events past the start position
+            Err(err) if err.kind() == read::ErrorKind::CommitNotFound => {
+                return Ok(());
+            }
+            Err(err) => return Err(Error::Read(err)),
+        };
+        apply_all(&mut self.checkpoints, projections, &mut checkpoints, events)
+            .await
+    }
+
+    /// Rebuilds the given projection from scratch, by resetting it along with
+    /// its checkpoint, and then applying all events that were committed up
+    /// until now.
+    ///
+    /// # Errors
+    ///
+    /// When reading events or accessing the checkpoint store fails.
+    pub async fn rebuild(
+        &mut self,
+        projection: &mut dyn Projection<A::Event>,
+    ) -> RunResult<A, C> {
+        projection.reset();
+        self.checkpoints
+            .delete(projection.name())
+            .await
+            .map_err(Error::Checkpoint)?;
+        self.catch_up(&mut [projection]).await
+    }
+
+    /// Returns how far behind the store the given projection is.
+    ///
+    /// # Errors
+    ///
+    /// When reading events or accessing the checkpoint store fails.
+    pub async fn lag(
+        &mut self,
+        projection: &dyn Projection<A::Event>,
+    ) -> Result<Lag, Error<A::Error, C::Error>> {
+        let checkpoint = self
+            .checkpoints
+            .load(projection.name())
+            .await
+            .map_err(Error::Checkpoint)?;
+        let last = self
+            .all_stream
+            .read_unconverted(all::Options {
+                position: all::Position::Last,
+                direction: read::Direction::Backward,
+                limit: Some(1),
+            })
+            .await;
+        let head = match last {
+            Ok(events) => Box::pin(events)
+                .next()
+                .await
+                .transpose()
+                .map_err(Error::Read)?
+                .map(|item| item.global_position),
+            Err(err) if err.kind() == read::ErrorKind::CommitNotFound => None,
+            Err(err) => return Err(Error::Read(err)),
+        };
+        Ok(Lag { checkpoint, head })
+    }
+
+    async fn load_checkpoints(
+        &mut self,
+        projections: &[&mut dyn Projection<A::Event>],
+    ) -> Result<Vec<Option<GlobalPosition>>, Error<A::Error, C::Error>> {
+        let mut checkpoints = Vec::with_capacity(projections.len());
+        for projection in projections {
+            let checkpoint = self
+                .checkpoints
+                .load(projection.name())
+                .await
+                .map_err(Error::Checkpoint)?;
+            checkpoints.push(checkpoint);
+        }
+        Ok(checkpoints)
+    }
+}
+
+impl<A, C> ProjectionRunner<A, C>
+where
+    A: all::Subscribe,
+    C: CheckpointStore,
+{
+    /// Applies all events to the given projections, each starting right after
+    /// its own checkpoint, and then keeps applying events as they are
+    /// committed.
+    ///
+    /// Runs until reading events or accessing the checkpoint store fails.
+    ///
+    /// # Errors
+    ///
+    /// When reading events or accessing the checkpoint store fails.
+    pub async fn run(
+        &mut self,
+        projections: &mut [&mut dyn Projection<A::Event>],
+    ) -> RunResult<A, C> {
+        let mut checkpoints = self.load_checkpoints(projections).await?;
+        let Some(start) = start_position(&checkpoints) else {
+            return Ok(());
+        };
+        let events = self
+            .all_stream
+            .subscribe(all::Position::GlobalPosition(start))
+            .await
+            .map_err(Error::Read)?;
+        apply_all(&mut self.checkpoints, projections, &mut checkpoints, events)
+            .await
+    }
+}
+
+/// Returns the position from which events should be read, so that no
+/// projection misses any event, or [`None`] if there are no projections.
+fn start_position(
+    checkpoints: &[Option<GlobalPosition>],
+) -> Option<GlobalPosition> {
+    checkpoints
+        .iter()
+        .map(|checkpoint| checkpoint.map_or(0, |position| position + 1))
+        .min()
+}
+
+/// Applies each of the given events to every projection whose checkpoint
+/// precedes it, saving the projection's checkpoint afterwards.
+async fn apply_all<T, E, C>(
+    checkpoint_store: &mut C,
+    projections: &mut [&mut dyn Projection<T>],
+    checkpoints: &mut [Option<GlobalPosition>],
+    events: impl Stream<Item = Result<ConvertedItem<T>, E>>,
+) -> Result<(), Error<E, C::Error>>
+where
+    T: Event,
+    C: CheckpointStore,
+{
+    let mut events = Box::pin(events);
+    while let Some(item) = events.next().await {
+        let item = item.map_err(Error::Read)?;
+        let position = item.global_position;
+        for (projection, checkpoint) in
+            projections.iter_mut().zip(checkpoints.iter_mut())
+        {
+            if checkpoint.is_some_and(|checkpoint| checkpoint >= position) {
+                continue;
+            }
+            projection.apply(
+                &item.stream_id,
+                item.commit_number,
+                item.event.clone(),
+            );
+            checkpoint_store
+                .save(projection.name(), position)
+                .await
+                .map_err(Error::Checkpoint)?;
+            *checkpoint = Some(position);
+        }
+    }
+    Ok(())
+}
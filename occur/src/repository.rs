@@ -0,0 +1,216 @@
+//! Command handling on top of an event [`Store`].
+//!
+//! An [`EntityRepository`] runs the full cycle of handling a command that
+//! targets a single entity:
+//!
+//! 1. Rehydrate the entity from its event stream (see [`entity::load`]).
+//! 2. Decide which events should be committed in response to the command, using
+//!    a user-supplied `decide` function.
+//! 3. Commit the decided events using optimistic concurrency, so they're only
+//!    committed if no other events were committed to the stream since it was
+//!    read.
+//! 4. If a concurrent commit did happen, retry from step 1 according to the
+//!    repository's [`RetryPolicy`].
+
+use std::future::Future;
+use std::marker::PhantomData;
+
+use crate::entity::{self, Rehydrated};
+use crate::store::{write, ReadStream, WriteStream};
+use crate::{Entity, ErrorWithKind, Event, Store};
+
+/// Decides whether a failed attempt at handling a command should be retried.
+#[allow(clippy::module_name_repetitions)]
+pub trait RetryPolicy: Send {
+    /// Called after attempt number `attempt` (starting from 1) failed because
+    /// events were concurrently committed to the stream.
+    ///
+    /// Resolves to whether another attempt should be made. The returned future
+    /// may delay before resolving (e.g., to implement backoff).
+    fn retry(&mut self, attempt: u32) -> impl Future<Output = bool> + Send;
+}
+
+/// Retries immediately, until the given number of attempts were made.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MaxAttempts(pub u32);
+
+impl Default for MaxAttempts {
+    fn default() -> Self { Self(3) }
+}
+
+impl RetryPolicy for MaxAttempts {
+    async fn retry(&mut self, attempt: u32) -> bool { attempt < self.0 }
+}
+
+/// Never retries.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    async fn retry(&mut self, _attempt: u32) -> bool { false }
+}
+
+/// The result of successfully handling a command.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Outcome<E, T> {
+    /// The entity after the committed events were applied to it.
+    pub rehydrated: Rehydrated<E>,
+
+    /// The events that were committed, which may be empty.
+    pub events: Vec<T>,
+}
+
+/// Errors that might occur when handling a command.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<R, RE, WE> {
+    /// The command was rejected by the `decide` function.
+    #[error("command rejected")]
+    Rejected(R),
+
+    /// The entity could not be loaded.
+    #[error("failed to load entity")]
+    Read(#[source] RE),
+
+    /// The decided events could not be committed.
+    #[error("failed to commit events")]
+    Write(#[source] WE),
+
+    /// Events were concurrently committed to the stream on every attempt, and
+    /// the retry policy gave up.
+    #[error("gave up after {attempts} attempt(s) due to concurrent commits")]
+    Conflict {
+        attempts: u32,
+        #[source]
+        source: WE,
+    },
+}
+
+type ReadError<S> = <<S as Store>::ReadStream as ReadStream>::Error;
+type WriteError<S> = <<S as Store>::WriteStream as WriteStream>::Error;
+
+/// Handles commands targeting entities of type `E`, whose events are held in
+/// a store of type `S`.
+///
+/// See the [module documentation](self) for details.
+#[allow(clippy::module_name_repetitions)]
+pub struct EntityRepository<S, E, P = MaxAttempts> {
+    store: S,
+    retry_policy: P,
+    entity: PhantomData<fn() -> E>,
+}
+
+impl<S, E> EntityRepository<S, E>
+where
+    S: Store,
+    E: Entity<S::Event>,
+{
+    /// Creates a repository that retries according to [`MaxAttempts`]'s
+    /// default.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            retry_policy: MaxAttempts::default(),
+            entity: PhantomData,
+        }
+    }
+}
+
+impl<S, E, P> EntityRepository<S, E, P>
+where
+    S: Store,
+    E: Entity<S::Event>,
+    P: RetryPolicy,
+{
+    /// Replaces the retry policy of the repository.
+    pub fn with_retry_policy<Q: RetryPolicy>(
+        self,
+        retry_policy: Q,
+    ) -> EntityRepository<S, E, Q> {
+        EntityRepository {
+            store: self.store,
+            retry_policy,
+            entity: PhantomData,
+        }
+    }
+
+    /// Returns the underlying store.
+    pub const fn store(&self) -> &S { &self.store }
+
+    /// Rehydrates the entity with the given stream ID.
+    ///
+    /// # Errors
+    ///
+    /// When reading from the stream fails.
+    pub fn load(
+        &self,
+        id: <S::Event as Event>::StreamId,
+    ) -> impl Future<Output = Result<Rehydrated<E>, ReadError<S>>> + '_ {
+        // only borrows the store, so that the future doesn't require the retry
+        // policy to be `Sync`
+        entity::load(&self.store, id)
+    }
+
+    /// Handles a `command` targeting the entity with the given stream ID.
+    ///
+    /// The `decide` function is given the current entity (or [`None`], if it
+    /// doesn't exist yet) and the command, and returns the events that should
+    /// be committed in response, or a rejection. It might be called more than
+    /// once when retrying, so it should be free of side effects.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Rejected`] when `decide` rejects the command.
+    /// - [`Error::Read`] when the entity could not be loaded.
+    /// - [`Error::Write`] when the decided events could not be committed.
+    /// - [`Error::Conflict`] when the retry policy gave up.
+    pub async fn handle<C, R, F>(
+        &mut self,
+        id: <S::Event as Event>::StreamId,
+        command: C,
+        mut decide: F,
+    ) -> Result<Outcome<E, S::Event>, Error<R, ReadError<S>, WriteError<S>>>
+    where
+        C: Clone,
+        F: FnMut(&Option<E>, C) -> Result<Vec<S::Event>, R>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+
+            let rehydrated =
+                self.load(id.clone()).await.map_err(Error::Read)?;
+            let events = decide(&rehydrated.entity, command.clone())
+                .map_err(Error::Rejected)?;
+            if events.is_empty() {
+                return Ok(Outcome { rehydrated, events });
+            }
+
+            let first_commit_number = rehydrated.next_commit_number();
+            let mut stream = self.store.write_stream(id.clone());
+            let commit_result = stream
+                .commit_many_with_number(&events, first_commit_number)
+                .await;
+            match commit_result {
+                Ok(_) => {
+                    let rehydrated = events
+                        .iter()
+                        .cloned()
+                        .zip(first_commit_number..)
+                        .fold(rehydrated, |rehydrated, (event, number)| {
+                            rehydrated.apply(&id, number, event)
+                        });
+                    return Ok(Outcome { rehydrated, events });
+                }
+                Err(err) if err.kind() == write::ErrorKind::ConditionNotMet => {
+                    if !self.retry_policy.retry(attempt).await {
+                        return Err(Error::Conflict {
+                            attempts: attempt,
+                            source: err,
+                        });
+                    }
+                }
+                Err(err) => return Err(Error::Write(err)),
+            }
+        }
+    }
+}
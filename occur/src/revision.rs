@@ -5,24 +5,37 @@ use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
+#[cfg(feature = "derive")] pub use occur_derive::Convert;
+
 use crate::Event;
 
 /// A type whose instances have revisions.
 ///
 /// Documentation for this trait assumes it is implemented for an enum type,
 /// and that each enum variant is assigned a unique revision value.
+///
+/// Prefer deriving this trait over implementing it by hand, which ensures that
+/// [`Revision::revision`] and [`Revision::revision_set`] never drift apart:
+///
+/// ```
+/// # use occur::Revision;
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug, Revision)]
+/// enum Event {
+///     Created { name: String },
+///     #[revision(name = "Renamed", version = 1)]
+///     Renamed { new_name: String },
+/// }
+///
+/// let event = Event::Renamed { new_name: "admin".to_owned() };
+/// assert_eq!(event.revision(), ("Renamed", 1));
+/// ```
 pub trait Revision: Clone + Eq + Hash + Debug + Send + Sync + 'static {
     /// Used as the revision value that uniquely distinguishes enum variants.
     ///
-    /// TODO:
-    ///     Not using `#![feature(associated_type_defaults)]` anymore, so the
-    ///     next paragraph needs to be updated. It'll probably refer to the
-    ///     yet-be-implemented derive-macro for Revision.
-    ///
-    /// By default, this is a pair of a string name and revision number. The
-    /// name typically matches the enum variant's identifier, and the revision
-    /// number starts on 0 then increments by 1 every time a new revision is
-    /// introduced for the enum variant.
+    /// When using `#[derive(Revision)]`, this is a pair of a string name and
+    /// revision number. The name typically matches the enum variant's
+    /// identifier, and the revision number starts on 0 then increments by 1
+    /// every time a new revision is introduced for the enum variant.
     type Value: Debug + Clone + Eq + Hash;
 
     /// Returns the revision value of the enum variant.
@@ -76,9 +89,85 @@ impl<'a, T: Event> OldOrNewRef<'a, T> {
             Self::New(new) => OldOrNew::New(new.to_owned()),
         }
     }
+
+    /// Returns the revision value of the referenced event.
+    #[must_use]
+    pub fn revision(&self) -> T::Value {
+        match self {
+            Self::Old(old) => old.revision(),
+            Self::New(new) => new.revision(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for OldOrNewRef<'_, T>
+where
+    T: Event + serde::Serialize,
+    T::OldRevision: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Old(old) => old.serialize(serializer),
+            Self::New(new) => new.serialize(serializer),
+        }
+    }
 }
 
 /// A type whose instances can be converted to newer revisions of themselves.
+///
+/// Prefer deriving this trait over implementing it by hand, which also
+/// verifies at compile time that every conversion targets a strictly newer
+/// revision:
+///
+/// ```
+/// # use occur::{revision, Revision};
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug, Revision)]
+/// enum Event {
+///     #[revision(version = 2)]
+///     Renamed { first_name: String, last_name: String },
+/// }
+///
+/// #[allow(non_camel_case_types)]
+/// #[derive(Clone, PartialEq, Eq, Hash, Debug, revision::Convert)]
+/// enum OldEvent {
+///     #[revision(name = "Renamed", version = 0)]
+///     #[convert(into = Self, with = renamed_v0)]
+///     Renamed_V0 { name: String },
+///
+///     #[revision(name = "Renamed", version = 1)]
+///     #[convert(into = Event, with = renamed_v1)]
+///     Renamed_V1 { name: String },
+/// }
+///
+/// fn renamed_v0(old: OldEvent) -> OldEvent {
+///     let OldEvent::Renamed_V0 { name } = old else { unreachable!() };
+///     OldEvent::Renamed_V1 { name }
+/// }
+///
+/// fn renamed_v1(old: OldEvent) -> Event {
+///     let OldEvent::Renamed_V1 { name } = old else { unreachable!() };
+///     let (first_name, last_name) = name.split_once(' ').unwrap();
+///     Event::Renamed {
+///         first_name: first_name.to_owned(),
+///         last_name: last_name.to_owned(),
+///     }
+/// }
+/// # impl occur::Event for Event {
+/// #     type StreamId = u32;
+/// #     type OldRevision = OldEvent;
+/// # }
+///
+/// # use occur::revision::Convert as _;
+/// let old = OldEvent::Renamed_V0 { name: "John Doe".to_owned() };
+/// assert_eq!(old.convert_until_new(), Event::Renamed {
+///     first_name: "John".to_owned(),
+///     last_name: "Doe".to_owned(),
+/// });
+/// ```
 pub trait Convert: Revision {
     /// The newer type to which this type can be converted to.
     type Event: Event<Value = Self::Value>;
@@ -89,7 +178,8 @@ pub trait Convert: Revision {
     /// as needed to acquire an instance of [`Self::New`].
     ///
     /// Ensure that each invocation of `convert` returns a newer variant
-    /// revision, to avoid an infinite conversion loop.
+    /// revision, to avoid an infinite conversion loop. When deriving this
+    /// trait, the derived implementation panics if it doesn't.
     fn convert(self) -> OldOrNew<Self::Event>;
 
     /// Converts this instances as many times as needed until it becomes a new
@@ -136,3 +226,19 @@ impl<T: Event> Convert for Empty<T> {
     type Event = T;
     fn convert(self) -> OldOrNew<Self::Event> { unreachable!() }
 }
+
+#[cfg(feature = "serde")]
+impl<T: Event> serde::Serialize for Empty<T> {
+    fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        unreachable!()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Event> serde::Deserialize<'de> for Empty<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        _: D,
+    ) -> Result<Self, D::Error> {
+        Err(serde::de::Error::custom("an empty revision has no variants"))
+    }
+}
@@ -0,0 +1,245 @@
+//! Reading all events of a store, across streams, in a single global order.
+//!
+//! Every event committed to a [`GlobalStore`](crate::store::GlobalStore) is
+//! assigned a [`GlobalPosition`], which is unique across all streams of the
+//! store and increases with every commit. Events committed to the same stream
+//! are ordered the same way globally as they are within their stream.
+
+use std::future::Future;
+use std::ops::Range;
+
+use futures::{future, Stream, TryStreamExt};
+
+use crate::store::read::MaybeUnknown;
+use crate::store::{read, CommitNumber};
+use crate::{revision, Event};
+
+/// Position of an event within the global order of all events in a store.
+///
+/// Whenever an event is committed to a store it is assigned a sequentially
+/// increasing global position, starting from 0.
+pub type GlobalPosition = u64;
+
+/// Position of an event within the global order of all events in a store.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum Position {
+    /// Represents the position of the first event.
+    First,
+    /// Represents the position of the last event.
+    Last,
+    /// Represents the position of the event at the given global position.
+    GlobalPosition(GlobalPosition),
+}
+
+/// Options for reading all events of a store.
+///
+/// Semantics are the same as [`read::Options`], with the global position of
+/// events used in place of their commit number.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct Options {
+    /// The position of the first event to read.
+    pub position: Position,
+
+    /// The direction in which events should be read.
+    pub direction: read::Direction,
+
+    /// The maximum number of events to read. If `None`, events will be read
+    /// until the first or last event is reached, depending on the read
+    /// `direction`.
+    pub limit: Option<usize>,
+}
+
+impl Options {
+    /// Returns the range of global positions that should be read from a store
+    /// whose last event was assigned `last_global_position`, or [`None`] if
+    /// the store is empty or the read's position is out of bounds.
+    ///
+    /// Meant to be used by implementors of [`AllStream`]. See [`read::range`].
+    #[must_use]
+    pub fn range(
+        &self,
+        last_global_position: Option<GlobalPosition>,
+    ) -> Option<Range<GlobalPosition>> {
+        let last = last_global_position?;
+        let start = match self.position {
+            Position::First => 0,
+            Position::Last => last,
+            Position::GlobalPosition(position) => position,
+        };
+        read::range(start, last, self.direction, self.limit)
+    }
+}
+
+/// An event read from a store, along with its whereabouts.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Item<Id, E> {
+    /// The ID of the stream to which the event was committed.
+    pub stream_id: Id,
+    /// The commit number of the event within its stream.
+    pub commit_number: CommitNumber,
+    /// The global position of the event within the store.
+    pub global_position: GlobalPosition,
+    /// The event itself.
+    pub event: E,
+}
+
+impl<Id, E> Item<Id, E> {
+    /// Maps the event of the item using the provided function.
+    pub fn map<F>(self, f: impl FnOnce(E) -> F) -> Item<Id, F> {
+        Item {
+            stream_id: self.stream_id,
+            commit_number: self.commit_number,
+            global_position: self.global_position,
+            event: f(self.event),
+        }
+    }
+}
+
+/// An item read using [`AllStream::read_unconverted`].
+pub type UnconvertedItem<T> =
+    Item<<T as Event>::StreamId, MaybeUnknown<revision::OldOrNew<T>>>;
+
+/// An item read using [`AllStream::read`].
+pub type ConvertedItem<T> = Item<<T as Event>::StreamId, T>;
+
+/// Converts the event of an item to its newest revision, or returns [`None`]
+/// if the event is of an unknown revision.
+fn convert<T: Event>(item: UnconvertedItem<T>) -> Option<ConvertedItem<T>> {
+    let Item { stream_id, commit_number, global_position, event } = item;
+    event.known().map(|event| Item {
+        stream_id,
+        commit_number,
+        global_position,
+        event: event.to_new(),
+    })
+}
+
+/// A read-only view of all events in a store, in their global order.
+///
+/// Reads fail with [`read::ErrorKind::CommitNotFound`] when the specified
+/// [`Position::GlobalPosition`] was not found within the store.
+///
+/// Reads have the same consistency guarantees, and report errors the same way,
+/// as [`read::ReadStream`] reads:
+/// a read observes all events that were committed to the store when it
+/// started, in their global order, and none that were committed afterwards.
+/// Events of unknown revisions are handled according to the stream's
+/// [`read::UnknownRevisionPolicy`] as well.
+#[allow(clippy::module_name_repetitions, clippy::type_complexity)]
+pub trait AllStream: Send {
+    /// The type of events held within the store.
+    type Event: Event;
+
+    /// The type of error that might occur when reading events.
+    type Error: read::Error;
+
+    /// Same as [`read::ReadStream::set_unknown_revision_policy`].
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    );
+
+    #[rustfmt::skip]
+    /// Read events without converting them to their newest revision.
+    ///
+    /// Use [`Self::read`] to automatically convert the read events (using
+    /// [`revision::OldOrNew::to_new`]).
+    fn read_unconverted(
+        &mut self,
+        options: Options,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<UnconvertedItem<Self::Event>, Self::Error>>,
+            Self::Error,
+        >
+    > + Send;
+
+    #[rustfmt::skip]
+    /// Read events based on the provided options.
+    fn read(
+        &mut self,
+        options: Options,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<ConvertedItem<Self::Event>, Self::Error>>,
+            Self::Error,
+        >
+    > + Send {
+        let future = self.read_unconverted(options);
+        async {
+            future.await.map(|it| {
+                it.try_filter_map(|item| future::ready(Ok(convert(item))))
+            })
+        }
+    }
+
+    #[rustfmt::skip]
+    /// Read all events, from first to last.
+    fn read_all(
+        &mut self,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<ConvertedItem<Self::Event>, Self::Error>>,
+            Self::Error,
+        >
+    > + Send {
+        self.read(Options {
+            position: Position::First,
+            direction: read::Direction::Forward,
+            limit: None,
+        })
+    }
+}
+
+/// A view of all events in a store that can be subscribed to.
+///
+/// Same as [`read::Subscribe`], but over the global order of all events in a
+/// store.
+#[allow(clippy::type_complexity)]
+pub trait Subscribe: AllStream {
+    #[rustfmt::skip]
+    /// Subscribe to all events without converting them to their newest
+    /// revision.
+    ///
+    /// The subscription starts at the given `position`:
+    /// - [`Position::First`] replays all events of the store.
+    /// - [`Position::Last`] starts at the last event committed at the time of
+    ///   subscribing (or at the first event, if the store is empty).
+    /// - [`Position::GlobalPosition`] starts at the given global position,
+    ///   which doesn't have to be committed yet.
+    ///
+    /// Use [`Self::subscribe`] to automatically convert events (using
+    /// [`revision::OldOrNew::to_new`]).
+    fn subscribe_unconverted(
+        &mut self,
+        position: Position,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<UnconvertedItem<Self::Event>, Self::Error>>
+                + Send,
+            Self::Error,
+        >
+    > + Send;
+
+    #[rustfmt::skip]
+    /// Subscribe to all events, starting at the given `position`.
+    ///
+    /// See [`Self::subscribe_unconverted`] for details.
+    fn subscribe(
+        &mut self,
+        position: Position,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<ConvertedItem<Self::Event>, Self::Error>>
+                + Send,
+            Self::Error,
+        >
+    > + Send {
+        let future = self.subscribe_unconverted(position);
+        async {
+            future.await.map(|it| {
+                it.try_filter_map(|item| future::ready(Ok(convert(item))))
+            })
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Sources of commit timestamps.
+
+use std::time::SystemTime;
+
+/// Provides the current time, used by stores to timestamp committed events
+/// (see [`crate::envelope::Recorded::timestamp`]).
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// A [`Clock`] that returns the system time.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime { SystemTime::now() }
+}
@@ -0,0 +1,121 @@
+use futures::{future, StreamExt as _};
+
+use crate::store::all::{self, AllStream, UnconvertedItem};
+use crate::store::file::log::{Location, Segments};
+use crate::store::file::read::{read_event, ReadError};
+use crate::store::shared::tail::{self, Notify};
+use crate::store::shared::{page, GlobalEntry, GlobalLog};
+use crate::store::{read, Deserializer};
+use crate::Event;
+
+#[allow(clippy::module_name_repetitions)]
+pub struct FileAllStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) events: GlobalLog<T::StreamId, Location>,
+    pub(super) committed: Notify,
+    pub(super) segments: Segments,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+impl<T, D> AllStream for FileAllStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted(
+        &mut self,
+        options: all::Options,
+    ) -> ReadResult<impl futures::Stream<Item = ReadResult<UnconvertedItem<T>>>>
+    {
+        let len = self.events.read().await.len();
+        let last_global_position = len.checked_sub(1).map(|last| last as u64);
+        let Some(range) = options.range(last_global_position) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let segments = self.segments.clone();
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = page::read(
+            self.events.clone(),
+            range,
+            options.direction,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(index, entry)| {
+            future::ready(item(&segments, &deserializer, &policy, index, entry))
+        }))
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+impl<T, D> all::Subscribe for FileAllStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    async fn subscribe_unconverted(
+        &mut self,
+        position: all::Position,
+    ) -> ReadResult<
+        impl futures::Stream<Item = ReadResult<UnconvertedItem<T>>> + Send,
+    > {
+        let start = match position {
+            all::Position::First => 0,
+            all::Position::Last => {
+                self.events.read().await.len().saturating_sub(1)
+            }
+            all::Position::GlobalPosition(position) => {
+                usize::try_from(position).unwrap_or(usize::MAX)
+            }
+        };
+        let segments = self.segments.clone();
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = tail::tail(
+            self.events.clone(),
+            self.committed.clone(),
+            start,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(index, entry)| {
+            future::ready(item(&segments, &deserializer, &policy, index, entry))
+        }))
+    }
+}
+
+/// Reads and deserializes an entry of the global log (see [`read_event`]).
+fn item<T, D>(
+    segments: &Segments,
+    deserializer: &D,
+    policy: &read::UnknownRevisionPolicy,
+    index: usize,
+    entry: GlobalEntry<T::StreamId, Location>,
+) -> Option<ReadResult<UnconvertedItem<T>>>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    let recorded = read_event(segments, deserializer, policy, entry.event)?;
+    Some(recorded.map(|recorded| all::Item {
+        stream_id: entry.stream_id,
+        commit_number: entry.commit_number,
+        global_position: index as all::GlobalPosition,
+        event: recorded.map(|recorded| recorded.event),
+    }))
+}
@@ -0,0 +1,505 @@
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use uuid::Uuid;
+
+use crate::envelope::EventId;
+use crate::store::file::{BoxError, OpenError, SyncPolicy};
+use crate::store::CommitNumber;
+
+/// The extension of segment files.
+const EXTENSION: &str = "log";
+
+/// The name of the file that's locked by the store that has the log open.
+const LOCK_FILE_NAME: &str = "LOCK";
+
+/// The length of a record's header: the length of its body, followed by the
+/// checksum of its body.
+const HEADER_LEN: usize = 8;
+
+/// The location of a serialized event within a segment.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Location {
+    segment: usize,
+    offset: u64,
+    len: u32,
+}
+
+/// The segments of a log, from first to last, shared by its writer and all of
+/// its readers.
+#[derive(Clone, Default)]
+pub(super) struct Segments(Arc<RwLock<Vec<Arc<File>>>>);
+
+impl Segments {
+    /// Reads the serialized event at the given location.
+    pub(super) fn read(&self, location: Location) -> io::Result<Vec<u8>> {
+        let segment = Arc::clone(
+            &self.0.read().unwrap_or_else(PoisonError::into_inner)
+                [location.segment],
+        );
+        let mut event = vec![0; location.len as usize];
+        read_exact_at(&segment, &mut event, location.offset)?;
+        Ok(event)
+    }
+
+    /// Adds a segment, returning its index.
+    fn push(&self, segment: Arc<File>) -> usize {
+        let mut segments =
+            self.0.write().unwrap_or_else(PoisonError::into_inner);
+        segments.push(segment);
+        segments.len() - 1
+    }
+}
+
+/// A commit of events to a stream, as appended to the log.
+#[allow(clippy::struct_field_names)]
+pub(super) struct Commit<'a> {
+    pub(super) stream_id: &'a str,
+    pub(super) commit_number: CommitNumber,
+    pub(super) ids: &'a [EventId],
+    pub(super) events: &'a [Vec<u8>],
+}
+
+/// A commit, as read from the log when it's opened.
+pub(super) struct Record {
+    pub(super) stream_id: String,
+    pub(super) commit_number: CommitNumber,
+    pub(super) events: Vec<(EventId, Location)>,
+}
+
+/// Appends commits to the last segment of a log, rolling over to a new segment
+/// once it's full.
+pub(super) struct Writer {
+    /// The lock file of the log, which stays locked until the writer (and
+    /// with it, the store) is dropped.
+    _lock: File,
+    dir: PathBuf,
+    segments: Segments,
+    /// The last segment, which is opened for appending.
+    file: Arc<File>,
+    /// The index of the last segment.
+    segment: usize,
+    /// The length of the last segment.
+    len: u64,
+    next_global_position: u64,
+    /// The number of commits that were appended since the last segment was
+    /// last synced.
+    unsynced: usize,
+    /// Set if a failed append couldn't be undone, in which case the end of
+    /// the log is unknown, and nothing can be appended to it anymore.
+    broken: bool,
+}
+
+impl Writer {
+    /// Appends a commit as a single record, returning the locations of its
+    /// events.
+    ///
+    /// If the append fails, the segment is truncated back to its previous
+    /// length, so that later records aren't appended after a torn one.
+    pub(super) fn append(
+        &mut self,
+        commit: &Commit<'_>,
+        sync_policy: SyncPolicy,
+        segment_size: u64,
+    ) -> io::Result<Vec<Location>> {
+        if self.broken {
+            return Err(io::Error::other(
+                "a failed append left the log in an unknown state",
+            ));
+        }
+        if self.len > 0 && self.len >= segment_size {
+            self.roll()?;
+        }
+        let (record, spans) = encode(self.next_global_position, commit)?;
+        if let Err(err) = self.write(&record, sync_policy) {
+            if self.file.set_len(self.len).is_err() {
+                self.broken = true;
+            }
+            return Err(err);
+        }
+        let locations = spans
+            .into_iter()
+            .map(|(offset, len)| Location {
+                segment: self.segment,
+                offset: self.len + offset as u64,
+                len,
+            })
+            .collect();
+        self.len += record.len() as u64;
+        self.next_global_position += commit.events.len() as u64;
+        Ok(locations)
+    }
+
+    /// Flushes all appended commits to disk.
+    pub(super) fn sync(&mut self) -> io::Result<()> {
+        if self.unsynced > 0 {
+            self.file.sync_data()?;
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        record: &[u8],
+        sync_policy: SyncPolicy,
+    ) -> io::Result<()> {
+        (&*self.file).write_all(record)?;
+        self.unsynced += 1;
+        match sync_policy {
+            SyncPolicy::EveryCommit => self.sync(),
+            SyncPolicy::Batched(commits) if self.unsynced >= commits => {
+                self.sync()
+            }
+            SyncPolicy::Batched(_) | SyncPolicy::Os => Ok(()),
+        }
+    }
+
+    /// Seals the last segment, and starts a new one.
+    fn roll(&mut self) -> io::Result<()> {
+        // sealed segments are always synced, so that only the last segment
+        // can end with a torn write
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        let file =
+            Arc::new(create_segment(&self.dir, self.next_global_position)?);
+        self.segment = self.segments.push(file.clone());
+        self.file = file;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// Opens the log in `dir` (creating it if it doesn't exist), calling
+/// `on_record` with each of its records, in order.
+///
+/// The log is locked until the returned writer is dropped, and fails to be
+/// opened with [`OpenError::Locked`] while it's locked by another writer.
+///
+/// A torn write at the end of the last segment (see [`is_torn`]) is the
+/// result of an append that was interrupted, and never acknowledged, so it's
+/// truncated. Any other invalid record, or one for which `on_record` fails,
+/// fails the whole log with [`OpenError::Corrupted`].
+pub(super) fn open(
+    dir: &Path,
+    mut on_record: impl FnMut(Record) -> Result<(), BoxError>,
+) -> Result<(Segments, Writer), OpenError> {
+    fs::create_dir_all(dir)?;
+    let lock = lock(dir)?;
+    let mut paths = segment_paths(dir)?;
+    if paths.is_empty() {
+        create_segment(dir, 0)?;
+        paths.push((0, segment_path(dir, 0)));
+    }
+    let segments = Segments::default();
+    let mut next_global_position = 0;
+    let mut last = None;
+    for (i, (first_global_position, path)) in paths.iter().enumerate() {
+        let corrupted =
+            |offset: usize, source: BoxError| OpenError::Corrupted {
+                segment: path.clone(),
+                offset: offset as u64,
+                source,
+            };
+        if *first_global_position != next_global_position {
+            return Err(corrupted(
+                0,
+                "segment doesn't follow its previous".into(),
+            ));
+        }
+        let file = OpenOptions::new().read(true).append(true).open(path)?;
+        let bytes = fs::read(path)?;
+        let is_last = i == paths.len() - 1;
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let decoded = decode(&bytes[offset..], i, offset);
+            if is_last && is_torn(&decoded, &bytes[offset..]) {
+                file.set_len(offset as u64)?;
+                file.sync_all()?;
+                break;
+            }
+            let (global_position, record, len) = match decoded {
+                Decoded::Record { global_position, record, len } => {
+                    (global_position, record, len)
+                }
+                Decoded::Incomplete => {
+                    return Err(corrupted(offset, "incomplete record".into()));
+                }
+                Decoded::Mismatch { .. } => {
+                    return Err(corrupted(offset, "checksum mismatch".into()));
+                }
+                Decoded::Malformed => {
+                    return Err(corrupted(offset, "malformed record".into()));
+                }
+            };
+            if global_position != next_global_position {
+                return Err(corrupted(
+                    offset,
+                    "unexpected global position".into(),
+                ));
+            }
+            next_global_position += record.events.len() as u64;
+            on_record(record).map_err(|source| corrupted(offset, source))?;
+            offset += len;
+        }
+        let file = Arc::new(file);
+        let segment = segments.push(file.clone());
+        last = Some((file, segment, offset as u64));
+    }
+    let (file, segment, len) = last.expect("the log has at least one segment");
+    let writer = Writer {
+        _lock: lock,
+        dir: dir.to_owned(),
+        segments: segments.clone(),
+        file,
+        segment,
+        len,
+        next_global_position,
+        unsynced: 0,
+        broken: false,
+    };
+    Ok((segments, writer))
+}
+
+/// Takes an exclusive lock of the log in `dir`, which is held for as long as
+/// the returned file is open.
+fn lock(dir: &Path) -> Result<File, OpenError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE_NAME))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(OpenError::Locked),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Returns the first global positions and paths of the segments in `dir`,
+/// ordered by their first global positions.
+fn segment_paths(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new(EXTENSION)) {
+            continue;
+        }
+        let first_global_position = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok());
+        if let Some(first_global_position) = first_global_position {
+            paths.push((first_global_position, path));
+        }
+    }
+    paths.sort_unstable();
+    Ok(paths)
+}
+
+/// Segments are named after the global position of their first event, so that
+/// their names order them.
+fn segment_path(dir: &Path, first_global_position: u64) -> PathBuf {
+    dir.join(format!("{first_global_position:020}.{EXTENSION}"))
+}
+
+fn create_segment(dir: &Path, first_global_position: u64) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create_new(true)
+        .open(segment_path(dir, first_global_position))?;
+    sync_dir(dir)?;
+    Ok(file)
+}
+
+/// The offset (within its record) and length of a serialized event.
+type Span = (usize, u32);
+
+/// Encodes a commit as a record, returning it along with the spans of its
+/// events within it.
+///
+/// A record is laid out as follows (with all integers in little-endian):
+///
+/// ```text
+/// [body length: u32][CRC-32 of body: u32]
+/// body:
+///   [global position: u64][commit number: u32]
+///   [stream ID length: u32][stream ID: UTF-8]
+///   [event count: u32]
+///   for each event: [event ID: 16 bytes][length: u32][serialized event]
+/// ```
+fn encode(
+    global_position: u64,
+    commit: &Commit<'_>,
+) -> io::Result<(Vec<u8>, Vec<Span>)> {
+    let mut record = vec![0; HEADER_LEN];
+    record.extend(global_position.to_le_bytes());
+    record.extend(commit.commit_number.to_le_bytes());
+    record.extend(len(commit.stream_id.len())?.to_le_bytes());
+    record.extend(commit.stream_id.as_bytes());
+    record.extend(len(commit.events.len())?.to_le_bytes());
+    let mut spans = Vec::with_capacity(commit.events.len());
+    for (id, event) in commit.ids.iter().zip(commit.events) {
+        let event_len = len(event.len())?;
+        record.extend(id.0.as_bytes());
+        record.extend(event_len.to_le_bytes());
+        spans.push((record.len(), event_len));
+        record.extend(event);
+    }
+    let body_len = len(record.len() - HEADER_LEN)?;
+    let checksum = crc32fast::hash(&record[HEADER_LEN..]);
+    record[..4].copy_from_slice(&body_len.to_le_bytes());
+    record[4..HEADER_LEN].copy_from_slice(&checksum.to_le_bytes());
+    Ok((record, spans))
+}
+
+/// Converts the length of a part of a record to the `u32` it's encoded as.
+fn len(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "commit is too large")
+    })
+}
+
+enum Decoded {
+    Record {
+        global_position: u64,
+        record: Record,
+        len: usize,
+    },
+    /// The record is cut off by the end of its segment.
+    Incomplete,
+    /// The record fails its checksum.
+    Mismatch {
+        len: usize,
+    },
+    /// The record passes its checksum, but can't be decoded.
+    Malformed,
+}
+
+/// Returns whether the record at the start of `bytes` (which extend to the end
+/// of its segment) is a torn write.
+///
+/// That's the case when the record is incomplete, when it fails its checksum
+/// and is the last record of its segment, or when all remaining bytes are
+/// zeros (as when a part of the segment never reached the disk before a
+/// crash).
+fn is_torn(decoded: &Decoded, bytes: &[u8]) -> bool {
+    let is_last = match decoded {
+        Decoded::Incomplete => true,
+        Decoded::Mismatch { len } => *len == bytes.len(),
+        Decoded::Record { .. } | Decoded::Malformed => false,
+    };
+    is_last || bytes.iter().all(|byte| *byte == 0)
+}
+
+/// Decodes the record at the start of `bytes`, which is located at `offset`
+/// within the segment of the given index.
+fn decode(bytes: &[u8], segment: usize, offset: usize) -> Decoded {
+    let mut reader = Reader { bytes, position: 0 };
+    let (Some(body_len), Some(checksum)) = (reader.u32(), reader.u32()) else {
+        return Decoded::Incomplete;
+    };
+    let Some(body) = reader.take(body_len as usize) else {
+        return Decoded::Incomplete;
+    };
+    if crc32fast::hash(body) != checksum {
+        return Decoded::Mismatch { len: HEADER_LEN + body.len() };
+    }
+    let offset = offset + HEADER_LEN;
+    let mut reader = Reader { bytes: body, position: 0 };
+    let mut decode_body = || {
+        let global_position = reader.u64()?;
+        let commit_number = reader.u32()?;
+        let stream_id_len = reader.u32()? as usize;
+        let stream_id = reader.take(stream_id_len)?;
+        let stream_id = String::from_utf8(stream_id.to_vec()).ok()?;
+        let events = (0..reader.u32()?)
+            .map(|_| {
+                let id = Uuid::from_slice(reader.take(16)?).ok()?;
+                let len = reader.u32()?;
+                let location = Location {
+                    segment,
+                    offset: (offset + reader.position) as u64,
+                    len,
+                };
+                reader.take(len as usize)?;
+                Some((EventId(id), location))
+            })
+            .collect::<Option<_>>()?;
+        let record = Record { stream_id, commit_number, events };
+        Some((global_position, record))
+    };
+    match decode_body() {
+        Some((global_position, record)) if reader.position == body.len() => {
+            Decoded::Record {
+                global_position,
+                record,
+                len: HEADER_LEN + body.len(),
+            }
+        }
+        _ => Decoded::Malformed,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(len)?;
+        let bytes = self.bytes.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(
+    file: &File,
+    mut buf: &mut [u8],
+    mut offset: u64,
+) -> io::Result<()> {
+    use std::os::windows::fs::FileExt as _;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Flushes the entries of a directory to disk, so that segments which were
+/// created (or truncated) within it survive a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> { File::open(dir)?.sync_all() }
+
+/// Directories can't be opened as files on Windows, where their entries are
+/// flushed along with their files.
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+const fn sync_dir(_: &Path) -> io::Result<()> { Ok(()) }
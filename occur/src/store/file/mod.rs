@@ -0,0 +1,372 @@
+//! An event store that keeps its events in an append-only log on local disk.
+//!
+//! [`FileStore`] needs no database, which makes it a fit for edge deployments
+//! and CLI tools. Its log is a directory of segment files, each holding the
+//! commits that were appended to it (as one record per commit), in order. A
+//! segment is sealed once it's [larger](FileStore::with_segment_size) than
+//! the segment size, and a new segment is started.
+//!
+//! Each record starts with a CRC-32 checksum, and holds all events of its
+//! commit, so that a commit is either entirely in the log or not at all. When
+//! a store is opened, it reads the whole log to index the events of each
+//! stream (and of all streams, in a single global order), and truncates a
+//! torn write at its end (of a commit that was interrupted by a crash, and
+//! thus never acknowledged). Only the indices of events are kept in memory,
+//! and events are read from disk as reads are consumed.
+//!
+//! When commits are flushed to disk is decided by the store's
+//! [`SyncPolicy`].
+//!
+//! A log can only be opened by a single store at a time (clones of a store
+//! share its log), which holds a lock of the log until it's dropped.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use futures_locks::RwLock;
+pub use read::ReadError;
+pub use write::WriteError;
+
+use crate::store::clock::{Clock, SystemClock};
+pub use crate::store::file::all::FileAllStream;
+use crate::store::file::log::{Location, Segments, Writer};
+pub use crate::store::file::read::FileReadStream;
+pub use crate::store::file::write::FileWriteStream;
+use crate::store::read::UnknownRevisionPolicy;
+use crate::store::serialization::Serialization;
+use crate::store::shared::idempotency::CommittedIds;
+use crate::store::shared::sharded::ShardedMap;
+use crate::store::shared::tail::Notify;
+use crate::store::shared::{
+    trim_committed_ids,
+    GlobalEntry,
+    GlobalLog,
+    StreamEvents,
+};
+pub use crate::store::shared::{
+    DEFAULT_IDEMPOTENCY_WINDOW,
+    DEFAULT_READ_PAGE_SIZE,
+};
+use crate::store::{Deserializer, GlobalStore, Serializer};
+use crate::{Event, Store};
+
+mod all;
+mod log;
+mod read;
+mod write;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The default size (in bytes) above which a segment is sealed.
+pub const DEFAULT_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// When commits are flushed to disk (using `fsync`, or its equivalent).
+///
+/// Either way, commits are visible to reads as soon as they're acknowledged,
+/// and [`FileStore::sync`] flushes all of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Each commit is flushed before it's acknowledged, so that acknowledged
+    /// commits survive a crash of the machine.
+    #[default]
+    EveryCommit,
+    /// Commits are flushed once every given number of commits, so that a
+    /// crash of the machine loses, at most, the commits that were
+    /// acknowledged since the last flush.
+    Batched(usize),
+    /// Commits are never flushed (other than by [`FileStore::sync`]), which
+    /// is left to the OS. Acknowledged commits survive a crash of the process,
+    /// but not of the machine.
+    Os,
+}
+
+/// An error of opening a [`FileStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum OpenError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The log is opened by another store (possibly of another process).
+    #[error("the log is locked by another store")]
+    Locked,
+    /// The log holds an invalid record, other than at its end.
+    ///
+    /// This is also the case when a record's stream ID can't be parsed, as
+    /// when the log was written by a store of different events.
+    #[error("corrupted record at offset {offset} of {}", segment.display())]
+    Corrupted {
+        segment: PathBuf,
+        offset: u64,
+        #[source]
+        source: BoxError,
+    },
+}
+
+/// An event store that keeps its events in an append-only log on local disk
+/// (see [module documentation](self)).
+///
+/// Cloning the store is cheap, and all clones share the same log. File
+/// operations are blocking, and are performed by the task that reads or
+/// commits events.
+#[allow(clippy::module_name_repetitions)]
+pub struct FileStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    events_by_stream_id: Arc<ShardedMap<T::StreamId, StreamEvents<Location>>>,
+    all_events: GlobalLog<T::StreamId, Location>,
+    all_committed: Notify,
+    segments: Segments,
+    writer: Arc<futures_locks::Mutex<Writer>>,
+    serializer: S,
+    deserializer: D,
+    clock: Arc<dyn Clock>,
+    idempotency_window: usize,
+    read_page_size: usize,
+    unknown_revision_policy: UnknownRevisionPolicy,
+    sync_policy: SyncPolicy,
+    segment_size: u64,
+}
+
+impl<T, S, D> FileStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    /// Opens the store whose log is in `dir`, creating an empty one if the
+    /// directory doesn't exist (or is empty).
+    ///
+    /// The whole log is read to index its events, and a torn write at its end
+    /// is truncated.
+    ///
+    /// # Errors
+    ///
+    /// When the log fails to be read (or truncated), or with
+    /// [`OpenError::Corrupted`] when it holds an invalid record other than at
+    /// its end, or with [`OpenError::Locked`] when it's opened by another
+    /// store.
+    pub fn open(
+        dir: impl AsRef<Path>,
+        serialization: Serialization<S, D>,
+    ) -> Result<Self, OpenError>
+    where
+        T::StreamId: FromStr,
+        <T::StreamId as FromStr>::Err:
+            std::error::Error + Send + Sync + 'static,
+    {
+        let mut streams = HashMap::new();
+        let mut all_events = Vec::new();
+        let (segments, writer) = log::open(dir.as_ref(), |record| {
+            let stream_id: T::StreamId = record.stream_id.parse()?;
+            let (events, committed_ids) = streams
+                .entry(stream_id.clone())
+                .or_insert_with(|| (Vec::new(), CommittedIds::default()));
+            if events.len() != record.commit_number as usize {
+                return Err("unexpected commit number".into());
+            }
+            all_events.extend(
+                record.events.iter().zip(record.commit_number..).map(
+                    |((_, location), commit_number)| GlobalEntry {
+                        stream_id: stream_id.clone(),
+                        commit_number,
+                        event: *location,
+                    },
+                ),
+            );
+            events.extend(record.events.iter().map(|(_, location)| *location));
+            committed_ids.insert(
+                record
+                    .events
+                    .iter()
+                    .map(|(id, _)| *id)
+                    .zip(record.commit_number..),
+                DEFAULT_IDEMPOTENCY_WINDOW,
+            );
+            Ok(())
+        })?;
+        let events_by_stream_id = streams
+            .into_iter()
+            .map(|(stream_id, (events, committed_ids))| {
+                (stream_id, StreamEvents {
+                    events: Arc::new(RwLock::new(events)),
+                    committed: Notify::default(),
+                    committed_ids: Arc::new(Mutex::new(committed_ids)),
+                })
+            })
+            .collect();
+        let Serialization { serializer, deserializer } = serialization;
+        Ok(Self {
+            events_by_stream_id: Arc::new(events_by_stream_id),
+            all_events: Arc::new(RwLock::new(all_events)),
+            all_committed: Notify::default(),
+            segments,
+            writer: Arc::new(futures_locks::Mutex::new(writer)),
+            serializer,
+            deserializer,
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            read_page_size: DEFAULT_READ_PAGE_SIZE,
+            unknown_revision_policy: UnknownRevisionPolicy::default(),
+            sync_policy: SyncPolicy::default(),
+            segment_size: DEFAULT_SEGMENT_SIZE,
+        })
+    }
+
+    /// Flushes all commits to disk, regardless of the [`SyncPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// When the last segment of the log fails to be flushed.
+    pub async fn sync(&self) -> io::Result<()> {
+        self.writer.lock().await.sync()
+    }
+
+    /// Replaces the clock used to timestamp committed events (which is
+    /// [`SystemClock`] by default).
+    #[must_use]
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Sets the number of recently committed event IDs that are remembered
+    /// per stream, for the purpose of making commits idempotent (which is
+    /// [`DEFAULT_IDEMPOTENCY_WINDOW`] by default).
+    ///
+    /// When the store is opened, the IDs of the last
+    /// [`DEFAULT_IDEMPOTENCY_WINDOW`] events of each stream are remembered,
+    /// and those beyond a smaller window are forgotten once it's set (a larger
+    /// window is only filled by new commits). A window of 0 disables
+    /// idempotency altogether.
+    #[must_use]
+    pub fn with_idempotency_window(self, idempotency_window: usize) -> Self {
+        trim_committed_ids(&self.events_by_stream_id, idempotency_window);
+        Self { idempotency_window, ..self }
+    }
+
+    /// Sets the maximum number of events that are read at once (which is
+    /// [`DEFAULT_READ_PAGE_SIZE`] by default).
+    #[must_use]
+    pub fn with_read_page_size(self, read_page_size: usize) -> Self {
+        Self { read_page_size, ..self }
+    }
+
+    /// Sets the policy with which read and all streams of the store handle
+    /// events of unknown revisions (which is [`UnknownRevisionPolicy::Fail`]
+    /// by default).
+    #[must_use]
+    pub fn with_unknown_revision_policy(
+        self,
+        unknown_revision_policy: UnknownRevisionPolicy,
+    ) -> Self {
+        Self { unknown_revision_policy, ..self }
+    }
+
+    /// Sets when commits are flushed to disk (which is
+    /// [`SyncPolicy::EveryCommit`] by default).
+    #[must_use]
+    pub fn with_sync_policy(self, sync_policy: SyncPolicy) -> Self {
+        Self { sync_policy, ..self }
+    }
+
+    /// Sets the size (in bytes) above which a segment is sealed, and commits
+    /// are appended to a new segment (which is [`DEFAULT_SEGMENT_SIZE`] by
+    /// default).
+    #[must_use]
+    pub fn with_segment_size(self, segment_size: u64) -> Self {
+        Self { segment_size, ..self }
+    }
+}
+
+impl<T, S, D> Clone for FileStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            events_by_stream_id: self.events_by_stream_id.clone(),
+            all_events: self.all_events.clone(),
+            all_committed: self.all_committed.clone(),
+            segments: self.segments.clone(),
+            writer: self.writer.clone(),
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            read_page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+            sync_policy: self.sync_policy,
+            segment_size: self.segment_size,
+        }
+    }
+}
+
+impl<T, S, D> Store for FileStore<T, S, D>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    type Event = T;
+    type WriteStream = FileWriteStream<T, S>;
+    type ReadStream = FileReadStream<T, D>;
+
+    fn write_stream(&self, id: T::StreamId) -> Self::WriteStream {
+        let stream = self.events_by_stream_id.get_or_default(id.clone());
+        FileWriteStream {
+            id,
+            events: stream.events,
+            committed: stream.committed,
+            committed_ids: stream.committed_ids,
+            idempotency_window: self.idempotency_window,
+            all_events: self.all_events.clone(),
+            all_committed: self.all_committed.clone(),
+            writer: self.writer.clone(),
+            sync_policy: self.sync_policy,
+            segment_size: self.segment_size,
+            serializer: self.serializer.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+
+    fn read_stream(&self, id: T::StreamId) -> Self::ReadStream {
+        let stream = self.events_by_stream_id.get_or_default(id);
+        FileReadStream {
+            events: stream.events,
+            committed: stream.committed,
+            segments: self.segments.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+        }
+    }
+}
+
+impl<T, S, D> GlobalStore for FileStore<T, S, D>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    type AllStream = FileAllStream<T, D>;
+
+    fn all_stream(&self) -> Self::AllStream {
+        FileAllStream {
+            events: self.all_events.clone(),
+            committed: self.all_committed.clone(),
+            segments: self.segments.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use futures::{future, Stream, StreamExt as _};
+
+use crate::envelope::Recorded;
+use crate::store::file::log::{Location, Segments};
+use crate::store::file::BoxError;
+use crate::store::read::{MaybeUnknown, Subscribed};
+use crate::store::shared::tail::{self, Notify};
+use crate::store::shared::{page, SmartVec};
+use crate::store::{read, serialization, Deserializer, ReadStream, Subscribe};
+use crate::{revision, ErrorWithKind, Event};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct FileReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) events: SmartVec<Location>,
+    pub(super) committed: Notify,
+    pub(super) segments: Segments,
+    pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct ReadError {
+    kind: read::ErrorKind,
+    source: Option<BoxError>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl ErrorWithKind for ReadError {
+    type Kind = read::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl read::Error for ReadError {}
+
+impl ReadError {
+    pub(super) fn new(kind: read::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(source: std::io::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(read::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<serialization::Error> for ReadError {
+    fn from(source: serialization::Error) -> Self {
+        let kind = match source.kind() {
+            serialization::ErrorKind::Format => read::ErrorKind::Serialization,
+            serialization::ErrorKind::UnknownRevision => {
+                read::ErrorKind::UnknownRevision
+            }
+        };
+        Self { source: Some(Box::new(source)), ..Self::new(kind) }
+    }
+}
+
+type ReadResult<T> = Result<T, ReadError>;
+
+/// A recorded event, as read before it's converted to its newest revision.
+type Unconverted<T> = MaybeUnknown<Recorded<revision::OldOrNew<T>>>;
+
+/// Reads the event at the given location and deserializes it, handling an
+/// unknown revision according to `policy`.
+///
+/// Returns [`None`] when the event should be skipped.
+pub(super) fn read_event<D>(
+    segments: &Segments,
+    deserializer: &D,
+    policy: &read::UnknownRevisionPolicy,
+    location: Location,
+) -> Option<ReadResult<Unconverted<D::Event>>>
+where
+    D: Deserializer<SerializedEvent = Vec<u8>>,
+{
+    let event = match segments.read(location) {
+        Ok(event) => event,
+        Err(err) => return Some(Err(err.into())),
+    };
+    Some(policy.deserialize(deserializer, event)?.map_err(ReadError::from))
+}
+
+impl<T, D> ReadStream for FileReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    type Event = T;
+    type Error = ReadError;
+
+    async fn read_unconverted_recorded(
+        &mut self,
+        options: read::Options,
+    ) -> ReadResult<
+        impl Stream<
+            Item = ReadResult<MaybeUnknown<Recorded<revision::OldOrNew<T>>>>,
+        >,
+    > {
+        let len = self.events.read().await.len();
+        let last_commit_number = len.checked_sub(1).map(page::commit_number);
+        let Some(range) = options.range(last_commit_number) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let segments = self.segments.clone();
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = page::read(
+            self.events.clone(),
+            range,
+            options.direction,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(_, location)| {
+            future::ready(read_event(
+                &segments,
+                &deserializer,
+                &policy,
+                location,
+            ))
+        }))
+    }
+
+    fn unknown_revision_policy(&self) -> &read::UnknownRevisionPolicy {
+        &self.unknown_revision_policy
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+impl<T, D> Subscribe for FileReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    async fn subscribe_unconverted(
+        &mut self,
+        position: read::Position,
+    ) -> ReadResult<
+        impl futures::Stream<
+                Item = ReadResult<
+                    Subscribed<MaybeUnknown<revision::OldOrNew<T>>>,
+                >,
+            > + Send,
+    > {
+        let start = match position {
+            read::Position::First => 0,
+            read::Position::Last => {
+                self.events.read().await.len().saturating_sub(1)
+            }
+            read::Position::CommitNumber(number) => number as usize,
+        };
+        let segments = self.segments.clone();
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = tail::tail(
+            self.events.clone(),
+            self.committed.clone(),
+            start,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(index, location)| {
+            let commit_number = page::commit_number(index);
+            let subscribed = read_event(
+                &segments,
+                &deserializer,
+                &policy,
+                location,
+            )
+            .map(|recorded| {
+                Ok((commit_number, recorded?.map(|recorded| recorded.event)))
+            });
+            future::ready(subscribed)
+        }))
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, PoisonError};
+use std::time::SystemTime;
+
+use crate::envelope::{Envelope, Recorded};
+use crate::store::clock::Clock;
+use crate::store::file::log::{Commit, Location, Writer};
+use crate::store::file::{BoxError, SyncPolicy};
+use crate::store::shared::idempotency::{CommittedIds, SharedCommittedIds};
+use crate::store::shared::tail::Notify;
+use crate::store::shared::{page, GlobalEntry, GlobalLog, SmartVec};
+use crate::store::{
+    serialization,
+    write,
+    CommitNumber,
+    Serializer,
+    WriteStream,
+};
+use crate::{revision, ErrorWithKind, Event};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct FileWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    pub(super) id: T::StreamId,
+    pub(super) events: SmartVec<Location>,
+    pub(super) committed: Notify,
+    pub(super) committed_ids: SharedCommittedIds,
+    pub(super) idempotency_window: usize,
+    pub(super) all_events: GlobalLog<T::StreamId, Location>,
+    pub(super) all_committed: Notify,
+    pub(super) writer: Arc<futures_locks::Mutex<Writer>>,
+    pub(super) sync_policy: SyncPolicy,
+    pub(super) segment_size: u64,
+    pub(super) serializer: S,
+    pub(super) clock: Arc<dyn Clock>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct WriteError {
+    kind: write::ErrorKind,
+    source: Option<BoxError>,
+    last_commit_number: Option<CommitNumber>,
+    backtrace: std::backtrace::Backtrace,
+}
+
+impl WriteError {
+    fn new(kind: write::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            last_commit_number: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+}
+
+impl From<serialization::Error> for WriteError {
+    fn from(source: serialization::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Serialization)
+        }
+    }
+}
+
+impl From<std::io::Error> for WriteError {
+    fn from(source: std::io::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Other)
+        }
+    }
+}
+
+impl From<write::CheckError> for WriteError {
+    fn from(err: write::CheckError) -> Self {
+        match err {
+            write::CheckError::StreamFull => {
+                Self::new(write::ErrorKind::StreamFull)
+            }
+            write::CheckError::ConditionNotMet { last_commit_number } => Self {
+                last_commit_number,
+                ..Self::new(write::ErrorKind::ConditionNotMet)
+            },
+        }
+    }
+}
+
+impl ErrorWithKind for WriteError {
+    type Kind = write::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+impl write::Error for WriteError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        self.last_commit_number
+    }
+}
+
+type CommitResult<T> = Result<T, WriteError>;
+
+impl<T, S> WriteStream for FileWriteStream<T, S>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    type Event = T;
+    type Error = WriteError;
+
+    async fn commit_old_or_new_envelope(
+        &mut self,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
+        condition: write::Condition,
+    ) -> CommitResult<CommitNumber> {
+        let commit_number = self.append(vec![envelope], condition).await?;
+        Ok(commit_number.expect("a single event was committed"))
+    }
+
+    fn commit_many_envelopes<'a>(
+        &mut self,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
+        condition: write::Condition,
+    ) -> impl Future<Output = CommitResult<Option<CommitNumber>>> + Send {
+        let envelopes: Vec<_> = envelopes
+            .into_iter()
+            .map(|envelope| envelope.map(revision::OldOrNewRef::New))
+            .collect();
+        self.append(envelopes, condition)
+    }
+}
+
+impl<T, S> FileWriteStream<T, S>
+where
+    T: Event,
+    T::StreamId: Display,
+    S: Serializer<Event = T, SerializedEvent = Vec<u8>>,
+{
+    /// Timestamps, serializes and appends the given events to the log, given
+    /// the provided condition holds, and then indexes them.
+    ///
+    /// The events are appended as a single record, and are flushed to disk
+    /// according to the store's [`SyncPolicy`] before they're indexed (and
+    /// become visible to reads).
+    async fn append(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        if envelopes.is_empty() {
+            return Ok(None);
+        }
+        let mut events = self.events.write().await;
+        let ids: Vec<_> =
+            envelopes.iter().map(|envelope| envelope.metadata.id).collect();
+        if write::has_duplicate_ids(&ids) {
+            return Err(WriteError::new(write::ErrorKind::DuplicateEventId));
+        }
+        let lookup = self.committed_ids().lookup(&ids);
+        match lookup {
+            write::Lookup::New => {}
+            write::Lookup::Committed(commit_number) => {
+                return Ok(Some(commit_number));
+            }
+            write::Lookup::Partial => {
+                return Err(WriteError::new(
+                    write::ErrorKind::PartiallyCommitted,
+                ));
+            }
+        }
+        let last_commit_number =
+            events.len().checked_sub(1).map(page::commit_number);
+        let commit_number = condition.check(last_commit_number, ids.len())?;
+        // timestamps are taken while the stream is locked, so that they're
+        // ordered the same way as commit numbers
+        let serialized_events = self.serialize(envelopes, self.clock.now())?;
+        let stream_id = self.id.to_string();
+        let commit = Commit {
+            stream_id: &stream_id,
+            commit_number,
+            ids: &ids,
+            events: &serialized_events,
+        };
+        // the writer and the global log are locked while the stream is still
+        // locked, so that events are ordered the same way in the log and in
+        // both indices, and so that nothing is awaited once the record is
+        // appended (a commit that's dropped after its record is appended
+        // would otherwise never be indexed)
+        let mut writer = self.writer.lock().await;
+        let mut all_events = self.all_events.write().await;
+        let locations =
+            writer.append(&commit, self.sync_policy, self.segment_size)?;
+        drop(writer);
+        all_events.extend(locations.iter().zip(commit_number..).map(
+            |(location, commit_number)| GlobalEntry {
+                stream_id: self.id.clone(),
+                commit_number,
+                event: *location,
+            },
+        ));
+        drop(all_events);
+        events.extend(locations);
+        self.committed_ids().insert(
+            ids.into_iter().zip(commit_number..),
+            self.idempotency_window,
+        );
+        drop(events);
+        self.committed.notify(usize::MAX);
+        self.all_committed.notify(usize::MAX);
+        Ok(Some(commit_number))
+    }
+
+    /// Serializes the given events, recording them with the given timestamp.
+    fn serialize(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        timestamp: SystemTime,
+    ) -> CommitResult<Vec<Vec<u8>>> {
+        envelopes
+            .into_iter()
+            .map(|Envelope { event, metadata }| {
+                self.serializer
+                    .serialize(Recorded { event, metadata, timestamp })
+                    .map_err(WriteError::from)
+            })
+            .collect()
+    }
+
+    fn committed_ids(&self) -> std::sync::MutexGuard<'_, CommittedIds> {
+        // the lock is never held across an await or a panicking call, so it
+        // can't be poisoned
+        self.committed_ids.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
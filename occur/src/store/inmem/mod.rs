@@ -1,35 +1,69 @@
-use std::collections::HashMap;
 use std::sync::Arc;
 
-use futures_locks::RwLock;
 pub use read::ReadError;
-pub use serialization::no_serialization;
+pub use serialization::{no_serialization, NoSerializer};
+pub use snapshot::{InmemSnapshotStore, NoSnapshotSerializer};
+pub use transaction::TransactionError;
 pub use write::WriteError;
 
+use crate::store::clock::{Clock, SystemClock};
+pub use crate::store::inmem::all::InmemAllStream;
 use crate::store::inmem::read::InmemReadStream;
 use crate::store::inmem::write::InmemWriteStream;
+use crate::store::read::UnknownRevisionPolicy;
 use crate::store::serialization::Serialization;
-use crate::store::{Deserializer, Serializer};
+use crate::store::shared::sharded::ShardedMap;
+use crate::store::shared::tail::Notify;
+use crate::store::shared::{
+    trim_committed_ids,
+    GlobalLog,
+    SmartVec,
+    StreamEvents,
+};
+pub use crate::store::shared::{
+    DEFAULT_IDEMPOTENCY_WINDOW,
+    DEFAULT_READ_PAGE_SIZE,
+};
+use crate::store::transaction::Transaction;
+use crate::store::{
+    CommitNumber,
+    Deserializer,
+    GlobalStore,
+    Serializer,
+    TransactionalStore,
+};
 use crate::{Event, Store};
 
+mod all;
 mod read;
 mod serialization;
+mod snapshot;
+mod transaction;
 mod write;
 
-type SmartVec<T> = Arc<RwLock<Vec<T>>>;
-
+/// An event store that holds all events in memory.
+///
+/// Cloning the store is cheap, and all clones share the same events. Streams
+/// are kept in a sharded map, so that streams can be opened concurrently from
+/// many tasks.
 #[allow(clippy::module_name_repetitions)]
-#[derive(Default)]
 pub struct InmemStore<T, S, D>
 where
     T: Event,
     S: Serializer<Event = T>,
     D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
-    S::SerializedEvent: Clone + Send + Sync,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
 {
-    events_by_stream_id: HashMap<T::StreamId, SmartVec<S::SerializedEvent>>,
+    events_by_stream_id:
+        Arc<ShardedMap<T::StreamId, StreamEvents<S::SerializedEvent>>>,
+    all_events: GlobalLog<T::StreamId, S::SerializedEvent>,
+    all_committed: Notify,
     serializer: S,
     deserializer: D,
+    clock: Arc<dyn Clock>,
+    idempotency_window: usize,
+    read_page_size: usize,
+    unknown_revision_policy: UnknownRevisionPolicy,
 }
 
 impl<T, S, D> InmemStore<T, S, D>
@@ -37,11 +71,105 @@ where
     T: Event,
     S: Serializer<Event = T>,
     D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
-    S::SerializedEvent: Clone + Send + Sync,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
 {
     pub fn new(serialization: Serialization<S, D>) -> Self {
         let Serialization { serializer, deserializer } = serialization;
-        Self { events_by_stream_id: HashMap::new(), serializer, deserializer }
+        Self {
+            events_by_stream_id: Arc::default(),
+            all_events: SmartVec::default(),
+            all_committed: Notify::default(),
+            serializer,
+            deserializer,
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            read_page_size: DEFAULT_READ_PAGE_SIZE,
+            unknown_revision_policy: UnknownRevisionPolicy::default(),
+        }
+    }
+
+    /// Replaces the clock used to timestamp committed events (which is
+    /// [`SystemClock`] by default).
+    #[must_use]
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Sets the number of recently committed event IDs that are remembered
+    /// per stream, for the purpose of making commits idempotent (which is
+    /// [`DEFAULT_IDEMPOTENCY_WINDOW`] by default).
+    ///
+    /// Re-committing an event whose ID was forgotten commits it again. A
+    /// window of 0 disables idempotency altogether. IDs that were already
+    /// remembered beyond the window are forgotten.
+    #[must_use]
+    pub fn with_idempotency_window(self, idempotency_window: usize) -> Self {
+        trim_committed_ids(&self.events_by_stream_id, idempotency_window);
+        Self { idempotency_window, ..self }
+    }
+
+    /// Sets the maximum number of events that are read at once (which is
+    /// [`DEFAULT_READ_PAGE_SIZE`] by default).
+    ///
+    /// Reads and subscriptions only hold the lock of a stream while reading a
+    /// single page, and deserialize events as they are consumed.
+    #[must_use]
+    pub fn with_read_page_size(self, read_page_size: usize) -> Self {
+        Self { read_page_size, ..self }
+    }
+
+    /// Sets the policy with which read and all streams of the store handle
+    /// events of unknown revisions (which is [`UnknownRevisionPolicy::Fail`]
+    /// by default).
+    ///
+    /// The policy can also be set per stream (see
+    /// [`ReadStream::set_unknown_revision_policy`]).
+    ///
+    /// [`ReadStream::set_unknown_revision_policy`]:
+    /// crate::store::ReadStream::set_unknown_revision_policy
+    #[must_use]
+    pub fn with_unknown_revision_policy(
+        self,
+        unknown_revision_policy: UnknownRevisionPolicy,
+    ) -> Self {
+        Self { unknown_revision_policy, ..self }
+    }
+}
+
+impl<T, S, D> Clone for InmemStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            events_by_stream_id: self.events_by_stream_id.clone(),
+            all_events: self.all_events.clone(),
+            all_committed: self.all_committed.clone(),
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+            clock: self.clock.clone(),
+            idempotency_window: self.idempotency_window,
+            read_page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+        }
+    }
+}
+
+impl<T, S, D> Default for InmemStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T> + Default,
+    D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent> + Default,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new(Serialization {
+            serializer: S::default(),
+            deserializer: D::default(),
+        })
     }
 }
 
@@ -50,25 +178,77 @@ where
     T: Event,
     S: Serializer<Event = T>,
     D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
-    S::SerializedEvent: Clone + Send + Sync,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
 {
     type Event = T;
     type WriteStream = InmemWriteStream<T, S>;
     type ReadStream = InmemReadStream<T, D>;
 
-    fn write_stream(&mut self, id: T::StreamId) -> Self::WriteStream {
-        let events = self.events_by_stream_id.entry(id).or_default();
+    fn write_stream(&self, id: T::StreamId) -> Self::WriteStream {
+        let stream = self.events_by_stream_id.get_or_default(id.clone());
         InmemWriteStream {
-            events: events.clone(),
+            id,
+            events: stream.events,
+            committed: stream.committed,
+            committed_ids: stream.committed_ids,
+            idempotency_window: self.idempotency_window,
+            all_events: self.all_events.clone(),
+            all_committed: self.all_committed.clone(),
             serializer: self.serializer.clone(),
+            clock: self.clock.clone(),
         }
     }
 
-    fn read_stream(&mut self, id: T::StreamId) -> Self::ReadStream {
-        let events = self.events_by_stream_id.entry(id).or_default();
+    fn read_stream(&self, id: T::StreamId) -> Self::ReadStream {
+        let stream = self.events_by_stream_id.get_or_default(id);
         InmemReadStream {
-            events: events.clone(),
+            events: stream.events,
+            committed: stream.committed,
             deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
         }
     }
 }
+
+impl<T, S, D> GlobalStore for InmemStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    type AllStream = InmemAllStream<T, D>;
+
+    fn all_stream(&self) -> Self::AllStream {
+        InmemAllStream {
+            events: self.all_events.clone(),
+            committed: self.all_committed.clone(),
+            deserializer: self.deserializer.clone(),
+            page_size: self.read_page_size,
+            unknown_revision_policy: self.unknown_revision_policy.clone(),
+        }
+    }
+}
+
+impl<T, S, D> TransactionalStore for InmemStore<T, S, D>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    D: Deserializer<Event = T, SerializedEvent = S::SerializedEvent>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    type TransactionError = TransactionError;
+
+    async fn commit_transaction(
+        &self,
+        transaction: Transaction<'_, T>,
+    ) -> Result<Vec<Option<CommitNumber>>, TransactionError> {
+        let appends = transaction
+            .into_appends()
+            .into_iter()
+            .map(|append| (self.write_stream(append.stream_id.clone()), append))
+            .collect();
+        transaction::commit(&self.all_events, &*self.clock, appends).await
+    }
+}
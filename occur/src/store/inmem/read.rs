@@ -1,5 +1,10 @@
-use crate::store::inmem::SmartVec;
-use crate::store::{read, Deserializer, ReadStream};
+use futures::{future, Stream, StreamExt as _};
+
+use crate::envelope::Recorded;
+use crate::store::read::{MaybeUnknown, Subscribed};
+use crate::store::shared::tail::{self, Notify};
+use crate::store::shared::{page, SmartVec};
+use crate::store::{read, serialization, Deserializer, ReadStream, Subscribe};
 use crate::{revision, ErrorWithKind, Event};
 
 #[derive(Clone)]
@@ -7,10 +12,13 @@ pub struct InmemReadStream<T, D>
 where
     T: Event,
     D: Deserializer<Event = T>,
-    D::SerializedEvent: Clone + Send + Sync,
+    D::SerializedEvent: Clone + Send + Sync + 'static,
 {
     pub(super) events: SmartVec<D::SerializedEvent>,
+    pub(super) committed: Notify,
     pub(super) deserializer: D,
+    pub(super) page_size: usize,
+    pub(super) unknown_revision_policy: read::UnknownRevisionPolicy,
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -18,6 +26,7 @@ where
 #[error("{kind}")]
 pub struct ReadError {
     kind: read::ErrorKind,
+    source: Option<serialization::Error>,
     backtrace: std::backtrace::Backtrace,
 }
 
@@ -26,49 +35,138 @@ impl ErrorWithKind for ReadError {
     fn kind(&self) -> Self::Kind { self.kind }
 }
 
+impl read::Error for ReadError {}
+
+impl ReadError {
+    pub(super) fn new(kind: read::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+}
+
+impl From<serialization::Error> for ReadError {
+    fn from(source: serialization::Error) -> Self {
+        let kind = match source.kind() {
+            serialization::ErrorKind::Format => read::ErrorKind::Serialization,
+            serialization::ErrorKind::UnknownRevision => {
+                read::ErrorKind::UnknownRevision
+            }
+        };
+        Self { source: Some(source), ..Self::new(kind) }
+    }
+}
+
 type ReadResult<T> = Result<T, ReadError>;
 
+/// A recorded event, as read before it's converted to its newest revision.
+type Unconverted<T> = MaybeUnknown<Recorded<revision::OldOrNew<T>>>;
+
+/// Deserializes an event, handling an unknown revision according to `policy`.
+///
+/// Returns [`None`] when the event should be skipped.
+pub(super) fn deserialize<D>(
+    deserializer: &D,
+    policy: &read::UnknownRevisionPolicy,
+    event: D::SerializedEvent,
+) -> Option<ReadResult<Unconverted<D::Event>>>
+where
+    D: Deserializer,
+    D::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    Some(policy.deserialize(deserializer, event)?.map_err(ReadError::from))
+}
+
 impl<T, D> ReadStream for InmemReadStream<T, D>
 where
     T: Event,
     D: Deserializer<Event = T>,
-    D::SerializedEvent: Clone + Send + Sync,
+    D::SerializedEvent: Clone + Send + Sync + 'static,
 {
     type Event = T;
     type Error = ReadError;
 
-    async fn read_unconverted(
+    async fn read_unconverted_recorded(
         &mut self,
         options: read::Options,
-    ) -> ReadResult<impl futures::Stream<Item = revision::OldOrNew<T>>> {
-        let events = self.events.read().await;
-        let start = match options.position {
+    ) -> ReadResult<
+        impl Stream<
+            Item = ReadResult<MaybeUnknown<Recorded<revision::OldOrNew<T>>>>,
+        >,
+    > {
+        let len = self.events.read().await.len();
+        let last_commit_number = len.checked_sub(1).map(page::commit_number);
+        let Some(range) = options.range(last_commit_number) else {
+            return Err(ReadError::new(read::ErrorKind::CommitNotFound));
+        };
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = page::read(
+            self.events.clone(),
+            range,
+            options.direction,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(_, event)| {
+            future::ready(deserialize(&deserializer, &policy, event))
+        }))
+    }
+
+    fn unknown_revision_policy(&self) -> &read::UnknownRevisionPolicy {
+        &self.unknown_revision_policy
+    }
+
+    fn set_unknown_revision_policy(
+        &mut self,
+        policy: read::UnknownRevisionPolicy,
+    ) {
+        self.unknown_revision_policy = policy;
+    }
+}
+
+impl<T, D> Subscribe for InmemReadStream<T, D>
+where
+    T: Event,
+    D: Deserializer<Event = T>,
+    D::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    async fn subscribe_unconverted(
+        &mut self,
+        position: read::Position,
+    ) -> ReadResult<
+        impl futures::Stream<
+                Item = ReadResult<
+                    Subscribed<MaybeUnknown<revision::OldOrNew<T>>>,
+                >,
+            > + Send,
+    > {
+        let start = match position {
             read::Position::First => 0,
-            read::Position::Last => events.len() - 1,
+            read::Position::Last => {
+                self.events.read().await.len().saturating_sub(1)
+            }
             read::Position::CommitNumber(number) => number as usize,
         };
-        if start >= events.len() {
-            return Err(ReadError {
-                kind: read::ErrorKind::CommitNotFound,
-                backtrace: std::backtrace::Backtrace::capture(),
-            });
-        }
-        let limit = options.limit.unwrap_or(usize::MAX);
-        let deserialized_events: Vec<_> = match options.direction {
-            read::Direction::Forward => events[start..]
-                .iter()
-                .take(limit)
-                .cloned()
-                .map(|event| self.deserializer.deserialize(event))
-                .collect(),
-            read::Direction::Backward => events[0..start]
-                .iter()
-                .rev()
-                .take(limit)
-                .cloned()
-                .map(|event| self.deserializer.deserialize(event))
-                .collect(),
-        };
-        Ok(futures::stream::iter(deserialized_events))
+        let deserializer = self.deserializer.clone();
+        let policy = self.unknown_revision_policy.clone();
+        let events = tail::tail(
+            self.events.clone(),
+            self.committed.clone(),
+            start,
+            self.page_size,
+        );
+        Ok(events.filter_map(move |(index, event)| {
+            let commit_number = page::commit_number(index);
+            let subscribed =
+                deserialize(&deserializer, &policy, event).map(|recorded| {
+                    Ok((
+                        commit_number,
+                        recorded?.map(|recorded| recorded.event),
+                    ))
+                });
+            future::ready(subscribed)
+        }))
     }
 }
@@ -1,6 +1,7 @@
 use std::marker::PhantomData;
 
-use crate::store::serialization::Serialization;
+use crate::envelope::Recorded;
+use crate::store::serialization::{self, Serialization};
 use crate::store::{Deserializer, Serializer};
 use crate::{revision, Event};
 
@@ -25,24 +26,25 @@ impl<T: Event> NoSerializer<T> {
 
 impl<T: Event> Serializer for NoSerializer<T> {
     type Event = T;
-    type SerializedEvent = revision::OldOrNew<T>;
+    type SerializedEvent = Recorded<revision::OldOrNew<T>>;
 
     fn serialize(
         &self,
-        event: revision::OldOrNewRef<Self::Event>,
-    ) -> Self::SerializedEvent {
-        event.to_owned()
+        recorded: Recorded<revision::OldOrNewRef<Self::Event>>,
+    ) -> Result<Self::SerializedEvent, serialization::Error> {
+        Ok(recorded.map(revision::OldOrNewRef::to_owned))
     }
 }
 
 impl<T: Event> Deserializer for NoSerializer<T> {
     type Event = T;
-    type SerializedEvent = revision::OldOrNew<T>;
+    type SerializedEvent = Recorded<revision::OldOrNew<T>>;
 
     fn deserialize(
         &self,
         event: Self::SerializedEvent,
-    ) -> revision::OldOrNew<Self::Event> {
-        event
+    ) -> Result<Recorded<revision::OldOrNew<Self::Event>>, serialization::Error>
+    {
+        Ok(event)
     }
 }
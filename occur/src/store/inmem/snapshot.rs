@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::store::serialization;
+use crate::store::snapshot::{Serializer, Snapshot, SnapshotStore};
+use crate::{Entity, Event};
+
+/// An in-memory [`SnapshotStore`], which holds snapshotted entities as
+/// serialized by a snapshot [`Serializer`].
+///
+/// Use [`NoSnapshotSerializer`] to hold snapshotted entities as-is.
+#[allow(clippy::module_name_repetitions)]
+pub struct InmemSnapshotStore<T: Event, S: Serializer> {
+    snapshots: HashMap<T::StreamId, Snapshot<S::SerializedEntity>>,
+    serializer: S,
+    event: PhantomData<fn() -> T>,
+}
+
+impl<T: Event, S: Serializer> InmemSnapshotStore<T, S> {
+    #[must_use]
+    pub fn new(serializer: S) -> Self {
+        Self { snapshots: HashMap::new(), serializer, event: PhantomData }
+    }
+}
+
+impl<T, S> SnapshotStore for InmemSnapshotStore<T, S>
+where
+    T: Event,
+    S: Serializer,
+    S::Entity: Entity<T> + Clone + Send + Sync,
+    S::SerializedEntity: Clone + Send + Sync,
+{
+    type Event = T;
+    type Entity = S::Entity;
+    type Error = serialization::Error;
+
+    async fn load(
+        &mut self,
+        id: &T::StreamId,
+    ) -> Result<Option<Snapshot<S::Entity>>, Self::Error> {
+        self.snapshots
+            .get(id)
+            .cloned()
+            .map(|snapshot| {
+                snapshot.try_map(|entity| self.serializer.deserialize(entity))
+            })
+            .transpose()
+    }
+
+    async fn save(
+        &mut self,
+        id: &T::StreamId,
+        snapshot: &Snapshot<S::Entity>,
+    ) -> Result<(), Self::Error> {
+        let snapshot = Snapshot {
+            commit_number: snapshot.commit_number,
+            schema_version: snapshot.schema_version,
+            entity: self.serializer.serialize(&snapshot.entity)?,
+        };
+        self.snapshots.insert(id.clone(), snapshot);
+        Ok(())
+    }
+
+    async fn delete(&mut self, id: &T::StreamId) -> Result<(), Self::Error> {
+        self.snapshots.remove(id);
+        Ok(())
+    }
+}
+
+/// A snapshot [`Serializer`] that keeps entities as-is.
+#[allow(clippy::module_name_repetitions)]
+pub struct NoSnapshotSerializer<E>(PhantomData<fn() -> E>);
+
+impl<E> NoSnapshotSerializer<E> {
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self { Self(PhantomData) }
+}
+
+impl<E> Clone for NoSnapshotSerializer<E> {
+    fn clone(&self) -> Self { Self::new() }
+}
+
+impl<E: Clone> Serializer for NoSnapshotSerializer<E> {
+    type Entity = E;
+    type SerializedEntity = E;
+
+    fn serialize(&self, entity: &E) -> Result<E, serialization::Error> {
+        Ok(entity.clone())
+    }
+
+    fn deserialize(&self, entity: E) -> Result<E, serialization::Error> {
+        Ok(entity)
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::envelope::EventId;
+use crate::store::clock::Clock;
+use crate::store::inmem::write::{Check, InmemWriteStream};
+use crate::store::inmem::WriteError;
+use crate::store::shared::GlobalLog;
+use crate::store::transaction::{self, Append};
+use crate::store::{write, CommitNumber, Serializer};
+use crate::{revision, ErrorWithKind, Event};
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, thiserror::Error)]
+#[error("append #{index} failed: {source}")]
+pub struct TransactionError {
+    index: usize,
+    source: WriteError,
+}
+
+impl ErrorWithKind for TransactionError {
+    type Kind = write::ErrorKind;
+    fn kind(&self) -> Self::Kind { self.source.kind() }
+}
+
+impl write::Error for TransactionError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        write::Error::last_commit_number(&self.source)
+    }
+}
+
+impl transaction::Error for TransactionError {
+    fn append_index(&self) -> Option<usize> { Some(self.index) }
+}
+
+type TransactionResult<T> = Result<T, TransactionError>;
+
+/// Commits each append to its write stream, or none of them.
+///
+/// All streams must record their events in the given global log, and events
+/// are timestamped by the given clock.
+///
+/// # Panics
+///
+/// When any of the streams records its events in another global log.
+pub(super) async fn commit<T, S>(
+    all_events: &GlobalLog<T::StreamId, S::SerializedEvent>,
+    clock: &dyn Clock,
+    appends: Vec<(InmemWriteStream<T, S>, Append<'_, T>)>,
+) -> TransactionResult<Vec<Option<CommitNumber>>>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    // the transaction is only atomic if all of its events are recorded under
+    // the same lock of the global log
+    assert!(
+        appends
+            .iter()
+            .all(|(stream, _)| Arc::ptr_eq(&stream.all_events, all_events)),
+        "all streams of a transaction belong to the same store",
+    );
+
+    // streams are always locked in the same order (by address), so that
+    // concurrent transactions can't deadlock
+    let mut addresses: Vec<_> =
+        appends.iter().map(|(stream, _)| stream.address()).collect();
+    addresses.sort_unstable();
+    addresses.dedup();
+    let lock_index = |stream: &InmemWriteStream<T, S>| {
+        addresses
+            .binary_search(&stream.address())
+            .expect("all streams of the transaction are locked")
+    };
+    let mut locked = Vec::with_capacity(addresses.len());
+    for &address in &addresses {
+        let (stream, _) = appends
+            .iter()
+            .find(|(stream, _)| stream.address() == address)
+            .expect("address was taken from one of the streams");
+        locked.push(stream.events.write().await);
+    }
+
+    // all appends are checked before any of them is recorded, with appends to
+    // the same stream accounting for the events of previous ones
+    let mut n_pending = vec![0; locked.len()];
+    let mut checked = Vec::with_capacity(appends.len());
+    for (index, (stream, append)) in appends.iter().enumerate() {
+        let ids: Vec<_> = append
+            .envelopes
+            .iter()
+            .map(|envelope| envelope.metadata.id)
+            .collect();
+        if ids.is_empty() {
+            checked.push((None, ids));
+            continue;
+        }
+        let i = lock_index(stream);
+        let n_events = locked[i].len() + n_pending[i];
+        let check = stream
+            .check(&ids, n_events, append.condition)
+            .map_err(|source| TransactionError { index, source })?;
+        if matches!(check, Check::Append(_)) {
+            n_pending[i] += ids.len();
+        }
+        checked.push((Some(check), ids));
+    }
+
+    // all events of the transaction share the same timestamp, taken while all
+    // streams are locked
+    let timestamp = clock.now();
+    // events are serialized before any of them is recorded as well, so that a
+    // serialization error leaves all streams untouched
+    let mut staged = Vec::with_capacity(appends.len());
+    for (index, ((stream, append), (check, ids))) in
+        appends.iter().zip(checked).enumerate()
+    {
+        staged.push(match check {
+            None => Staged::Done(None),
+            Some(Check::Committed(commit_number)) => {
+                Staged::Done(Some(commit_number))
+            }
+            Some(Check::Append(commit_number)) => {
+                let envelopes = append
+                    .envelopes
+                    .iter()
+                    .map(|envelope| {
+                        envelope
+                            .as_ref()
+                            .map(|&event| revision::OldOrNewRef::New(event))
+                    })
+                    .collect();
+                let events = stream
+                    .serialize(envelopes, timestamp)
+                    .map_err(|source| TransactionError { index, source })?;
+                Staged::Append { commit_number, events, ids }
+            }
+        });
+    }
+
+    let mut all_events = all_events.write().await;
+    let mut commit_numbers = Vec::with_capacity(staged.len());
+    for ((stream, _), staged) in appends.iter().zip(staged) {
+        commit_numbers.push(match staged {
+            Staged::Done(commit_number) => commit_number,
+            Staged::Append { commit_number, events, ids } => {
+                stream.record(
+                    &mut locked[lock_index(stream)],
+                    &mut all_events,
+                    events,
+                    ids,
+                    commit_number,
+                );
+                stream.notify();
+                Some(commit_number)
+            }
+        });
+    }
+    Ok(commit_numbers)
+}
+
+/// An append of a transaction, ready to be recorded.
+enum Staged<E> {
+    /// Nothing should be recorded, the append results in the given commit
+    /// number.
+    Done(Option<CommitNumber>),
+    /// The serialized events should be recorded, starting at the given commit
+    /// number.
+    Append { commit_number: CommitNumber, events: Vec<E>, ids: Vec<EventId> },
+}
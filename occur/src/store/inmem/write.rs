@@ -1,7 +1,19 @@
 use std::future::Future;
+use std::sync::{Arc, PoisonError};
+use std::time::SystemTime;
 
-use crate::store::inmem::SmartVec;
-use crate::store::{write, CommitNumber, Serializer, WriteStream};
+use crate::envelope::{Envelope, EventId, Recorded};
+use crate::store::clock::Clock;
+use crate::store::shared::idempotency::{CommittedIds, SharedCommittedIds};
+use crate::store::shared::tail::Notify;
+use crate::store::shared::{page, GlobalEntry, GlobalLog, SmartVec};
+use crate::store::{
+    serialization,
+    write,
+    CommitNumber,
+    Serializer,
+    WriteStream,
+};
 use crate::{revision, ErrorWithKind, Event};
 
 #[derive(Clone)]
@@ -9,10 +21,17 @@ pub struct InmemWriteStream<T, S>
 where
     T: Event,
     S: Serializer<Event = T>,
-    S::SerializedEvent: Clone + Send + Sync,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
 {
+    pub(super) id: T::StreamId,
     pub(super) events: SmartVec<S::SerializedEvent>,
+    pub(super) committed: Notify,
+    pub(super) committed_ids: SharedCommittedIds,
+    pub(super) idempotency_window: usize,
+    pub(super) all_events: GlobalLog<T::StreamId, S::SerializedEvent>,
+    pub(super) all_committed: Notify,
     pub(super) serializer: S,
+    pub(super) clock: Arc<dyn Clock>,
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -20,83 +39,232 @@ where
 #[error("{kind}")]
 pub struct WriteError {
     kind: write::ErrorKind,
-    source: Option<std::num::TryFromIntError>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    last_commit_number: Option<CommitNumber>,
     backtrace: std::backtrace::Backtrace,
 }
 
+impl WriteError {
+    fn new(kind: write::ErrorKind) -> Self {
+        Self {
+            kind,
+            source: None,
+            last_commit_number: None,
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+}
+
+impl From<serialization::Error> for WriteError {
+    fn from(source: serialization::Error) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(write::ErrorKind::Serialization)
+        }
+    }
+}
+
+impl From<write::CheckError> for WriteError {
+    fn from(err: write::CheckError) -> Self {
+        match err {
+            write::CheckError::StreamFull => {
+                Self::new(write::ErrorKind::StreamFull)
+            }
+            write::CheckError::ConditionNotMet { last_commit_number } => Self {
+                last_commit_number,
+                ..Self::new(write::ErrorKind::ConditionNotMet)
+            },
+        }
+    }
+}
+
 impl ErrorWithKind for WriteError {
     type Kind = write::ErrorKind;
     fn kind(&self) -> Self::Kind { self.kind }
 }
 
+impl write::Error for WriteError {
+    fn last_commit_number(&self) -> Option<CommitNumber> {
+        self.last_commit_number
+    }
+}
+
 type CommitResult<T> = Result<T, WriteError>;
 
 impl<T, S> WriteStream for InmemWriteStream<T, S>
 where
     T: Event,
     S: Serializer<Event = T>,
-    S::SerializedEvent: Clone + Send + Sync,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
 {
     type Event = T;
     type Error = WriteError;
 
-    fn commit_old_or_new(
+    async fn commit_old_or_new_envelope(
         &mut self,
-        event: revision::OldOrNewRef<'_, Self::Event>,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
         condition: write::Condition,
-    ) -> impl Future<Output = CommitResult<CommitNumber>> + Send {
-        let serialized_event = self.serializer.serialize(event);
-        async move {
-            let mut events = self.events.write().await;
-            let commit_number = next_commit_number(events.len(), condition)?;
-            events.push(serialized_event);
-            Ok(commit_number)
-        }
+    ) -> CommitResult<CommitNumber> {
+        let commit_number = self.append(vec![envelope], condition).await?;
+        Ok(commit_number.expect("a single event was committed"))
     }
 
-    fn commit_many<'a>(
+    fn commit_many_envelopes<'a>(
         &mut self,
-        events: impl IntoIterator<Item = &'a Self::Event>,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
         condition: write::Condition,
     ) -> impl Future<Output = CommitResult<Option<CommitNumber>>> + Send {
-        let events_to_commit: Vec<_> = events
+        let envelopes: Vec<_> = envelopes
             .into_iter()
-            .map(revision::OldOrNewRef::New)
-            .map(|event| self.serializer.serialize(event))
+            .map(|envelope| envelope.map(revision::OldOrNewRef::New))
             .collect();
-        async move {
-            if events_to_commit.is_empty() {
-                return Ok(None);
-            }
-            let mut events = self.events.write().await;
-            let commit_number = next_commit_number(events.len(), condition)?;
-            events.extend(events_to_commit);
-            Ok(Some(commit_number))
+        self.append(envelopes, condition)
+    }
+}
+
+impl<T, S> InmemWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    /// Timestamps, serializes and appends the given events to the stream (and
+    /// to the global log), given the provided condition holds.
+    async fn append(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        condition: write::Condition,
+    ) -> CommitResult<Option<CommitNumber>> {
+        if envelopes.is_empty() {
+            return Ok(None);
         }
+        let mut events = self.events.write().await;
+        let ids: Vec<_> =
+            envelopes.iter().map(|envelope| envelope.metadata.id).collect();
+        let commit_number = match self.check(&ids, events.len(), condition)? {
+            Check::Append(commit_number) => commit_number,
+            Check::Committed(commit_number) => return Ok(Some(commit_number)),
+        };
+        // timestamps are taken while the stream is locked, so that they're
+        // ordered the same way as commit numbers
+        let serialized_events = self.serialize(envelopes, self.clock.now())?;
+        // the global log is locked while the stream is still locked, so that
+        // events of each stream are ordered the same way globally
+        let mut all_events = self.all_events.write().await;
+        self.record(
+            &mut events,
+            &mut all_events,
+            serialized_events,
+            ids,
+            commit_number,
+        );
+        self.notify();
+        Ok(Some(commit_number))
     }
 }
 
-fn next_commit_number(
-    n_events: usize,
-    condition: write::Condition,
-) -> CommitResult<CommitNumber> {
-    let commit_number =
-        u32::try_from(n_events).map_err(|source| WriteError {
-            kind: write::ErrorKind::StreamFull,
-            source: Some(source),
-            backtrace: std::backtrace::Backtrace::capture(),
-        })?;
-    match condition {
-        write::Condition::None => {}
-        write::Condition::AssignCommitNumber(assign_commit_number) => {
-            if commit_number != assign_commit_number {
-                return Err(WriteError {
-                    kind: write::ErrorKind::ConditionNotMet,
-                    source: None,
-                    backtrace: std::backtrace::Backtrace::capture(),
-                });
+/// The outcome of checking whether events can be appended to a stream.
+pub(super) enum Check {
+    /// The events should be appended, starting at the given commit number.
+    Append(CommitNumber),
+    /// The events were already committed, starting at the given commit number.
+    Committed(CommitNumber),
+}
+
+impl<T, S> InmemWriteStream<T, S>
+where
+    T: Event,
+    S: Serializer<Event = T>,
+    S::SerializedEvent: Clone + Send + Sync + 'static,
+{
+    /// Checks whether events with the given IDs can be appended to the stream,
+    /// given it holds `n_events` events and the provided condition holds.
+    ///
+    /// Must only be called while the stream is locked for writing.
+    pub(super) fn check(
+        &self,
+        ids: &[EventId],
+        n_events: usize,
+        condition: write::Condition,
+    ) -> CommitResult<Check> {
+        if write::has_duplicate_ids(ids) {
+            return Err(WriteError::new(write::ErrorKind::DuplicateEventId));
+        }
+        let lookup = self.committed_ids().lookup(ids);
+        match lookup {
+            write::Lookup::New => {}
+            write::Lookup::Committed(commit_number) => {
+                return Ok(Check::Committed(commit_number));
+            }
+            write::Lookup::Partial => {
+                return Err(WriteError::new(
+                    write::ErrorKind::PartiallyCommitted,
+                ));
             }
         }
+        let last_commit_number =
+            n_events.checked_sub(1).map(page::commit_number);
+        Ok(Check::Append(condition.check(last_commit_number, ids.len())?))
+    }
+
+    /// Serializes the given events, recording them with the given timestamp.
+    pub(super) fn serialize(
+        &self,
+        envelopes: Vec<Envelope<revision::OldOrNewRef<'_, T>>>,
+        timestamp: SystemTime,
+    ) -> CommitResult<Vec<S::SerializedEvent>> {
+        envelopes
+            .into_iter()
+            .map(|Envelope { event, metadata }| {
+                self.serializer
+                    .serialize(Recorded { event, metadata, timestamp })
+                    .map_err(WriteError::from)
+            })
+            .collect()
+    }
+
+    /// Appends serialized events to the stream's `events` and to the global
+    /// log, and remembers their IDs.
+    ///
+    /// Must only be called after a successful [`Self::check`], while both the
+    /// stream and the global log are locked for writing.
+    pub(super) fn record(
+        &self,
+        events: &mut Vec<S::SerializedEvent>,
+        all_events: &mut Vec<GlobalEntry<T::StreamId, S::SerializedEvent>>,
+        serialized_events: Vec<S::SerializedEvent>,
+        ids: Vec<EventId>,
+        commit_number: CommitNumber,
+    ) {
+        all_events.extend(
+            serialized_events.iter().cloned().zip(commit_number..).map(
+                |(event, commit_number)| GlobalEntry {
+                    stream_id: self.id.clone(),
+                    commit_number,
+                    event,
+                },
+            ),
+        );
+        events.extend(serialized_events);
+        self.committed_ids().insert(
+            ids.into_iter().zip(commit_number..),
+            self.idempotency_window,
+        );
+    }
+
+    /// Wakes up subscribers of the stream and of the global log.
+    pub(super) fn notify(&self) {
+        self.committed.notify(usize::MAX);
+        self.all_committed.notify(usize::MAX);
+    }
+
+    /// Returns the address of the stream's events, which uniquely identifies
+    /// the stream within its store.
+    pub(super) fn address(&self) -> usize { Arc::as_ptr(&self.events).addr() }
+
+    fn committed_ids(&self) -> std::sync::MutexGuard<'_, CommittedIds> {
+        // the lock is never held across an await or a panicking call, so it
+        // can't be poisoned
+        self.committed_ids.lock().unwrap_or_else(PoisonError::into_inner)
     }
-    Ok(commit_number)
 }
@@ -1,12 +1,20 @@
-pub use read::ReadStream;
+pub use all::AllStream;
+pub use read::{ReadStream, Subscribe};
 pub use serialization::{Deserializer, Serializer};
+pub use transaction::{Transaction, TransactionalStore};
 pub use write::{CommitNumber, WriteStream};
 
 use crate::Event;
 
+pub mod all;
+pub mod clock;
+#[cfg(feature = "file")] pub mod file;
 pub mod inmem;
 pub mod read;
 pub mod serialization;
+mod shared;
+pub mod snapshot;
+pub mod transaction;
 pub mod write;
 
 /// An event store for events of a specific types.
@@ -17,7 +25,11 @@ pub mod write;
 ///
 /// Note that a stream doesn't have to exist before writing or reading from it.
 /// A non-existent stream is equivalent to an empty stream.
-pub trait Store {
+///
+/// A store is a shared handle: cloning it is cheap, and all clones refer to
+/// the same underlying storage. Streams can be opened through a shared
+/// reference, so a single store can be used from many tasks at once.
+pub trait Store: Clone + Send + Sync {
     /// The type of events held within the store.
     type Event: Event;
 
@@ -29,13 +41,27 @@ pub trait Store {
 
     /// Returns a write stream for the given stream ID.
     fn write_stream(
-        &mut self,
+        &self,
         id: <Self::Event as Event>::StreamId,
     ) -> Self::WriteStream;
 
     /// Returns a read stream for the given stream ID.
     fn read_stream(
-        &mut self,
+        &self,
         id: <Self::Event as Event>::StreamId,
     ) -> Self::ReadStream;
 }
+
+/// An event store that can also read all of its events, across streams, in a
+/// single global order.
+///
+/// This is a separate trait from [`Store`], as not all storage backends can
+/// efficiently maintain a global order of events (e.g., when each stream is
+/// stored in its own partition). See [`all`] module documentation for details.
+pub trait GlobalStore: Store {
+    /// The type that is used to read all events of the store.
+    type AllStream: AllStream<Event = Self::Event>;
+
+    /// Returns a read-only view of all events in the store.
+    fn all_stream(&self) -> Self::AllStream;
+}
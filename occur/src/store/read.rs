@@ -1,10 +1,14 @@
+use std::any::Any;
 use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
 
 use derive_more::Display;
-use futures::{Stream, StreamExt};
+use futures::{future, Stream, TryStreamExt};
 
+use crate::envelope::Recorded;
 use crate::error::ErrorWithKind;
-use crate::store::CommitNumber;
+use crate::store::{serialization, CommitNumber, Deserializer};
 use crate::{revision, Event};
 
 /// Position of an event within an event stream.
@@ -41,8 +45,8 @@ pub struct Options {
     /// [position..=last_committed_event]
     /// ```
     ///
-    /// When direction is [`Direction::Forward`], events will be read until the
-    /// first event in the stream is reached, from new to old:
+    /// When direction is [`Direction::Backward`], events will be read until
+    /// the first event in the stream is reached, from new to old:
     /// ```text
     /// [position..=first_committed_event]
     /// ```
@@ -54,6 +58,54 @@ pub struct Options {
     pub limit: Option<usize>,
 }
 
+impl Options {
+    /// Returns the range of commit numbers that should be read from a stream
+    /// whose last event was assigned `last_commit_number`, or [`None`] if the
+    /// stream is empty or the read's position is out of bounds.
+    ///
+    /// Meant to be used by implementors of [`ReadStream`]. See [`range`].
+    #[must_use]
+    pub fn range(
+        &self,
+        last_commit_number: Option<CommitNumber>,
+    ) -> Option<Range<u64>> {
+        let last = u64::from(last_commit_number?);
+        let start = match self.position {
+            Position::First => 0,
+            Position::Last => last,
+            Position::CommitNumber(commit_number) => u64::from(commit_number),
+        };
+        range(start, last, self.direction, self.limit)
+    }
+}
+
+/// Returns the range of positions that should be read, starting at `start`.
+///
+/// Positions are either commit numbers or global positions, where `last` is
+/// the position of the last event. The range holds up to `limit` positions,
+/// in the given `direction`. Returns [`None`] if `start` is out of bounds. The
+/// range is empty when the read's limit is 0.
+#[must_use]
+pub fn range(
+    start: u64,
+    last: u64,
+    direction: Direction,
+    limit: Option<usize>,
+) -> Option<Range<u64>> {
+    if start > last {
+        return None;
+    }
+    // any limit beyond the number of positions is the same as no limit
+    let limit = limit
+        .map_or(u64::MAX, |limit| u64::try_from(limit).unwrap_or(u64::MAX));
+    Some(match direction {
+        Direction::Forward => {
+            start..start.saturating_add(limit).min(last.saturating_add(1))
+        }
+        Direction::Backward => (start + 1).saturating_sub(limit)..start + 1,
+    })
+}
+
 /// Errors that might occur when reading events from a stream.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
 pub enum ErrorKind {
@@ -62,6 +114,17 @@ pub enum ErrorKind {
     #[display("commit not found")]
     CommitNotFound,
 
+    /// An event couldn't be deserialized (e.g., because it's corrupt).
+    #[display("serialization error")]
+    Serialization,
+
+    /// An event was committed with a revision that is unknown to the reader.
+    ///
+    /// See [`crate::store::serialization::ErrorKind::UnknownRevision`] and
+    /// [`UnknownRevisionPolicy::Fail`].
+    #[display("unknown revision")]
+    UnknownRevision,
+
     /// An unexpected error occurred.
     ///
     /// Can be used by implementors of [`ReadStream`] to denote
@@ -71,18 +134,240 @@ pub enum ErrorKind {
     Other,
 }
 
+/// An error that might occur when reading events from a stream.
+pub trait Error: ErrorWithKind<Kind = ErrorKind> + Send {}
+
+/// An event that was committed with a revision that is unknown to the reader
+/// (e.g., by a newer deployment, during a rolling upgrade).
+///
+/// The event is opaque to the reader, so only its raw payload (as it's held
+/// by the store) is available.
+#[derive(Debug)]
+pub struct Unknown {
+    payload: Box<dyn Any + Send + Sync>,
+}
+
+impl Unknown {
+    /// Wraps the raw payload of an event.
+    pub fn new(payload: impl Any + Send + Sync) -> Self {
+        Self { payload: Box::new(payload) }
+    }
+
+    /// Returns the raw payload of the event, or [`None`] if the payload is not
+    /// of type `P` (which is the serialized event type of the store).
+    #[must_use]
+    pub fn payload<P: Any>(&self) -> Option<&P> { self.payload.downcast_ref() }
+}
+
+/// An event read without converting it to its newest revision, which might be
+/// of a revision that is unknown to the reader.
+///
+/// Events of unknown revisions are only read as [`MaybeUnknown::Unknown`]
+/// when the stream's policy is [`UnknownRevisionPolicy::Surface`].
+#[derive(Debug)]
+pub enum MaybeUnknown<T> {
+    /// An event of a known revision.
+    Known(T),
+    /// An event of an unknown revision.
+    Unknown(Unknown),
+}
+
+impl<T> MaybeUnknown<T> {
+    /// Maps a known event using the provided function.
+    pub fn map<F>(self, f: impl FnOnce(T) -> F) -> MaybeUnknown<F> {
+        match self {
+            Self::Known(event) => MaybeUnknown::Known(f(event)),
+            Self::Unknown(unknown) => MaybeUnknown::Unknown(unknown),
+        }
+    }
+
+    /// Returns the event if it's known, or [`None`] otherwise.
+    pub fn known(self) -> Option<T> {
+        match self {
+            Self::Known(event) => Some(event),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+/// How reads handle events that were committed with a revision that is
+/// unknown to the reader.
+///
+/// Set using [`ReadStream::set_unknown_revision_policy`].
+#[derive(Clone, Default)]
+pub enum UnknownRevisionPolicy {
+    /// The event is read as an error of kind [`ErrorKind::UnknownRevision`].
+    #[default]
+    Fail,
+
+    /// The event is skipped, and reported to the given function.
+    ///
+    /// Allows read-only consumers to keep running while events of newer
+    /// revisions are committed.
+    Skip(Arc<dyn Fn(&Unknown) + Send + Sync>),
+
+    /// The event is read as [`MaybeUnknown::Unknown`], which carries its raw
+    /// payload, by reads that don't convert events (such as
+    /// [`ReadStream::read_unconverted_recorded`]). Consumers may handle the
+    /// event, or ignore it and keep reading.
+    ///
+    /// Reads that convert events to their newest revision (such as
+    /// [`ReadStream::read`]) skip the event, as it can't be converted.
+    Surface,
+}
+
+impl UnknownRevisionPolicy {
+    /// Creates a policy that skips unknown events, reporting them to `report`.
+    pub fn skip(report: impl Fn(&Unknown) + Send + Sync + 'static) -> Self {
+        Self::Skip(Arc::new(report))
+    }
+
+    /// Deserializes an event as it's held by a store, handling an event that
+    /// was committed with an unknown revision according to the policy.
+    ///
+    /// Meant to be used by implementors of [`ReadStream`]. Returns [`None`]
+    /// when the event should be skipped.
+    pub fn deserialize<D>(
+        &self,
+        deserializer: &D,
+        event: D::SerializedEvent,
+    ) -> Option<Deserialized<D::Event>>
+    where
+        D: Deserializer,
+        D::SerializedEvent: Clone + Send + Sync + 'static,
+    {
+        // the payload is only kept when an unknown event might be handed over
+        // to the reader, as the deserializer consumes it
+        let payload = match self {
+            Self::Fail => None,
+            Self::Skip(_) | Self::Surface => Some(event.clone()),
+        };
+        let err = match deserializer.deserialize(event) {
+            Ok(recorded) => return Some(Ok(MaybeUnknown::Known(recorded))),
+            Err(err) => err,
+        };
+        let unknown = payload
+            .filter(|_| err.kind() == serialization::ErrorKind::UnknownRevision)
+            .map(Unknown::new);
+        match (self, unknown) {
+            (Self::Skip(report), Some(unknown)) => {
+                report(&unknown);
+                None
+            }
+            (Self::Surface, Some(unknown)) => {
+                Some(Ok(MaybeUnknown::Unknown(unknown)))
+            }
+            _ => Some(Err(err)),
+        }
+    }
+}
+
+/// The result of [`UnknownRevisionPolicy::deserialize`].
+pub type Deserialized<T> =
+    Result<MaybeUnknown<Recorded<revision::OldOrNew<T>>>, serialization::Error>;
+
+impl std::fmt::Debug for UnknownRevisionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fail => f.write_str("Fail"),
+            Self::Skip(_) => f.write_str("Skip"),
+            Self::Surface => f.write_str("Surface"),
+        }
+    }
+}
+
 /// An event stream from which events can be read.
 ///
 /// This is the read side of an event stream. See [`crate::store::WriteStream`]
 /// for the write side.
-#[allow(clippy::module_name_repetitions)]
+///
+/// # Consistency
+///
+/// Reads are lazy: events are fetched (and deserialized) as the returned
+/// stream is consumed, rather than all at once. Regardless, every read must
+/// observe a consistent snapshot of the event stream, as it was when the read
+/// started:
+/// - Only committed events are read, and no committed event within the read
+///   range is skipped.
+/// - Events committed after the read started are never read, even if the read
+///   range would have included them. Use [`Subscribe`] to also read events as
+///   they are committed.
+///
+/// Implementors must not block writers of the stream while a read is being
+/// consumed.
+///
+/// # Errors
+///
+/// A read fails as a whole when it can't start (e.g., with
+/// [`ErrorKind::CommitNotFound`]). Once started, each event is read as a
+/// [`Result`], as reading a specific event might fail (e.g., with
+/// [`ErrorKind::Serialization`] when the event can't be deserialized).
+///
+/// Events that were committed with a revision that is unknown to the reader
+/// are handled according to the stream's [`UnknownRevisionPolicy`].
+#[allow(clippy::module_name_repetitions, clippy::type_complexity)]
 pub trait ReadStream: Send {
     /// The type of events held within the stream.
     type Event: Event;
 
     /// The type of error that might occur when trying to commit an event.
-    type Error: ErrorWithKind<Kind = ErrorKind>;
+    type Error: Error;
+
+    /// Returns how reads and subscriptions handle events that were committed
+    /// with an unknown revision.
+    fn unknown_revision_policy(&self) -> &UnknownRevisionPolicy;
+
+    /// Sets how subsequent reads and subscriptions handle events that were
+    /// committed with an unknown revision (which is
+    /// [`UnknownRevisionPolicy::Fail`] by default).
+    fn set_unknown_revision_policy(&mut self, policy: UnknownRevisionPolicy);
 
+    #[rustfmt::skip]
+    /// Read events from the stream along with their metadata, without
+    /// converting them to their newest revision.
+    ///
+    /// Use [`Self::read_recorded`] to automatically convert the read events
+    /// (using [`revision::OldOrNew::to_new`]).
+    fn read_unconverted_recorded(
+        &mut self,
+        options: Options,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<
+                Item=Result<
+                    MaybeUnknown<Recorded<revision::OldOrNew<Self::Event>>>,
+                    Self::Error,
+                >
+            >,
+            Self::Error,
+        >
+    > + Send;
+
+    #[rustfmt::skip]
+    /// Read events from the stream along with their metadata, based on the
+    /// provided options.
+    fn read_recorded(
+        &mut self,
+        options: Options,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<Recorded<Self::Event>, Self::Error>>,
+            Self::Error,
+        >
+    > + Send {
+        let future = self.read_unconverted_recorded(options);
+        async {
+            future.await.map(|it| {
+                it.try_filter_map(|item| {
+                    future::ready(Ok(item.known().map(|recorded| {
+                        recorded.map(revision::OldOrNew::to_new)
+                    })))
+                })
+            })
+        }
+    }
+
+    #[rustfmt::skip]
     /// Read events from the stream without converting them to their newest
     /// revision.
     ///
@@ -92,11 +377,23 @@ pub trait ReadStream: Send {
         &mut self,
         options: Options,
     ) -> impl Future<
-        Output = Result<
-            impl Stream<Item = revision::OldOrNew<Self::Event>>,
+        Output=Result<
+            impl Stream<
+                Item=Result<
+                    MaybeUnknown<revision::OldOrNew<Self::Event>>,
+                    Self::Error,
+                >
+            >,
             Self::Error,
-        >,
-    > + Send;
+        >
+    > + Send {
+        let future = self.read_unconverted_recorded(options);
+        async {
+            future.await.map(|it| {
+                it.map_ok(|item| item.map(|recorded| recorded.event))
+            })
+        }
+    }
 
     #[rustfmt::skip]
     /// Read events from the stream based on the provided options.
@@ -105,12 +402,12 @@ pub trait ReadStream: Send {
         options: Options,
     ) -> impl Future<
         Output=Result<
-            impl Stream<Item=Self::Event>,
+            impl Stream<Item=Result<Self::Event, Self::Error>>,
             Self::Error,
         >
     > + Send {
-        let future = self.read_unconverted(options);
-        async { future.await.map(|it| it.map(revision::OldOrNew::to_new)) }
+        let future = self.read_recorded(options);
+        async { future.await.map(|it| it.map_ok(|recorded| recorded.event)) }
     }
 
     #[rustfmt::skip]
@@ -119,7 +416,7 @@ pub trait ReadStream: Send {
         &mut self,
     ) -> impl Future<
         Output=Result<
-            impl Stream<Item=Self::Event>,
+            impl Stream<Item=Result<Self::Event, Self::Error>>,
             Self::Error,
         >
     > + Send {
@@ -130,3 +427,71 @@ pub trait ReadStream: Send {
         })
     }
 }
+
+/// An event read by subscribing to a stream, along with its commit number.
+pub type Subscribed<T> = (CommitNumber, T);
+
+/// An event stream that can be subscribed to.
+///
+/// A subscription first yields the events that were already committed to the
+/// stream, and then keeps yielding events as they are committed, without
+/// polling. It never ends on its own; drop it to unsubscribe.
+///
+/// Events are only read from the underlying store as the subscription is
+/// polled, so a slow consumer doesn't cause events to pile up in memory.
+#[allow(clippy::type_complexity)]
+pub trait Subscribe: ReadStream {
+    #[rustfmt::skip]
+    /// Subscribe to the stream without converting events to their newest
+    /// revision.
+    ///
+    /// The subscription starts at the given `position`:
+    /// - [`Position::First`] replays all events of the stream.
+    /// - [`Position::Last`] starts at the last event committed at the time of
+    ///   subscribing (or at the first event, if the stream is empty).
+    /// - [`Position::CommitNumber`] starts at the given commit number, which
+    ///   doesn't have to be committed yet.
+    ///
+    /// Use [`Self::subscribe`] to automatically convert events (using
+    /// [`revision::OldOrNew::to_new`]).
+    fn subscribe_unconverted(
+        &mut self,
+        position: Position,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<
+                Item=Result<
+                    Subscribed<MaybeUnknown<revision::OldOrNew<Self::Event>>>,
+                    Self::Error,
+                >
+            > + Send,
+            Self::Error,
+        >
+    > + Send;
+
+    #[rustfmt::skip]
+    /// Subscribe to the stream, starting at the given `position`.
+    ///
+    /// See [`Self::subscribe_unconverted`] for details.
+    fn subscribe(
+        &mut self,
+        position: Position,
+    ) -> impl Future<
+        Output=Result<
+            impl Stream<Item=Result<Subscribed<Self::Event>, Self::Error>>
+                + Send,
+            Self::Error,
+        >
+    > + Send {
+        let future = self.subscribe_unconverted(position);
+        async {
+            future.await.map(|it| {
+                it.try_filter_map(|(commit_number, event)| {
+                    future::ready(Ok(event
+                        .known()
+                        .map(|event| (commit_number, event.to_new()))))
+                })
+            })
+        }
+    }
+}
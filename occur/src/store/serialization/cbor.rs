@@ -0,0 +1,79 @@
+//! Serialization of events as [CBOR].
+//!
+//! Events are laid out the same way as in the [`json`](super::json) format
+//! (along with their revision value), only encoded in a compact binary form.
+//!
+//! [CBOR]: https://cbor.io
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::store::serialization::tagged::{
+    format_error,
+    tagged_serialization,
+    Codec,
+    SnapshotSerializer,
+    TaggedDeserializer,
+    TaggedSerializer,
+};
+use crate::store::serialization::{Error, Serialization};
+use crate::Event;
+
+/// The CBOR format.
+#[derive(Copy, Clone, Debug)]
+pub struct Cbor;
+
+impl Codec for Cbor {
+    type Encoded = Vec<u8>;
+    type Value = ciborium::Value;
+
+    const NAME: &'static str = "CBOR";
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(format_error)?;
+        Ok(bytes)
+    }
+
+    fn decode<T: DeserializeOwned>(encoded: &Vec<u8>) -> Result<T, Error> {
+        ciborium::from_reader(encoded.as_slice()).map_err(format_error)
+    }
+
+    fn to_value<T: Serialize + ?Sized>(
+        value: &T,
+    ) -> Result<ciborium::Value, Error> {
+        ciborium::Value::serialized(value).map_err(format_error)
+    }
+
+    fn from_value<T: DeserializeOwned>(
+        value: ciborium::Value,
+    ) -> Result<T, Error> {
+        value.deserialized().map_err(format_error)
+    }
+}
+
+/// Serializes events as CBOR bytes.
+#[allow(clippy::module_name_repetitions)]
+pub type CborSerializer<T> = TaggedSerializer<Cbor, T>;
+
+/// Deserializes events from CBOR bytes that were serialized by
+/// [`CborSerializer`].
+#[allow(clippy::module_name_repetitions)]
+pub type CborDeserializer<T> = TaggedDeserializer<Cbor, T>;
+
+/// Serializes snapshotted entities as CBOR bytes.
+#[allow(clippy::module_name_repetitions)]
+pub type CborSnapshotSerializer<E> = SnapshotSerializer<Cbor, E>;
+
+/// Returns a serializer and deserializer pair that serialize events as CBOR.
+#[allow(clippy::module_name_repetitions)]
+#[must_use]
+pub fn cbor_serialization<T>(
+) -> Serialization<CborSerializer<T>, CborDeserializer<T>>
+where
+    T: Event + Serialize + DeserializeOwned,
+    T::OldRevision: Serialize + DeserializeOwned,
+    T::Value: Serialize,
+{
+    tagged_serialization()
+}
@@ -0,0 +1,336 @@
+//! Compression of serialized events.
+//!
+//! [`CompressedSerializer`] wraps any serializer that produces bytes (e.g.,
+//! [`cbor`](super::cbor) or [`msgpack`](super::msgpack)), and compresses its
+//! output. [`CompressedDeserializer`] wraps the matching deserializer, and
+//! decompresses payloads before they're deserialized.
+//!
+//! Each payload starts with a single header byte, which tells whether (and
+//! how) the rest of the payload is compressed:
+//!
+//! ```text
+//! [header][inner serializer's bytes, possibly compressed]
+//! ```
+//!
+//! Payloads smaller than the [threshold](CompressedSerializer::with_threshold)
+//! are kept uncompressed, as compressing them isn't worth the overhead. So are
+//! payloads that don't get any smaller when compressed. Either way, the
+//! deserializer decodes all payloads transparently, regardless of the
+//! [`Algorithm`] it was configured with (given the feature of the algorithm
+//! that compressed them is enabled).
+//!
+//! As a corrupt (or hostile) payload could claim to decompress to any size,
+//! the deserializer fails payloads that decompress to more than a [maximum
+//! size](CompressedDeserializer::with_max_size), rather than allocating it.
+
+#[cfg(feature = "zstd")] use std::io::Read as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::envelope::Recorded;
+use crate::revision;
+use crate::store::serialization::{Error, ErrorKind, Serialization};
+use crate::store::{Deserializer, Serializer};
+
+/// Payloads smaller than this (in bytes) are kept uncompressed by default.
+pub const DEFAULT_THRESHOLD: usize = 256;
+
+/// Payloads that decompress to more than this (in bytes) fail to be
+/// deserialized by default.
+pub const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// The default compression level of [`Algorithm::Zstd`].
+#[cfg(feature = "zstd")]
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+const UNCOMPRESSED: u8 = 0;
+#[cfg(feature = "zstd")]
+const ZSTD: u8 = 1;
+#[cfg(feature = "lz4")]
+const LZ4: u8 = 2;
+
+/// An algorithm with which payloads are compressed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Algorithm {
+    /// [Zstandard](https://facebook.github.io/zstd), with the given
+    /// compression level.
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+
+    /// [LZ4](https://lz4.org), which compresses less than
+    /// [`Algorithm::Zstd`], but faster.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Algorithm {
+    const fn header(self) -> u8 {
+        match self {
+            #[cfg(feature = "zstd")]
+            Self::Zstd { .. } => ZSTD,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => LZ4,
+        }
+    }
+
+    // only compressing with zstd might fail
+    #[allow(clippy::unnecessary_wraps)]
+    fn compress(self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Self::Zstd { level } => zstd::bulk::compress(bytes, level)
+                .map_err(|source| Error::new(ErrorKind::Format, source)),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => Ok(lz4_flex::compress_prepend_size(bytes)),
+        }
+    }
+}
+
+/// Decompresses a payload (without its header) that was compressed by the
+/// algorithm identified by `header`, failing if it decompresses to more than
+/// `max_size` bytes.
+fn decompress(
+    header: u8,
+    bytes: &[u8],
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    let too_large = || {
+        Error::new(
+            ErrorKind::Format,
+            format!("payload decompresses to more than {max_size} bytes"),
+        )
+    };
+    match header {
+        #[cfg(feature = "zstd")]
+        ZSTD => {
+            let mut decompressed = Vec::new();
+            // reads a byte past the maximum size, to tell whether it's exceeded
+            zstd::stream::read::Decoder::new(bytes)
+                .and_then(|decoder| {
+                    decoder
+                        .take(max_size as u64 + 1)
+                        .read_to_end(&mut decompressed)
+                })
+                .map_err(|source| Error::new(ErrorKind::Format, source))?;
+            if decompressed.len() > max_size {
+                return Err(too_large());
+            }
+            Ok(decompressed)
+        }
+        #[cfg(feature = "lz4")]
+        LZ4 => {
+            // the size is prepended as a little-endian u32, and is checked
+            // before it's allocated
+            let (size, compressed) = bytes
+                .split_first_chunk::<4>()
+                .map(|(size, rest)| (u32::from_le_bytes(*size) as usize, rest))
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::Format, "missing decompressed size")
+                })?;
+            if size > max_size {
+                return Err(too_large());
+            }
+            lz4_flex::decompress(compressed, size)
+                .map_err(|source| Error::new(ErrorKind::Format, source))
+        }
+        _ => Err(Error::new(
+            ErrorKind::Format,
+            format!("unsupported compression header: {header}"),
+        )),
+    }
+}
+
+/// Wraps the given serializer and deserializer pair, so that serialized events
+/// are compressed using `algorithm`.
+#[must_use]
+pub fn compressed<S, D>(
+    serialization: Serialization<S, D>,
+    algorithm: Algorithm,
+) -> Serialization<CompressedSerializer<S>, CompressedDeserializer<D>>
+where
+    S: Serializer<SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = S::Event, SerializedEvent = Vec<u8>>,
+{
+    let Serialization { serializer, deserializer } = serialization;
+    Serialization {
+        serializer: CompressedSerializer::new(serializer, algorithm),
+        deserializer: CompressedDeserializer::new(deserializer),
+    }
+}
+
+/// Metrics of a [`CompressedSerializer`], shared by all of its clones.
+#[derive(Default, Debug)]
+pub struct Metrics {
+    events: AtomicU64,
+    compressed_events: AtomicU64,
+    input_bytes: AtomicU64,
+    output_bytes: AtomicU64,
+}
+
+impl Metrics {
+    /// Returns the number of serialized events.
+    pub fn events(&self) -> u64 { self.events.load(Ordering::Relaxed) }
+
+    /// Returns the number of serialized events whose payload was compressed.
+    pub fn compressed_events(&self) -> u64 {
+        self.compressed_events.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total size (in bytes) of the payloads produced by the inner
+    /// serializer.
+    pub fn input_bytes(&self) -> u64 {
+        self.input_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total size (in bytes) of the payloads produced by the
+    /// compressed serializer, including their headers.
+    pub fn output_bytes(&self) -> u64 {
+        self.output_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the compression ratio, which is the size of the inner
+    /// serializer's payloads divided by the size of the produced payloads, or
+    /// [`None`] if no event was serialized yet.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ratio(&self) -> Option<f64> {
+        let output_bytes = self.output_bytes();
+        (output_bytes > 0)
+            .then(|| self.input_bytes() as f64 / output_bytes as f64)
+    }
+
+    fn record(&self, input_bytes: usize, output: &[u8]) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        if output[0] != UNCOMPRESSED {
+            self.compressed_events.fetch_add(1, Ordering::Relaxed);
+        }
+        self.input_bytes.fetch_add(input_bytes as u64, Ordering::Relaxed);
+        self.output_bytes.fetch_add(output.len() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Compresses the payloads produced by an inner serializer.
+///
+/// See the [module documentation](self) for details.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct CompressedSerializer<S> {
+    inner: S,
+    algorithm: Algorithm,
+    threshold: usize,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> CompressedSerializer<S>
+where
+    S: Serializer<SerializedEvent = Vec<u8>>,
+{
+    /// Wraps `inner`, compressing its payloads using `algorithm`.
+    #[must_use]
+    pub fn new(inner: S, algorithm: Algorithm) -> Self {
+        Self {
+            inner,
+            algorithm,
+            threshold: DEFAULT_THRESHOLD,
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Sets the minimal size (in bytes) of a payload for it to be compressed
+    /// (which is [`DEFAULT_THRESHOLD`] by default).
+    #[must_use]
+    pub fn with_threshold(self, threshold: usize) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// Returns the metrics of the serializer.
+    #[must_use]
+    pub fn metrics(&self) -> &Metrics { &self.metrics }
+}
+
+impl<S> Serializer for CompressedSerializer<S>
+where
+    S: Serializer<SerializedEvent = Vec<u8>>,
+{
+    type Event = S::Event;
+    type SerializedEvent = Vec<u8>;
+
+    fn serialize(
+        &self,
+        recorded: Recorded<revision::OldOrNewRef<Self::Event>>,
+    ) -> Result<Self::SerializedEvent, Error> {
+        let bytes = self.inner.serialize(recorded)?;
+        let compressed = if bytes.len() < self.threshold {
+            None
+        } else {
+            Some(self.algorithm.compress(&bytes)?)
+        };
+        let payload = match compressed {
+            Some(compressed) if compressed.len() < bytes.len() => {
+                with_header(self.algorithm.header(), &compressed)
+            }
+            _ => with_header(UNCOMPRESSED, &bytes),
+        };
+        self.metrics.record(bytes.len(), &payload);
+        Ok(payload)
+    }
+}
+
+fn with_header(header: u8, bytes: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(bytes.len() + 1);
+    payload.push(header);
+    payload.extend_from_slice(bytes);
+    payload
+}
+
+/// Decompresses payloads that were produced by a [`CompressedSerializer`],
+/// before deserializing them using an inner deserializer.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct CompressedDeserializer<D> {
+    inner: D,
+    max_size: usize,
+}
+
+impl<D> CompressedDeserializer<D>
+where
+    D: Deserializer<SerializedEvent = Vec<u8>>,
+{
+    /// Wraps `inner`, decompressing payloads before they're deserialized.
+    #[must_use]
+    pub const fn new(inner: D) -> Self {
+        Self { inner, max_size: DEFAULT_MAX_SIZE }
+    }
+
+    /// Sets the maximal size (in bytes) that a payload may decompress to
+    /// (which is [`DEFAULT_MAX_SIZE`] by default), beyond which it fails to be
+    /// deserialized with [`ErrorKind::Format`].
+    #[must_use]
+    pub fn with_max_size(self, max_size: usize) -> Self {
+        Self { max_size, ..self }
+    }
+}
+
+impl<D> Deserializer for CompressedDeserializer<D>
+where
+    D: Deserializer<SerializedEvent = Vec<u8>>,
+{
+    type Event = D::Event;
+    type SerializedEvent = Vec<u8>;
+
+    fn deserialize(
+        &self,
+        mut event: Self::SerializedEvent,
+    ) -> Result<Recorded<revision::OldOrNew<Self::Event>>, Error> {
+        let bytes = match event.first() {
+            None => {
+                return Err(Error::new(ErrorKind::Format, "empty payload"));
+            }
+            Some(&UNCOMPRESSED) => {
+                event.drain(..1);
+                event
+            }
+            Some(&header) => decompress(header, &event[1..], self.max_size)?,
+        };
+        self.inner.deserialize(bytes)
+    }
+}
@@ -0,0 +1,91 @@
+//! Serialization of events as JSON.
+//!
+//! Each recorded event is serialized as a JSON object holding the event, its
+//! metadata, commit timestamp, and revision value:
+//!
+//! ```json
+//! {
+//!   "revision": ["Renamed", 1],
+//!   "event": { "Renamed": { "new_name": "root" } },
+//!   "metadata": { "id": "...", ... },
+//!   "timestamp": { "secs_since_epoch": 1700000000, "nanos_since_epoch": 0 }
+//! }
+//! ```
+//!
+//! On deserialization, the revision value determines whether the event is
+//! deserialized as a new event or as an old revision of one (see
+//! [`revision::OldOrNew`](crate::revision::OldOrNew)).
+//!
+//! Snapshotted entities can be serialized as JSON as well, using
+//! [`JsonSnapshotSerializer`].
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::store::serialization::tagged::{
+    format_error,
+    tagged_serialization,
+    Codec,
+    SnapshotSerializer,
+    TaggedDeserializer,
+    TaggedSerializer,
+};
+use crate::store::serialization::{Error, Serialization};
+use crate::Event;
+
+/// The JSON format.
+#[derive(Copy, Clone, Debug)]
+pub struct Json;
+
+impl Codec for Json {
+    type Encoded = String;
+    type Value = serde_json::Value;
+
+    const NAME: &'static str = "JSON";
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+        serde_json::to_string(value).map_err(format_error)
+    }
+
+    fn decode<T: DeserializeOwned>(encoded: &String) -> Result<T, Error> {
+        serde_json::from_str(encoded).map_err(format_error)
+    }
+
+    fn to_value<T: Serialize + ?Sized>(
+        value: &T,
+    ) -> Result<serde_json::Value, Error> {
+        serde_json::to_value(value).map_err(format_error)
+    }
+
+    fn from_value<T: DeserializeOwned>(
+        value: serde_json::Value,
+    ) -> Result<T, Error> {
+        serde_json::from_value(value).map_err(format_error)
+    }
+}
+
+/// Serializes events as JSON strings.
+#[allow(clippy::module_name_repetitions)]
+pub type JsonSerializer<T> = TaggedSerializer<Json, T>;
+
+/// Deserializes events from JSON strings that were serialized by
+/// [`JsonSerializer`].
+#[allow(clippy::module_name_repetitions)]
+pub type JsonDeserializer<T> = TaggedDeserializer<Json, T>;
+
+/// Serializes snapshotted entities as JSON strings.
+#[allow(clippy::module_name_repetitions)]
+pub type JsonSnapshotSerializer<E> = SnapshotSerializer<Json, E>;
+
+/// Returns a serializer and deserializer pair that serialize events as JSON.
+#[allow(clippy::module_name_repetitions)]
+#[must_use]
+pub fn json_serialization<T>(
+) -> Serialization<JsonSerializer<T>, JsonDeserializer<T>>
+where
+    T: Event + Serialize + DeserializeOwned,
+    T::OldRevision: Serialize + DeserializeOwned,
+    T::Value: Serialize,
+{
+    tagged_serialization()
+}
@@ -0,0 +1,102 @@
+use derive_more::Display;
+
+use crate::envelope::Recorded;
+use crate::error::ErrorWithKind;
+use crate::{revision, Event};
+
+#[cfg(feature = "cbor")] pub mod cbor;
+#[cfg(any(feature = "zstd", feature = "lz4"))] pub mod compression;
+#[cfg(feature = "serde")] pub mod json;
+#[cfg(feature = "msgpack")] pub mod msgpack;
+#[cfg(feature = "serde")] pub mod tagged;
+
+/// Errors that might occur when serializing or deserializing events.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum ErrorKind {
+    /// The event couldn't be encoded, or its serialized form couldn't be
+    /// decoded (e.g., because it's corrupt).
+    #[display("invalid format")]
+    Format,
+
+    /// The serialized event holds a revision value that is neither a revision
+    /// of the event, nor of any of its old revisions.
+    #[display("unknown revision")]
+    UnknownRevision,
+}
+
+/// An error that might occur when serializing or deserializing events.
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct Error {
+    kind: ErrorKind,
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl Error {
+    /// Creates an error of the given kind, caused by `source`.
+    pub fn new(
+        kind: ErrorKind,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self { kind, source: source.into() }
+    }
+
+    /// Creates an error of kind [`ErrorKind::UnknownRevision`] for the given
+    /// revision value.
+    pub fn unknown_revision(revision: &impl std::fmt::Debug) -> Self {
+        Self::new(
+            ErrorKind::UnknownRevision,
+            format!("unknown revision: {revision:?}"),
+        )
+    }
+}
+
+impl ErrorWithKind for Error {
+    type Kind = ErrorKind;
+    fn kind(&self) -> Self::Kind { self.kind }
+}
+
+/// Serializes recorded events, along with their metadata, so they can be
+/// persisted by a store.
+pub trait Serializer: Clone + Send + Sync {
+    type Event: Event;
+    type SerializedEvent;
+
+    /// Serializes the given recorded event.
+    ///
+    /// # Errors
+    ///
+    /// When the event can't be represented by the serialization format.
+    fn serialize(
+        &self,
+        recorded: Recorded<revision::OldOrNewRef<Self::Event>>,
+    ) -> Result<Self::SerializedEvent, Error>;
+}
+
+/// Deserializes recorded events, along with their metadata, that were
+/// serialized by a matching [`Serializer`].
+pub trait Deserializer: Clone + Send + Sync {
+    type Event: Event;
+    type SerializedEvent;
+
+    /// Deserializes the given serialized event.
+    ///
+    /// # Errors
+    ///
+    /// - [`ErrorKind::Format`] when `event` is malformed.
+    /// - [`ErrorKind::UnknownRevision`] when `event` holds an unknown revision
+    ///   value.
+    fn deserialize(
+        &self,
+        event: Self::SerializedEvent,
+    ) -> Result<Recorded<revision::OldOrNew<Self::Event>>, Error>;
+}
+
+pub struct Serialization<S, D>
+where
+    S: Serializer,
+    D: Deserializer<Event = S::Event, SerializedEvent = S::SerializedEvent>,
+{
+    pub serializer: S,
+    pub deserializer: D,
+}
@@ -0,0 +1,83 @@
+//! Serialization of events as [MessagePack].
+//!
+//! Events are laid out the same way as in the [`json`](super::json) format
+//! (along with their revision value), only encoded in a compact binary form.
+//!
+//! [MessagePack]: https://msgpack.org
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::store::serialization::tagged::{
+    format_error,
+    tagged_serialization,
+    Codec,
+    SnapshotSerializer,
+    TaggedDeserializer,
+    TaggedSerializer,
+};
+use crate::store::serialization::{Error, Serialization};
+use crate::Event;
+
+/// The MessagePack format.
+#[derive(Copy, Clone, Debug)]
+pub struct Msgpack;
+
+impl Codec for Msgpack {
+    type Encoded = Vec<u8>;
+    type Value = rmpv::Value;
+
+    const NAME: &'static str = "MessagePack";
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+        // fields are serialized along with their names (rather than by their
+        // position), to keep the same layout as other formats
+        rmp_serde::to_vec_named(value).map_err(format_error)
+    }
+
+    fn decode<T: DeserializeOwned>(encoded: &Vec<u8>) -> Result<T, Error> {
+        rmp_serde::from_slice(encoded).map_err(format_error)
+    }
+
+    fn to_value<T: Serialize + ?Sized>(
+        value: &T,
+    ) -> Result<rmpv::Value, Error> {
+        rmpv::ext::to_value(value).map_err(format_error)
+    }
+
+    fn from_value<T: DeserializeOwned>(value: rmpv::Value) -> Result<T, Error> {
+        // rmpv's own deserializer doesn't lay out enums (nor types that
+        // serialize differently for compact formats) the same way as
+        // rmp_serde, so the value is encoded back, and decoded by rmp_serde
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &value).map_err(format_error)?;
+        rmp_serde::from_slice(&bytes).map_err(format_error)
+    }
+}
+
+/// Serializes events as MessagePack bytes.
+#[allow(clippy::module_name_repetitions)]
+pub type MsgpackSerializer<T> = TaggedSerializer<Msgpack, T>;
+
+/// Deserializes events from MessagePack bytes that were serialized by
+/// [`MsgpackSerializer`].
+#[allow(clippy::module_name_repetitions)]
+pub type MsgpackDeserializer<T> = TaggedDeserializer<Msgpack, T>;
+
+/// Serializes snapshotted entities as MessagePack bytes.
+#[allow(clippy::module_name_repetitions)]
+pub type MsgpackSnapshotSerializer<E> = SnapshotSerializer<Msgpack, E>;
+
+/// Returns a serializer and deserializer pair that serialize events as
+/// MessagePack.
+#[allow(clippy::module_name_repetitions)]
+#[must_use]
+pub fn msgpack_serialization<T>(
+) -> Serialization<MsgpackSerializer<T>, MsgpackDeserializer<T>>
+where
+    T: Event + Serialize + DeserializeOwned,
+    T::OldRevision: Serialize + DeserializeOwned,
+    T::Value: Serialize,
+{
+    tagged_serialization()
+}
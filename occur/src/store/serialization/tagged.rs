@@ -0,0 +1,281 @@
+//! The layout shared by all serde-based serialization formats.
+//!
+//! Each recorded event is serialized along with its revision value, which is
+//! used on deserialization to tell whether the event should be deserialized
+//! as a new event or as an old revision of one.
+//!
+//! The layout is implemented once by [`TaggedSerializer`] and
+//! [`TaggedDeserializer`], for any format that implements [`Codec`] (such as
+//! [`Json`](super::json::Json)). Each format's module provides aliases of
+//! these types, which are preferred over naming them directly.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::envelope::{Metadata, Recorded};
+use crate::store::serialization::{Error, ErrorKind, Serialization};
+use crate::store::{snapshot, Deserializer, Serializer};
+use crate::{revision, Event, Revision};
+
+/// A serde-based serialization format.
+///
+/// Besides encoding and decoding values, a codec converts values to and from
+/// the format's dynamically typed representation ([`Codec::Value`]), so that
+/// a serialized event can be decoded in a single pass, before it's known
+/// which type the event should be deserialized as.
+pub trait Codec: Send + Sync + 'static {
+    /// The type of encoded values.
+    type Encoded;
+
+    /// The format's dynamically typed representation of a value.
+    type Value: PartialEq + Debug + DeserializeOwned + Send + Sync;
+
+    /// The name of the format, as used in error messages.
+    const NAME: &'static str;
+
+    /// Encodes the given value.
+    ///
+    /// # Errors
+    ///
+    /// When the value can't be represented by the format.
+    fn encode<T: Serialize + ?Sized>(value: &T)
+        -> Result<Self::Encoded, Error>;
+
+    /// Decodes a value that was encoded by [`Self::encode`].
+    ///
+    /// # Errors
+    ///
+    /// When `encoded` is malformed, or doesn't hold a `T`.
+    fn decode<T: DeserializeOwned>(encoded: &Self::Encoded)
+        -> Result<T, Error>;
+
+    /// Converts the given value to its dynamically typed representation.
+    ///
+    /// # Errors
+    ///
+    /// When the value can't be represented by the format.
+    fn to_value<T: Serialize + ?Sized>(value: &T)
+        -> Result<Self::Value, Error>;
+
+    /// Converts a dynamically typed representation back to a value.
+    ///
+    /// # Errors
+    ///
+    /// When `value` doesn't hold a `T`.
+    fn from_value<T: DeserializeOwned>(value: Self::Value) -> Result<T, Error>;
+}
+
+/// A recorded event, tagged with its revision value.
+#[derive(Serialize, Deserialize)]
+struct Tagged<R, E> {
+    revision: R,
+    event: E,
+    metadata: Metadata,
+    timestamp: SystemTime,
+}
+
+impl<'a, T: Event> Tagged<T::Value, revision::OldOrNewRef<'a, T>> {
+    fn new(recorded: Recorded<revision::OldOrNewRef<'a, T>>) -> Self {
+        let Recorded { event, metadata, timestamp } = recorded;
+        let revision = event.revision();
+        Self { revision, event, metadata, timestamp }
+    }
+}
+
+/// Returns a serializer and deserializer pair that serialize events using the
+/// format `C`.
+#[must_use]
+pub fn tagged_serialization<C, T>(
+) -> Serialization<TaggedSerializer<C, T>, TaggedDeserializer<C, T>>
+where
+    C: Codec,
+    C::Encoded: Send,
+    T: Event + Serialize + DeserializeOwned,
+    T::OldRevision: Serialize + DeserializeOwned,
+    T::Value: Serialize,
+{
+    Serialization {
+        serializer: TaggedSerializer::new(),
+        deserializer: TaggedDeserializer::new(),
+    }
+}
+
+/// Serializes events using the format `C`, along with their revision value.
+pub struct TaggedSerializer<C: Codec, T: Event>(PhantomData<fn() -> (C, T)>);
+
+impl<C: Codec, T: Event> TaggedSerializer<C, T> {
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self { Self(PhantomData) }
+}
+
+impl<C: Codec, T: Event> Clone for TaggedSerializer<C, T> {
+    fn clone(&self) -> Self { Self::new() }
+}
+
+impl<C, T> Serializer for TaggedSerializer<C, T>
+where
+    C: Codec,
+    T: Event + Serialize,
+    T::OldRevision: Serialize,
+    T::Value: Serialize,
+{
+    type Event = T;
+    type SerializedEvent = C::Encoded;
+
+    fn serialize(
+        &self,
+        recorded: Recorded<revision::OldOrNewRef<Self::Event>>,
+    ) -> Result<Self::SerializedEvent, Error> {
+        C::encode(&Tagged::new(recorded))
+    }
+}
+
+/// Deserializes events that were serialized by [`TaggedSerializer`] using
+/// the format `C`.
+pub struct TaggedDeserializer<C: Codec, T: Event> {
+    revision_tags: RevisionTags<C::Value>,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<C, T> TaggedDeserializer<C, T>
+where
+    C: Codec,
+    T: Event,
+    T::Value: Serialize,
+{
+    /// Creates a deserializer for events of type `T`.
+    ///
+    /// # Panics
+    ///
+    /// When a revision value of `T` can't be represented by the format.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            revision_tags: RevisionTags::new::<T>(|value| {
+                C::to_value(value).unwrap_or_else(|err| {
+                    panic!(
+                        "revision value should be serializable as {}: {err}",
+                        C::NAME
+                    )
+                })
+            }),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<C: Codec, T: Event> Clone for TaggedDeserializer<C, T> {
+    fn clone(&self) -> Self {
+        Self { revision_tags: self.revision_tags.clone(), _event: PhantomData }
+    }
+}
+
+impl<C, T> Deserializer for TaggedDeserializer<C, T>
+where
+    C: Codec,
+    T: Event + DeserializeOwned,
+    T::OldRevision: DeserializeOwned,
+    T::Value: Serialize,
+{
+    type Event = T;
+    type SerializedEvent = C::Encoded;
+
+    fn deserialize(
+        &self,
+        event: Self::SerializedEvent,
+    ) -> Result<Recorded<revision::OldOrNew<Self::Event>>, Error> {
+        // the event is decoded as a dynamically typed value, until its
+        // revision tells which type it should be deserialized as
+        let Tagged { revision, event, metadata, timestamp }: Tagged<
+            C::Value,
+            C::Value,
+        > = C::decode(&event)?;
+        let event = match self.revision_tags.kind(&revision) {
+            Some(Kind::New) => revision::OldOrNew::New(C::from_value(event)?),
+            Some(Kind::Old) => revision::OldOrNew::Old(C::from_value(event)?),
+            None => return Err(Error::unknown_revision(&revision)),
+        };
+        Ok(Recorded { event, metadata, timestamp })
+    }
+}
+
+/// Serializes snapshotted entities using the format `C`.
+pub struct SnapshotSerializer<C: Codec, E>(PhantomData<fn() -> (C, E)>);
+
+impl<C: Codec, E> SnapshotSerializer<C, E> {
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self { Self(PhantomData) }
+}
+
+impl<C: Codec, E> Clone for SnapshotSerializer<C, E> {
+    fn clone(&self) -> Self { Self::new() }
+}
+
+impl<C, E> snapshot::Serializer for SnapshotSerializer<C, E>
+where
+    C: Codec,
+    E: Serialize + DeserializeOwned,
+{
+    type Entity = E;
+    type SerializedEntity = C::Encoded;
+
+    fn serialize(&self, entity: &E) -> Result<C::Encoded, Error> {
+        C::encode(entity)
+    }
+
+    fn deserialize(&self, entity: C::Encoded) -> Result<E, Error> {
+        C::decode(&entity)
+    }
+}
+
+/// Whether a revision value is of a new event, or of an old revision of one.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Kind {
+    Old,
+    New,
+}
+
+/// The revision values of an event and of its old revisions, as represented by
+/// a specific serialization format.
+struct RevisionTags<V>(Arc<[(V, Kind)]>);
+
+impl<V> Clone for RevisionTags<V> {
+    fn clone(&self) -> Self { Self(self.0.clone()) }
+}
+
+impl<V: PartialEq> RevisionTags<V> {
+    /// Collects the revision values of `T` and of [`Event::OldRevision`],
+    /// converting each to its representation using `to_tag`.
+    fn new<T: Event>(to_tag: impl Fn(&T::Value) -> V) -> Self {
+        let new = T::revision_set().into_iter().map(|value| (value, Kind::New));
+        let old = <T::OldRevision as Revision>::revision_set()
+            .into_iter()
+            .map(|value| (value, Kind::Old));
+        Self(
+            new.chain(old)
+                .map(|(value, kind)| (to_tag(&value), kind))
+                .collect(),
+        )
+    }
+
+    /// Returns the kind of the given revision tag, or `None` if it's not a
+    /// revision of the event.
+    fn kind(&self, tag: &V) -> Option<Kind> {
+        self.0.iter().find(|(value, _)| value == tag).map(|&(_, kind)| kind)
+    }
+}
+
+/// Creates an error of kind [`ErrorKind::Format`], caused by `source`.
+pub(super) fn format_error(
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> Error {
+    Error::new(ErrorKind::Format, source)
+}
@@ -0,0 +1,52 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::envelope::EventId;
+use crate::store::{write, CommitNumber};
+
+/// The default number of recently committed event IDs that are remembered per
+/// stream.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: usize = 1024;
+
+/// The IDs of events that were recently committed to a stream, along with
+/// their commit numbers.
+///
+/// Must only be accessed while the stream's events are locked for writing.
+#[derive(Default)]
+pub(in crate::store) struct CommittedIds {
+    commit_numbers: HashMap<EventId, CommitNumber>,
+    order: VecDeque<EventId>,
+}
+
+pub(in crate::store) type SharedCommittedIds = Arc<Mutex<CommittedIds>>;
+
+impl CommittedIds {
+    /// Looks up the given IDs among the remembered ones (see
+    /// [`write::lookup`]).
+    pub(in crate::store) fn lookup(&self, ids: &[EventId]) -> write::Lookup {
+        write::lookup(ids, |id| self.commit_numbers.get(id).copied())
+    }
+
+    /// Remembers the given IDs, forgetting the oldest ones so that at most
+    /// `window` IDs are remembered.
+    pub(in crate::store) fn insert(
+        &mut self,
+        ids: impl IntoIterator<Item = (EventId, CommitNumber)>,
+        window: usize,
+    ) {
+        for (id, commit_number) in ids {
+            self.commit_numbers.insert(id, commit_number);
+            self.order.push_back(id);
+        }
+        self.trim(window);
+    }
+
+    /// Forgets the oldest IDs, so that at most `window` IDs are remembered.
+    pub(in crate::store) fn trim(&mut self, window: usize) {
+        while self.order.len() > window {
+            if let Some(id) = self.order.pop_front() {
+                self.commit_numbers.remove(&id);
+            }
+        }
+    }
+}
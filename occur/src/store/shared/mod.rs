@@ -0,0 +1,81 @@
+//! Building blocks of the stores that index their events in memory (i.e.,
+//! [`InmemStore`], which holds the events themselves, and the file store, which
+//! holds their locations in its log).
+//!
+//! [`InmemStore`]: crate::store::inmem::InmemStore
+
+use std::sync::{Arc, PoisonError};
+
+use futures_locks::RwLock;
+pub use idempotency::DEFAULT_IDEMPOTENCY_WINDOW;
+pub use page::DEFAULT_READ_PAGE_SIZE;
+
+use crate::store::shared::idempotency::SharedCommittedIds;
+use crate::store::shared::sharded::ShardedMap;
+use crate::store::shared::tail::Notify;
+use crate::store::CommitNumber;
+
+pub(in crate::store) mod idempotency;
+pub(in crate::store) mod page;
+pub(in crate::store) mod sharded;
+pub(in crate::store) mod tail;
+
+pub(in crate::store) type SmartVec<T> = Arc<RwLock<Vec<T>>>;
+
+/// The events of a single stream, along with a way to notify subscribers when
+/// new events are committed, and the IDs of recently committed events.
+pub(in crate::store) struct StreamEvents<E> {
+    pub(in crate::store) events: SmartVec<E>,
+    pub(in crate::store) committed: Notify,
+    pub(in crate::store) committed_ids: SharedCommittedIds,
+}
+
+impl<E> Clone for StreamEvents<E> {
+    fn clone(&self) -> Self {
+        Self {
+            events: self.events.clone(),
+            committed: self.committed.clone(),
+            committed_ids: self.committed_ids.clone(),
+        }
+    }
+}
+
+impl<E> Default for StreamEvents<E> {
+    fn default() -> Self {
+        Self {
+            events: SmartVec::default(),
+            committed: Notify::default(),
+            committed_ids: SharedCommittedIds::default(),
+        }
+    }
+}
+
+/// Forgets the IDs of events committed to each of the given streams, other
+/// than the last `window` ones, for when the idempotency window of a store
+/// that already holds events is set.
+pub(in crate::store) fn trim_committed_ids<K, E>(
+    streams: &ShardedMap<K, StreamEvents<E>>,
+    window: usize,
+) where
+    K: std::hash::Hash,
+{
+    streams.for_each_value(|stream| {
+        // IDs are only forgotten, which is what a concurrent commit could do
+        // too, so the stream's events don't need to be locked
+        stream
+            .committed_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .trim(window);
+    });
+}
+
+/// An event recorded in the global log of a store.
+#[derive(Clone)]
+pub(in crate::store) struct GlobalEntry<Id, E> {
+    pub(in crate::store) stream_id: Id,
+    pub(in crate::store) commit_number: CommitNumber,
+    pub(in crate::store) event: E,
+}
+
+pub(in crate::store) type GlobalLog<Id, E> = SmartVec<GlobalEntry<Id, E>>;
@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use futures::Stream;
+
+use crate::store::shared::SmartVec;
+use crate::store::{read, CommitNumber};
+
+/// The default number of events that are read at once from a stream (or from
+/// the global log).
+pub const DEFAULT_READ_PAGE_SIZE: usize = 64;
+
+/// Returns the commit number of the event at the given index of a stream's
+/// events.
+///
+/// Events are only appended to a stream once they're assigned a commit number
+/// (see [`crate::store::write::Condition::check`]), so the index of every event
+/// fits.
+#[allow(clippy::cast_possible_truncation)]
+pub(in crate::store) const fn commit_number(index: usize) -> CommitNumber {
+    index as CommitNumber
+}
+
+/// Returns a stream of the events of `events` within `range`, ordered by the
+/// given `direction`.
+///
+/// Each event is yielded along with its index. Events are read lazily, in
+/// pages of up to `page_size` events, and the lock of `events` is only held
+/// while a single page is read.
+///
+/// As events are only ever appended, the yielded events are the same ones that
+/// were within `range` when it was computed.
+pub(in crate::store) fn read<T>(
+    events: SmartVec<T>,
+    range: Range<u64>,
+    direction: read::Direction,
+    page_size: usize,
+) -> impl Stream<Item = (usize, T)> + Send
+where
+    T: Clone + Send + Sync,
+{
+    let page_size = page_size.max(1);
+    // the range is within the bounds of `events`, so its indices fit
+    #[allow(clippy::cast_possible_truncation)]
+    let range = range.start as usize..range.end as usize;
+    let state = (events, range, VecDeque::new());
+    // boxed, so that reads can be consumed without pinning them first
+    Box::pin(futures::stream::unfold(
+        state,
+        move |(events, mut range, mut page)| async move {
+            if page.is_empty() && !range.is_empty() {
+                let indices = match direction {
+                    read::Direction::Forward => {
+                        let end = range.end.min(range.start + page_size);
+                        let indices = range.start..end;
+                        range.start = end;
+                        indices
+                    }
+                    read::Direction::Backward => {
+                        let start = range
+                            .start
+                            .max(range.end.saturating_sub(page_size));
+                        let indices = start..range.end;
+                        range.end = start;
+                        indices
+                    }
+                };
+                let events = events.read().await;
+                let entries =
+                    indices.clone().zip(events[indices].iter().cloned());
+                match direction {
+                    read::Direction::Forward => page.extend(entries),
+                    read::Direction::Backward => page.extend(entries.rev()),
+                }
+            }
+            let item = page.pop_front()?;
+            Some((item, (events, range, page)))
+        },
+    ))
+}
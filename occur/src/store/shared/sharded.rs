@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{PoisonError, RwLock};
+
+/// The number of shards of a [`ShardedMap`].
+const SHARD_COUNT: usize = 32;
+
+/// A concurrent hash map, split into independently locked shards.
+///
+/// Accessing keys of different shards never contends on the same lock, so
+/// opening different streams from many tasks doesn't serialize on a single
+/// global lock.
+pub(in crate::store) struct ShardedMap<K, V> {
+    shards: Box<[RwLock<HashMap<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K, V> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V: Clone + Default> ShardedMap<K, V> {
+    /// Returns a clone of the value of the given key, inserting a default
+    /// value first if the key is missing.
+    pub(in crate::store) fn get_or_default(&self, key: K) -> V {
+        let shard = self.shard(&key);
+        let existing = shard
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned();
+        existing.unwrap_or_else(|| {
+            shard
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(key)
+                .or_default()
+                .clone()
+        })
+    }
+}
+
+impl<K: Hash, V> ShardedMap<K, V> {
+    /// Calls `f` with each value, one shard at a time.
+    pub(in crate::store) fn for_each_value(&self, mut f: impl FnMut(&V)) {
+        for shard in &self.shards {
+            shard
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .values()
+                .for_each(&mut f);
+        }
+    }
+
+    fn shard(&self, key: &K) -> &RwLock<HashMap<K, V>> {
+        // truncating the hash is fine, as it's only used to pick a shard
+        #[allow(clippy::cast_possible_truncation)]
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for ShardedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let map = Self::default();
+        for (key, value) in iter {
+            map.shard(&key)
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key, value);
+        }
+        map
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use futures::Stream;
+
+use crate::store::shared::SmartVec;
+
+/// Notifies subscribers of a [`SmartVec`] whenever events are appended to it.
+pub(in crate::store) type Notify = Arc<event_listener::Event>;
+
+/// Returns a never-ending stream of the events in `events`, starting at index
+/// `start`, and then of every event appended afterwards.
+///
+/// Each event is yielded along with its index. Events are read in pages of up
+/// to `page_size` events, and only when the stream is polled. When there are
+/// no new events, the stream waits to be woken up by `notify`, which must be
+/// notified after events are appended.
+pub(in crate::store) fn tail<T>(
+    events: SmartVec<T>,
+    notify: Notify,
+    start: usize,
+    page_size: usize,
+) -> impl Stream<Item = (usize, T)> + Send
+where
+    T: Clone + Send + Sync,
+{
+    let page_size = page_size.max(1);
+    let state = (events, notify, start, VecDeque::new());
+    futures::stream::unfold(
+        state,
+        move |(events, notify, mut cursor, mut page)| async move {
+            while page.is_empty() {
+                // start listening before checking for events, so that appends
+                // which happen after the check aren't missed
+                let listener = notify.listen();
+                {
+                    let events = events.read().await;
+                    let end = events.len().min(cursor + page_size);
+                    if cursor < end {
+                        page.extend(
+                            (cursor..end)
+                                .zip(events[cursor..end].iter().cloned()),
+                        );
+                        cursor = end;
+                        break;
+                    }
+                }
+                listener.await;
+            }
+            let item = page.pop_front()?;
+            Some((item, (events, notify, cursor, page)))
+        },
+    )
+}
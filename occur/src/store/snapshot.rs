@@ -0,0 +1,265 @@
+//! Snapshots of entities, used to speed up rehydration of long streams.
+//!
+//! A snapshot holds an entity as it was after folding all events of its stream
+//! up to (and including) a given commit number. Loading an entity using
+//! [`load`] starts from its latest snapshot, and only folds the events that
+//! were committed afterwards.
+//!
+//! When to take snapshots is decided by a [`Policy`], such as [`EveryNEvents`]
+//! or [`OnDemand`] (in which case snapshots are only taken using [`take`]).
+//!
+//! A [`SnapshotStore`] that persists snapshots serializes the snapshotted
+//! entities using a [`Serializer`], and persists them along with their commit
+//! number and schema version.
+
+use std::future::Future;
+
+use crate::entity::{self, Rehydrated};
+use crate::store::{serialization, CommitNumber, ReadStream};
+use crate::{Entity, Event, Store};
+
+/// A snapshot of an entity.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Snapshot<E> {
+    /// The commit number of the last event that was folded into the entity.
+    pub commit_number: CommitNumber,
+
+    /// The schema version of the entity when the snapshot was taken (see
+    /// [`Entity::SCHEMA_VERSION`]).
+    pub schema_version: u32,
+
+    /// The snapshotted entity.
+    pub entity: E,
+}
+
+impl<E> Snapshot<E> {
+    /// Converts the snapshotted entity using `f`, keeping the commit number
+    /// and schema version (e.g., to serialize or deserialize it).
+    ///
+    /// # Errors
+    ///
+    /// When `f` fails.
+    pub fn try_map<F, Err>(
+        self,
+        f: impl FnOnce(E) -> Result<F, Err>,
+    ) -> Result<Snapshot<F>, Err> {
+        let Self { commit_number, schema_version, entity } = self;
+        Ok(Snapshot { commit_number, schema_version, entity: f(entity)? })
+    }
+}
+
+/// Serializes snapshotted entities so they can be persisted by a
+/// [`SnapshotStore`], and deserializes them back.
+pub trait Serializer: Clone + Send + Sync {
+    type Entity;
+    type SerializedEntity;
+
+    /// Serializes the given entity.
+    ///
+    /// # Errors
+    ///
+    /// When the entity can't be represented by the serialization format.
+    fn serialize(
+        &self,
+        entity: &Self::Entity,
+    ) -> Result<Self::SerializedEntity, serialization::Error>;
+
+    /// Deserializes an entity that was serialized by [`Self::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// When `entity` is malformed.
+    fn deserialize(
+        &self,
+        entity: Self::SerializedEntity,
+    ) -> Result<Self::Entity, serialization::Error>;
+}
+
+/// A store that holds the latest snapshot of each entity.
+#[allow(clippy::module_name_repetitions)]
+pub trait SnapshotStore: Send {
+    /// The type of events from which the snapshotted entities are folded.
+    type Event: Event;
+
+    /// The type of the snapshotted entities.
+    type Entity: Entity<Self::Event>;
+
+    /// The type of error that might occur when accessing the store.
+    type Error: std::error::Error;
+
+    #[rustfmt::skip]
+    /// Returns the latest snapshot of the entity with the given stream ID, if
+    /// one exists.
+    fn load(
+        &mut self,
+        id: &<Self::Event as Event>::StreamId,
+    ) -> impl Future<
+        Output=Result<Option<Snapshot<Self::Entity>>, Self::Error>
+    > + Send;
+
+    /// Saves a snapshot of the entity with the given stream ID, replacing any
+    /// previously saved snapshot.
+    fn save(
+        &mut self,
+        id: &<Self::Event as Event>::StreamId,
+        snapshot: &Snapshot<Self::Entity>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Deletes the snapshot of the entity with the given stream ID, if one
+    /// exists.
+    fn delete(
+        &mut self,
+        id: &<Self::Event as Event>::StreamId,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// Decides when [`load`] should take a snapshot of the loaded entity.
+pub trait Policy {
+    /// Returns whether a snapshot should be taken of an entity whose last
+    /// folded event has the given `commit_number`.
+    ///
+    /// `snapshot_commit_number` is the commit number of the snapshot that the
+    /// entity was loaded from, if any.
+    fn should_snapshot(
+        &self,
+        snapshot_commit_number: Option<CommitNumber>,
+        commit_number: CommitNumber,
+    ) -> bool;
+}
+
+/// Takes a snapshot whenever at least `N` events were folded since the last
+/// snapshot.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct EveryNEvents(pub u32);
+
+impl Policy for EveryNEvents {
+    fn should_snapshot(
+        &self,
+        snapshot_commit_number: Option<CommitNumber>,
+        commit_number: CommitNumber,
+    ) -> bool {
+        let n_events = snapshot_commit_number
+            .map_or(commit_number + 1, |number| commit_number - number);
+        n_events >= self.0
+    }
+}
+
+/// Never takes snapshots automatically; use [`take`] instead.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct OnDemand;
+
+impl Policy for OnDemand {
+    fn should_snapshot(
+        &self,
+        _: Option<CommitNumber>,
+        _: CommitNumber,
+    ) -> bool {
+        false
+    }
+}
+
+/// Errors that might occur when loading an entity using snapshots.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<RE, SE> {
+    /// Reading events from the stream failed.
+    #[error("failed to read events")]
+    Read(#[source] RE),
+
+    /// Accessing the snapshot store failed.
+    #[error("failed to access snapshot")]
+    Snapshot(#[source] SE),
+}
+
+type LoadResult<E, S, N> = Result<
+    Rehydrated<E>,
+    Error<
+        <<S as Store>::ReadStream as ReadStream>::Error,
+        <N as SnapshotStore>::Error,
+    >,
+>;
+
+/// Rehydrates an entity starting from its latest snapshot, then saves a new
+/// snapshot if the given `policy` says so.
+///
+/// Snapshots that were taken with a different [`Entity::SCHEMA_VERSION`] are
+/// deleted and ignored.
+///
+/// # Errors
+///
+/// When reading from the stream or accessing the snapshot store fails.
+pub async fn load<E, S, N, P>(
+    store: &S,
+    snapshots: &mut N,
+    policy: &P,
+    id: <S::Event as Event>::StreamId,
+) -> LoadResult<E, S, N>
+where
+    E: Entity<S::Event> + Clone + Send + Sync,
+    S: Store + Send,
+    N: SnapshotStore<Event = S::Event, Entity = E>,
+    P: Policy + Sync,
+{
+    let snapshot = snapshots.load(&id).await.map_err(Error::Snapshot)?;
+    let snapshot = match snapshot {
+        Some(snapshot) if snapshot.schema_version == E::SCHEMA_VERSION => {
+            Some(snapshot)
+        }
+        Some(_) => {
+            snapshots.delete(&id).await.map_err(Error::Snapshot)?;
+            None
+        }
+        None => None,
+    };
+
+    let snapshot_commit_number = snapshot.as_ref().map(|s| s.commit_number);
+    let rehydrated =
+        snapshot.map_or_else(Rehydrated::default, |snapshot| Rehydrated {
+            entity: Some(snapshot.entity),
+            last_commit_number: Some(snapshot.commit_number),
+        });
+
+    let mut stream = store.read_stream(id.clone());
+    let rehydrated =
+        entity::rehydrate_from(id.clone(), &mut stream, rehydrated)
+            .await
+            .map_err(Error::Read)?;
+
+    if let Some(commit_number) = rehydrated.last_commit_number {
+        if snapshot_commit_number != Some(commit_number)
+            && policy.should_snapshot(snapshot_commit_number, commit_number)
+        {
+            take(snapshots, &id, &rehydrated).await.map_err(Error::Snapshot)?;
+        }
+    }
+
+    Ok(rehydrated)
+}
+
+/// Saves a snapshot of the given rehydrated entity.
+///
+/// Does nothing if the entity doesn't exist.
+///
+/// # Errors
+///
+/// When saving the snapshot fails.
+pub async fn take<E, N>(
+    snapshots: &mut N,
+    id: &<N::Event as Event>::StreamId,
+    rehydrated: &Rehydrated<E>,
+) -> Result<(), N::Error>
+where
+    E: Entity<N::Event> + Clone + Send + Sync,
+    N: SnapshotStore<Entity = E>,
+{
+    let (Some(entity), Some(commit_number)) =
+        (&rehydrated.entity, rehydrated.last_commit_number)
+    else {
+        return Ok(());
+    };
+    let snapshot = Snapshot {
+        commit_number,
+        schema_version: E::SCHEMA_VERSION,
+        entity: entity.clone(),
+    };
+    snapshots.save(id, &snapshot).await
+}
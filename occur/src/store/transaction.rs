@@ -0,0 +1,128 @@
+//! Committing events to many streams of a store atomically.
+//!
+//! A [`Transaction`] stages appends to any number of streams, each with its own
+//! [`write::Condition`]. Committing it (see
+//! [`TransactionalStore::commit_transaction`]) either commits all of its
+//! appends, or none of them.
+
+use std::future::Future;
+
+use crate::envelope::Envelope;
+use crate::store::{write, CommitNumber, Store};
+use crate::Event;
+
+/// Events staged to be appended to a single stream as part of a
+/// [`Transaction`].
+pub struct Append<'a, T: Event> {
+    /// The ID of the stream to which events should be appended.
+    pub stream_id: T::StreamId,
+    /// The events to append, along with their metadata.
+    pub envelopes: Vec<Envelope<&'a T>>,
+    /// The condition that must hold for the events to be appended.
+    pub condition: write::Condition,
+}
+
+/// Appends to many streams that should be committed atomically.
+///
+/// Appends are applied in the order they were staged. A stream may be appended
+/// to more than once, in which case the condition of each append is checked
+/// against the stream as if the previous appends were already committed.
+pub struct Transaction<'a, T: Event> {
+    appends: Vec<Append<'a, T>>,
+}
+
+impl<'a, T: Event> Transaction<'a, T> {
+    /// Creates an empty transaction.
+    #[must_use]
+    pub const fn new() -> Self { Self { appends: Vec::new() } }
+
+    /// Stages events to be appended to the given stream along with their
+    /// metadata, given the provided condition holds.
+    ///
+    /// Same as [`crate::store::WriteStream::commit_many_envelopes`], except
+    /// the events are only committed along with the rest of the transaction.
+    #[must_use]
+    pub fn append_envelopes(
+        mut self,
+        stream_id: T::StreamId,
+        envelopes: impl IntoIterator<Item = Envelope<&'a T>>,
+        condition: write::Condition,
+    ) -> Self {
+        self.appends.push(Append {
+            stream_id,
+            envelopes: envelopes.into_iter().collect(),
+            condition,
+        });
+        self
+    }
+
+    /// Stages events to be appended to the given stream, given the provided
+    /// condition holds.
+    ///
+    /// Each event is committed with newly created metadata (see
+    /// [`crate::envelope::Metadata::new`]).
+    #[must_use]
+    pub fn append(
+        self,
+        stream_id: T::StreamId,
+        events: impl IntoIterator<Item = &'a T>,
+        condition: write::Condition,
+    ) -> Self {
+        self.append_envelopes(
+            stream_id,
+            events.into_iter().map(Envelope::new),
+            condition,
+        )
+    }
+
+    /// Returns the staged appends, in the order they were staged.
+    #[must_use]
+    pub fn appends(&self) -> &[Append<'a, T>] { &self.appends }
+
+    /// Consumes the transaction, returning the staged appends.
+    #[must_use]
+    pub fn into_appends(self) -> Vec<Append<'a, T>> { self.appends }
+}
+
+impl<T: Event> Default for Transaction<'_, T> {
+    fn default() -> Self { Self::new() }
+}
+
+/// An error that might occur when committing a [`Transaction`].
+pub trait Error: write::Error {
+    /// Returns the index of the append (within
+    /// [`Transaction::appends`]) that caused the transaction to fail, or
+    /// [`None`] if the failure isn't related to a specific append.
+    fn append_index(&self) -> Option<usize>;
+}
+
+/// An event store that can commit events to many streams atomically.
+///
+/// This is a separate trait from [`Store`], as not all storage backends can
+/// atomically write to many streams (e.g., when each stream is stored in its
+/// own partition).
+pub trait TransactionalStore: Store {
+    /// The type of error that might occur when committing a transaction.
+    type TransactionError: Error;
+
+    /// Commits all appends of the given transaction, or none of them.
+    ///
+    /// On successful commit, returns the result of each append (in the order
+    /// they were staged), as returned by
+    /// [`crate::store::WriteStream::commit_many_envelopes`]. Idempotency is
+    /// handled per append: an append whose events were all already committed
+    /// is skipped, and the commit number originally assigned to its first
+    /// event is returned.
+    ///
+    /// All events committed by the transaction are recorded with the same
+    /// timestamp, taken once for the whole transaction.
+    ///
+    /// If any append fails (e.g., because its condition doesn't hold), no
+    /// events are committed, and the error identifies the failed append.
+    fn commit_transaction(
+        &self,
+        transaction: Transaction<'_, Self::Event>,
+    ) -> impl Future<
+        Output = Result<Vec<Option<CommitNumber>>, Self::TransactionError>,
+    > + Send;
+}
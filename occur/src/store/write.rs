@@ -1,7 +1,9 @@
+use std::collections::HashSet;
 use std::future::Future;
 
 use derive_more::Display;
 
+use crate::envelope::{Envelope, EventId};
 use crate::error::ErrorWithKind;
 use crate::{revision, Event};
 
@@ -28,6 +30,86 @@ pub enum Condition {
     ///
     /// [OCC]: https://en.wikipedia.org/wiki/Optimistic_concurrency_control
     AssignCommitNumber(CommitNumber),
+
+    /// For the commit to succeed, the stream must not exist yet (i.e., no
+    /// events were committed to it).
+    ///
+    /// Typically used when committing creation events.
+    NoStream,
+
+    /// For the commit to succeed, the stream must already exist (i.e., at
+    /// least one event was committed to it).
+    StreamExists,
+
+    /// For the commit to succeed, the last event committed to the stream must
+    /// have been assigned the provided commit number.
+    ///
+    /// Equivalent to [`Condition::AssignCommitNumber`] with the following
+    /// commit number.
+    LastCommitNumber(CommitNumber),
+}
+
+impl Condition {
+    /// Checks whether `n_events` can be appended to a stream whose last event
+    /// was assigned `last_commit_number` (or [`None`] if the stream is empty),
+    /// given the condition holds.
+    ///
+    /// On success, returns the commit number to be assigned to the first of
+    /// the appended events. Meant to be used by implementors of
+    /// [`WriteStream`].
+    ///
+    /// # Errors
+    ///
+    /// - [`CheckError::StreamFull`] when not all events can be assigned a
+    ///   commit number.
+    /// - [`CheckError::ConditionNotMet`] when the condition doesn't hold.
+    pub fn check(
+        self,
+        last_commit_number: Option<CommitNumber>,
+        n_events: usize,
+    ) -> Result<CommitNumber, CheckError> {
+        let commit_number = match last_commit_number {
+            None => 0,
+            Some(last_commit_number) => last_commit_number
+                .checked_add(1)
+                .ok_or(CheckError::StreamFull)?,
+        };
+        // all events of the batch must be assigned a commit number
+        CommitNumber::try_from(n_events.saturating_sub(1))
+            .ok()
+            .and_then(|n| commit_number.checked_add(n))
+            .ok_or(CheckError::StreamFull)?;
+        let is_met = match self {
+            Self::None => true,
+            Self::AssignCommitNumber(assign_commit_number) => {
+                commit_number == assign_commit_number
+            }
+            Self::NoStream => last_commit_number.is_none(),
+            Self::StreamExists => last_commit_number.is_some(),
+            Self::LastCommitNumber(expected_last_commit_number) => {
+                last_commit_number == Some(expected_last_commit_number)
+            }
+        };
+        if !is_met {
+            return Err(CheckError::ConditionNotMet { last_commit_number });
+        }
+        Ok(commit_number)
+    }
+}
+
+/// The reason events can't be appended to a stream, as returned by
+/// [`Condition::check`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum CheckError {
+    /// See [`ErrorKind::StreamFull`].
+    StreamFull,
+
+    /// See [`ErrorKind::ConditionNotMet`] and [`Error::last_commit_number`].
+    ConditionNotMet {
+        /// The commit number of the last event in the stream, or [`None`] if
+        /// the stream is empty.
+        last_commit_number: Option<CommitNumber>,
+    },
 }
 
 /// Errors that might occur when committing an event to a stream.
@@ -46,6 +128,24 @@ pub enum ErrorKind {
     #[display("condition not met")]
     ConditionNotMet,
 
+    /// Some, but not all, events of a batch were already committed to the
+    /// stream (as identified by their event ID), or they were committed in a
+    /// different order.
+    ///
+    /// See [`WriteStream::commit_many_envelopes`].
+    #[display("partially committed")]
+    PartiallyCommitted,
+
+    /// The same event ID was given to more than one event of a batch.
+    ///
+    /// See [`WriteStream::commit_many_envelopes`].
+    #[display("duplicate event id")]
+    DuplicateEventId,
+
+    /// An event couldn't be serialized.
+    #[display("serialization error")]
+    Serialization,
+
     /// An unexpected error occurred.
     ///
     /// Can be used by implementors of [`WriteStream`] to denote
@@ -55,6 +155,18 @@ pub enum ErrorKind {
     Other,
 }
 
+/// An error that might occur when committing an event to a stream.
+pub trait Error: ErrorWithKind<Kind = ErrorKind> {
+    /// For errors of kind [`ErrorKind::ConditionNotMet`], returns the commit
+    /// number of the last event in the stream at the time of the commit, or
+    /// [`None`] if the stream was empty.
+    ///
+    /// Allows retrying a commit with an updated [`Condition`], without having
+    /// to read the stream first. Always returns [`None`] for other kinds of
+    /// errors.
+    fn last_commit_number(&self) -> Option<CommitNumber>;
+}
+
 /// An event stream to which events can be committed.
 ///
 /// This is the write side of an event stream. See [`crate::store::ReadStream`]
@@ -65,11 +177,73 @@ pub trait WriteStream: Send {
     type Event: Event;
 
     /// The type of error that might occur when trying to commit an event.
-    type Error: ErrorWithKind<Kind = ErrorKind>;
+    type Error: Error;
+
+    /// Commits either an old or a new event revision to the stream along with
+    /// its metadata, given the provided condition holds.
+    ///
+    /// On successful commit, returns the assigned commit number.
+    ///
+    /// Commits are idempotent: if an event with the same ID (see
+    /// [`crate::envelope::Metadata::id`]) was already committed to the stream,
+    /// the event isn't committed again, and the commit number originally
+    /// assigned to it is returned instead (regardless of `condition`).
+    /// Implementations may only remember a limited window of recently
+    /// committed event IDs.
+    ///
+    /// Prefer using [`WriteStream::commit_envelope`] instead when committing
+    /// new events.
+    fn commit_old_or_new_envelope(
+        &mut self,
+        envelope: Envelope<revision::OldOrNewRef<'_, Self::Event>>,
+        condition: Condition,
+    ) -> impl Future<Output = Result<CommitNumber, Self::Error>> + Send;
+
+    /// Commits many events to the stream along with their metadata, given the
+    /// provided condition holds.
+    ///
+    /// Either all events will successfully commit, or none will and an error
+    /// will be returned.
+    ///
+    /// On successful commit, returns the commit number assigned to the first
+    /// committed event. If the given `envelopes` iterator is empty, returns
+    /// `None`.
+    ///
+    /// Commits are idempotent, as described in
+    /// [`WriteStream::commit_old_or_new_envelope`], with the whole batch
+    /// treated as a unit: if all of its events were already committed (in the
+    /// same order), the commit number originally assigned to the first event
+    /// is returned. If only some of them were, the commit fails with
+    /// [`ErrorKind::PartiallyCommitted`]. A batch that holds the same event ID
+    /// more than once is rejected with [`ErrorKind::DuplicateEventId`].
+    fn commit_many_envelopes<'a>(
+        &mut self,
+        envelopes: impl IntoIterator<Item = Envelope<&'a Self::Event>>,
+        condition: Condition,
+    ) -> impl Future<Output = Result<Option<CommitNumber>, Self::Error>> + Send;
+
+    /// Commits an event to the stream along with its metadata, given the
+    /// provided condition holds.
+    ///
+    /// On successful commit, returns the assigned commit number.
+    fn commit_envelope(
+        &mut self,
+        envelope: Envelope<&Self::Event>,
+        condition: Condition,
+    ) -> impl Future<Output = Result<CommitNumber, Self::Error>> + Send {
+        self.commit_old_or_new_envelope(
+            envelope.map(revision::OldOrNewRef::New),
+            condition,
+        )
+    }
 
     /// Commits either an old or a new event revision to the stream, given the
     /// provided condition holds.
     ///
+    /// The event is committed with newly created metadata (see
+    /// [`crate::envelope::Metadata::new`]), and thus with a new event ID, so
+    /// the commit isn't idempotent (see [`WriteStream::commit`]).
+    ///
     /// On successful commit, returns the assigned commit number.
     ///
     /// Prefer using [`WriteStream::commit`] instead when committing new events.
@@ -77,12 +251,16 @@ pub trait WriteStream: Send {
         &mut self,
         event: revision::OldOrNewRef<'_, Self::Event>,
         condition: Condition,
-    ) -> impl Future<Output = Result<CommitNumber, Self::Error>> + Send;
+    ) -> impl Future<Output = Result<CommitNumber, Self::Error>> + Send {
+        self.commit_old_or_new_envelope(Envelope::new(event), condition)
+    }
 
     /// Commits many events to the stream, given the provided condition holds
     ///
     /// Either all events will successfully commit, or none will and an error
-    /// will be returned.
+    /// will be returned. Each event is committed with newly created metadata
+    /// (see [`crate::envelope::Metadata::new`]), and thus with a new event ID,
+    /// so the commit isn't idempotent (see [`WriteStream::commit`]).
     ///
     /// On successful commit, returns the commit number assigned to the first
     /// committed event. If the given `events` iterator is empty, returns
@@ -96,12 +274,27 @@ pub trait WriteStream: Send {
         &mut self,
         events: impl IntoIterator<Item = &'a Self::Event>,
         condition: Condition,
-    ) -> impl Future<Output = Result<Option<CommitNumber>, Self::Error>> + Send;
+    ) -> impl Future<Output = Result<Option<CommitNumber>, Self::Error>> + Send
+    {
+        self.commit_many_envelopes(
+            events.into_iter().map(Envelope::new),
+            condition,
+        )
+    }
 
     /// Commits an event to the stream, given the provided condition holds.
     ///
+    /// The event is committed with newly created metadata (see
+    /// [`crate::envelope::Metadata::new`]).
+    ///
     /// On successful commit, returns the assigned commit number.
     ///
+    /// As each call creates a new event ID, the commit isn't idempotent:
+    /// retrying it (e.g., after a timeout, when it's unknown whether the
+    /// event was committed) might commit the event twice. To retry safely,
+    /// create an [`Envelope`] once, and commit it (and retry committing it)
+    /// using [`WriteStream::commit_envelope`] instead.
+    ///
     /// For convenience, [`WriteStream::commit_unconditionally`] can be used in
     /// place of [`Condition::None`], and
     /// [`WriteStream::commit_as_number`] can be used in place of
@@ -160,3 +353,71 @@ pub trait WriteStream: Send {
         self.commit_many(events, Condition::AssignCommitNumber(commit_number))
     }
 }
+
+/// Returns whether an event ID appears more than once within `ids`.
+///
+/// A batch holding the same event ID more than once can't be committed
+/// idempotently, so implementors of [`WriteStream`] reject it with
+/// [`ErrorKind::DuplicateEventId`].
+#[must_use]
+pub fn has_duplicate_ids(ids: &[EventId]) -> bool {
+    let mut seen = HashSet::with_capacity(ids.len());
+    !ids.iter().all(|id| seen.insert(id))
+}
+
+/// The result of looking up the IDs of a batch of events among the IDs of
+/// events that were recently committed to a stream (see [`lookup`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Lookup {
+    /// None of the events were committed.
+    New,
+    /// All events were committed, in order, starting at the given commit
+    /// number.
+    Committed(CommitNumber),
+    /// Only some of the events were committed, or they were committed in a
+    /// different order (see [`ErrorKind::PartiallyCommitted`]).
+    Partial,
+}
+
+/// Looks up the IDs of a batch of events among the IDs of events that were
+/// recently committed to a stream, where `commit_number_of` returns the commit
+/// number of a committed ID.
+///
+/// Meant to be used by implementors of [`WriteStream`], to commit batches
+/// idempotently (see [`WriteStream::commit_many_envelopes`]).
+pub fn lookup(
+    ids: &[EventId],
+    commit_number_of: impl Fn(&EventId) -> Option<CommitNumber>,
+) -> Lookup {
+    let commit_numbers: Vec<_> = ids.iter().map(commit_number_of).collect();
+    if commit_numbers.iter().all(Option::is_none) {
+        return Lookup::New;
+    }
+    let Some(Some(first)) = commit_numbers.first().copied() else {
+        return Lookup::Partial;
+    };
+    let in_order = commit_numbers
+        .iter()
+        .zip(first..)
+        .all(|(commit_number, expected)| *commit_number == Some(expected));
+    if in_order {
+        Lookup::Committed(first)
+    } else {
+        Lookup::Partial
+    }
+}
+
+/// Same as [`lookup`], for a window of the IDs of the last events of a stream
+/// (in any order), along with their commit numbers.
+#[must_use]
+pub fn lookup_in_window(
+    window: &[(CommitNumber, EventId)],
+    ids: &[EventId],
+) -> Lookup {
+    lookup(ids, |id| {
+        window
+            .iter()
+            .find(|(_, committed_id)| committed_id == id)
+            .map(|(commit_number, _)| *commit_number)
+    })
+}
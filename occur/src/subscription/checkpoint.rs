@@ -0,0 +1,182 @@
+//! Persistence of subscription checkpoints.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::store::all::GlobalPosition;
+
+/// A store that holds the checkpoint of each subscription, keyed by the
+/// subscription's name.
+///
+/// A checkpoint is the global position of the last event that was handled by
+/// the subscription.
+#[allow(clippy::module_name_repetitions)]
+pub trait CheckpointStore: Send {
+    /// The type of error that might occur when accessing the store.
+    type Error: std::error::Error;
+
+    #[rustfmt::skip]
+    /// Returns the checkpoint of the subscription with the given name, if one
+    /// was saved.
+    fn load(
+        &mut self,
+        name: &str,
+    ) -> impl Future<
+        Output=Result<Option<GlobalPosition>, Self::Error>
+    > + Send;
+
+    /// Saves the checkpoint of the subscription with the given name, replacing
+    /// any previously saved checkpoint.
+    fn save(
+        &mut self,
+        name: &str,
+        position: GlobalPosition,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Deletes the checkpoint of the subscription with the given name, if one
+    /// was saved.
+    fn delete(
+        &mut self,
+        name: &str,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// An in-memory [`CheckpointStore`].
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Default, Debug)]
+pub struct InmemCheckpointStore {
+    checkpoints: HashMap<String, GlobalPosition>,
+}
+
+impl InmemCheckpointStore {
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+}
+
+impl CheckpointStore for InmemCheckpointStore {
+    type Error = Infallible;
+
+    async fn load(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<GlobalPosition>, Self::Error> {
+        Ok(self.checkpoints.get(name).copied())
+    }
+
+    async fn save(
+        &mut self,
+        name: &str,
+        position: GlobalPosition,
+    ) -> Result<(), Self::Error> {
+        self.checkpoints.insert(name.to_owned(), position);
+        Ok(())
+    }
+
+    async fn delete(&mut self, name: &str) -> Result<(), Self::Error> {
+        self.checkpoints.remove(name);
+        Ok(())
+    }
+}
+
+/// A [`CheckpointStore`] that holds each checkpoint in a file of its own,
+/// within a given directory.
+///
+/// Checkpoints are saved atomically, by writing them to a temporary file that
+/// then replaces the checkpoint file. Subscription names are encoded into file
+/// names, so that any name (including one that contains path separators) maps
+/// to a file of its own within the directory.
+///
+/// File operations are blocking, and are performed by the task that loads,
+/// saves or deletes a checkpoint. Saving a checkpoint also waits for it to be
+/// flushed to disk, which blocks the executor's thread meanwhile.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Creates a store that holds checkpoints within `dir`, which is created
+    /// when the first checkpoint is saved.
+    pub fn new(dir: impl Into<PathBuf>) -> Self { Self { dir: dir.into() } }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.checkpoint", encode(name)))
+    }
+}
+
+/// Encodes a subscription name as a file name, by percent-encoding every byte
+/// other than ASCII letters, digits, `-` and `_`.
+fn encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            encoded.push(char::from(byte));
+        } else {
+            // writing to a string can't fail
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Flushes the entries of a directory to disk, so that a checkpoint file which
+/// replaced another within it survives a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> { fs::File::open(dir)?.sync_all() }
+
+/// Directories can't be opened as files on Windows, where their entries are
+/// flushed along with their files.
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+const fn sync_dir(_: &Path) -> io::Result<()> { Ok(()) }
+
+impl CheckpointStore for FileCheckpointStore {
+    type Error = io::Error;
+
+    async fn load(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<GlobalPosition>, Self::Error> {
+        let contents = match fs::read_to_string(self.path(name)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        contents
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    async fn save(
+        &mut self,
+        name: &str,
+        position: GlobalPosition,
+    ) -> Result<(), Self::Error> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        let tmp_path = path.with_extension("checkpoint.tmp");
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            io::Write::write_all(&mut file, position.to_string().as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(tmp_path, path)?;
+        sync_dir(&self.dir)
+    }
+
+    async fn delete(&mut self, name: &str) -> Result<(), Self::Error> {
+        match fs::remove_file(self.path(name)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
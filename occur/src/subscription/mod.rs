@@ -0,0 +1,214 @@
+//! Catch-up subscriptions over all events of a store.
+//!
+//! A [`Subscription`] consumes every event of a store in their global order
+//! (see [`crate::store::all`]), first replaying history and then handling
+//! events as they are committed. After each event is handled, its global
+//! position is saved as the subscription's checkpoint in a [`CheckpointStore`],
+//! so that a restarted subscription resumes right after the last handled
+//! event.
+//!
+//! Delivery is at-least-once: if the subscription stops after an event was
+//! handled but before its checkpoint was saved, the event will be handled again
+//! once the subscription resumes. Handlers should therefore be idempotent.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+pub use checkpoint::{
+    CheckpointStore,
+    FileCheckpointStore,
+    InmemCheckpointStore,
+};
+use futures::StreamExt as _;
+
+use crate::store::all::{self, AllStream, ConvertedItem};
+use crate::{Event, Revision};
+
+pub mod checkpoint;
+
+type StreamIdPredicate<Id> = Arc<dyn Fn(&Id) -> bool + Send + Sync>;
+
+/// Selects which events are handled by a [`Subscription`].
+///
+/// By default, all events are selected. Events that aren't selected are
+/// skipped without being handled.
+pub struct Filter<T: Event> {
+    revisions: Option<HashSet<T::Value>>,
+    stream_id: Option<StreamIdPredicate<T::StreamId>>,
+}
+
+impl<T: Event> Default for Filter<T> {
+    fn default() -> Self { Self { revisions: None, stream_id: None } }
+}
+
+impl<T: Event> Clone for Filter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            revisions: self.revisions.clone(),
+            stream_id: self.stream_id.clone(),
+        }
+    }
+}
+
+impl<T: Event> Filter<T> {
+    /// Returns a filter that selects all events.
+    #[must_use]
+    pub fn all() -> Self { Self::default() }
+
+    /// Only selects events whose revision (see [`Revision::revision`]) is one
+    /// of the given values.
+    #[must_use]
+    pub fn revisions(
+        mut self,
+        revisions: impl IntoIterator<Item = T::Value>,
+    ) -> Self {
+        self.revisions = Some(revisions.into_iter().collect());
+        self
+    }
+
+    /// Only selects events of streams whose ID matches the given predicate.
+    #[must_use]
+    pub fn stream_id(
+        mut self,
+        predicate: impl Fn(&T::StreamId) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.stream_id = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Returns whether the given item is selected by the filter.
+    pub fn matches(&self, item: &ConvertedItem<T>) -> bool {
+        let revision_matches = self
+            .revisions
+            .as_ref()
+            .is_none_or(|revisions| revisions.contains(&item.event.revision()));
+        let stream_id_matches = self
+            .stream_id
+            .as_ref()
+            .is_none_or(|predicate| predicate(&item.stream_id));
+        revision_matches && stream_id_matches
+    }
+}
+
+/// Errors that might occur when running a subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<RE, CE, HE> {
+    /// Subscribing to the store failed.
+    #[error("failed to subscribe")]
+    Read(#[source] RE),
+
+    /// Accessing the checkpoint store failed.
+    #[error("failed to access checkpoint")]
+    Checkpoint(#[source] CE),
+
+    /// The handler failed to handle an event.
+    #[error("failed to handle event")]
+    Handler(#[source] HE),
+}
+
+/// A named, checkpointed subscription over all events of a store.
+///
+/// See the [module documentation](self) for details.
+pub struct Subscription<A, C>
+where
+    A: all::Subscribe,
+    C: CheckpointStore,
+{
+    name: String,
+    all_stream: A,
+    checkpoints: C,
+    filter: Filter<A::Event>,
+}
+
+impl<A, C> Subscription<A, C>
+where
+    A: all::Subscribe,
+    C: CheckpointStore,
+    <A::Event as Revision>::Value: Send + Sync,
+{
+    /// Creates a subscription with the given name, which is used to key its
+    /// checkpoint.
+    pub fn new(name: impl Into<String>, all_stream: A, checkpoints: C) -> Self {
+        Self {
+            name: name.into(),
+            all_stream,
+            checkpoints,
+            filter: Filter::default(),
+        }
+    }
+
+    /// Replaces the filter of the subscription.
+    #[must_use]
+    pub fn with_filter(self, filter: Filter<A::Event>) -> Self {
+        Self { filter, ..self }
+    }
+
+    /// Returns the name of the subscription.
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Returns the underlying checkpoint store.
+    pub const fn checkpoints(&self) -> &C { &self.checkpoints }
+
+    /// Returns the checkpoint of the subscription, which is the global position
+    /// of the last handled event, or [`None`] if no event was handled yet.
+    ///
+    /// # Errors
+    ///
+    /// When accessing the checkpoint store fails.
+    pub async fn checkpoint(
+        &mut self,
+    ) -> Result<Option<all::GlobalPosition>, C::Error> {
+        self.checkpoints.load(&self.name).await
+    }
+
+    /// Runs the subscription, handling each selected event using `handle`.
+    ///
+    /// The subscription resumes right after its checkpoint (or from the first
+    /// event, if it has none), and runs until `handle` returns
+    /// [`ControlFlow::Break`] or an error. The checkpoint is saved after every
+    /// event that is handled successfully, including the one that stops the
+    /// subscription.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Read`] when subscribing to the store or reading an event
+    ///   fails.
+    /// - [`Error::Checkpoint`] when accessing the checkpoint store fails.
+    /// - [`Error::Handler`] when `handle` fails, in which case the event is
+    ///   handled again the next time the subscription runs.
+    pub async fn run<F, Fut, HE>(
+        &mut self,
+        mut handle: F,
+    ) -> Result<(), Error<<A as AllStream>::Error, C::Error, HE>>
+    where
+        F: FnMut(ConvertedItem<A::Event>) -> Fut + Send,
+        Fut: Future<Output = Result<ControlFlow<()>, HE>> + Send,
+    {
+        let checkpoint = self.checkpoint().await.map_err(Error::Checkpoint)?;
+        let position = checkpoint.map_or(0, |position| position + 1);
+        let mut events = Box::pin(
+            self.all_stream
+                .subscribe(all::Position::GlobalPosition(position))
+                .await
+                .map_err(Error::Read)?,
+        );
+        while let Some(item) = events.next().await {
+            let item = item.map_err(Error::Read)?;
+            if !self.filter.matches(&item) {
+                continue;
+            }
+            let global_position = item.global_position;
+            let flow = handle(item).await.map_err(Error::Handler)?;
+            self.checkpoints
+                .save(&self.name, global_position)
+                .await
+                .map_err(Error::Checkpoint)?;
+            if flow.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+#![cfg(any(feature = "cbor", feature = "msgpack"))]
+
+use std::time::SystemTime;
+
+use futures::executor::block_on;
+use futures::TryStreamExt as _;
+use occur::envelope::{Metadata, Recorded};
+use occur::revision::{OldOrNew, OldOrNewRef};
+use occur::store::inmem::InmemStore;
+use occur::store::serialization::Serialization;
+use occur::store::write::Condition;
+use occur::store::{
+    Deserializer,
+    ReadStream as _,
+    Serializer,
+    Store as _,
+    WriteStream as _,
+};
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+fn recorded<E>(event: E) -> Recorded<E> {
+    Recorded { event, metadata: Metadata::new(), timestamp: SystemTime::now() }
+}
+
+/// Asserts that events (both new and old revisions) are deserialized as they
+/// were before serialization, and that old revisions are converted when read
+/// from a store.
+fn assert_round_trip<S, D>(serialization: impl Fn() -> Serialization<S, D>)
+where
+    S: Serializer<Event = user::Event, SerializedEvent = Vec<u8>>,
+    D: Deserializer<Event = user::Event, SerializedEvent = Vec<u8>>,
+{
+    let Serialization { serializer, deserializer } = serialization();
+    let id = admin_id();
+    let befriended = recorded(user::Event::Befriended { user: id });
+    let old = recorded(user::old::Revision::Deactivated_V0);
+
+    let bytes = serializer
+        .serialize(befriended.as_ref().map(OldOrNewRef::New))
+        .unwrap();
+    assert_eq!(
+        deserializer.deserialize(bytes).unwrap(),
+        befriended.map(OldOrNew::New),
+    );
+    let bytes =
+        serializer.serialize(old.as_ref().map(OldOrNewRef::Old)).unwrap();
+    assert_eq!(
+        deserializer.deserialize(bytes).unwrap(),
+        old.map(OldOrNew::Old)
+    );
+
+    let store = InmemStore::new(serialization());
+    let mut write_stream = store.write_stream(id);
+    block_on(async {
+        write_stream.commit_unconditionally(&admin_created()).await.unwrap();
+        write_stream
+            .commit_old_or_new(
+                OldOrNewRef::Old(&user::old::Revision::Deactivated_V0),
+                Condition::None,
+            )
+            .await
+            .unwrap();
+    });
+    let mut read_stream = store.read_stream(id);
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().try_collect().await
+    })
+    .unwrap();
+    let deactivated = user::Event::Deactivated { reason: String::new() };
+    assert_eq!(events, [admin_created(), deactivated]);
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_round_trip() {
+    use occur::store::serialization::cbor::cbor_serialization;
+    assert_round_trip(cbor_serialization::<user::Event>);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_round_trip() {
+    use occur::store::serialization::msgpack::msgpack_serialization;
+    assert_round_trip(msgpack_serialization::<user::Event>);
+}
@@ -0,0 +1,192 @@
+#![cfg(all(feature = "cbor", any(feature = "zstd", feature = "lz4")))]
+
+use std::time::SystemTime;
+
+use futures::executor::block_on;
+use futures::TryStreamExt as _;
+use occur::envelope::{Metadata, Recorded};
+use occur::revision::{OldOrNew, OldOrNewRef};
+use occur::store::inmem::InmemStore;
+use occur::store::serialization::cbor::{
+    cbor_serialization,
+    CborDeserializer,
+    CborSerializer,
+};
+use occur::store::serialization::compression::{
+    compressed,
+    Algorithm,
+    CompressedDeserializer,
+    CompressedSerializer,
+};
+use occur::store::serialization::{self, Serialization};
+use occur::store::{
+    Deserializer as _,
+    ReadStream as _,
+    Serializer as _,
+    Store as _,
+    WriteStream as _,
+};
+use occur::ErrorWithKind as _;
+use rstest::rstest;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+fn algorithms() -> Vec<Algorithm> {
+    #[cfg(feature = "zstd")]
+    use occur::store::serialization::compression::DEFAULT_ZSTD_LEVEL;
+
+    vec![
+        #[cfg(feature = "zstd")]
+        Algorithm::Zstd { level: DEFAULT_ZSTD_LEVEL },
+        #[cfg(feature = "lz4")]
+        Algorithm::Lz4,
+    ]
+}
+
+fn recorded(event: user::Event) -> Recorded<user::Event> {
+    Recorded { event, metadata: Metadata::new(), timestamp: SystemTime::now() }
+}
+
+/// An event whose serialized form is large, and compresses well.
+fn long_rename() -> user::Event {
+    user::Event::Renamed { new_name: "root".repeat(256) }
+}
+
+fn serializer(
+    algorithm: Algorithm,
+) -> CompressedSerializer<CborSerializer<user::Event>> {
+    CompressedSerializer::new(CborSerializer::new(), algorithm)
+}
+
+fn deserializer() -> CompressedDeserializer<CborDeserializer<user::Event>> {
+    CompressedDeserializer::new(CborDeserializer::new())
+}
+
+#[rstest]
+fn small_events_are_not_compressed(admin_created: user::Event) {
+    for algorithm in algorithms() {
+        let serializer = serializer(algorithm);
+        let recorded = recorded(admin_created.clone());
+
+        let bytes = serializer
+            .serialize(recorded.as_ref().map(OldOrNewRef::New))
+            .unwrap();
+
+        assert_eq!(bytes[0], 0);
+        assert_eq!(serializer.metrics().compressed_events(), 0);
+        assert_eq!(
+            deserializer().deserialize(bytes).unwrap(),
+            recorded.map(OldOrNew::New),
+        );
+    }
+}
+
+#[rstest]
+fn large_events_are_compressed() {
+    for algorithm in algorithms() {
+        let serializer = serializer(algorithm);
+        let recorded = recorded(long_rename());
+
+        let bytes = serializer
+            .serialize(recorded.as_ref().map(OldOrNewRef::New))
+            .unwrap();
+
+        assert_ne!(bytes[0], 0);
+        let metrics = serializer.metrics();
+        assert_eq!((metrics.events(), metrics.compressed_events()), (1, 1));
+        assert!(metrics.input_bytes() > 1024);
+        assert_eq!(metrics.output_bytes(), bytes.len() as u64);
+        assert!(metrics.ratio().unwrap() > 1.0);
+        assert_eq!(
+            deserializer().deserialize(bytes).unwrap(),
+            recorded.map(OldOrNew::New),
+        );
+    }
+}
+
+#[rstest]
+fn threshold_is_configurable(admin_created: user::Event) {
+    for algorithm in algorithms() {
+        let recorded = recorded(admin_created.clone());
+        let serialize = |serializer: CompressedSerializer<_>| {
+            serializer
+                .serialize(recorded.as_ref().map(OldOrNewRef::New))
+                .unwrap()
+        };
+
+        let bytes = serialize(serializer(algorithm).with_threshold(0));
+        assert_ne!(bytes[0], 0);
+        let bytes = serialize(serializer(algorithm).with_threshold(usize::MAX));
+        assert_eq!(bytes[0], 0);
+    }
+}
+
+#[rstest]
+fn malformed_payloads_fail() {
+    let deserializer = deserializer();
+
+    let empty = deserializer.deserialize(Vec::new()).unwrap_err();
+    let unknown_header = deserializer.deserialize(vec![42, 1, 2]).unwrap_err();
+
+    assert_eq!(empty.kind(), serialization::ErrorKind::Format);
+    assert_eq!(unknown_header.kind(), serialization::ErrorKind::Format);
+}
+
+#[rstest]
+fn oversized_payloads_fail() {
+    for algorithm in algorithms() {
+        let recorded = recorded(long_rename());
+        let bytes = serializer(algorithm)
+            .serialize(recorded.as_ref().map(OldOrNewRef::New))
+            .unwrap();
+
+        let err = deserializer()
+            .with_max_size(1024)
+            .deserialize(bytes.clone())
+            .unwrap_err();
+
+        assert_eq!(err.kind(), serialization::ErrorKind::Format);
+        assert_eq!(
+            deserializer().deserialize(bytes).unwrap(),
+            recorded.map(OldOrNew::New),
+        );
+    }
+
+    // a payload claiming to decompress to 4 GiB fails before it's allocated
+    #[cfg(feature = "lz4")]
+    {
+        let forged = vec![2, 0xff, 0xff, 0xff, 0xff, 0];
+        let err = deserializer().deserialize(forged).unwrap_err();
+        assert_eq!(err.kind(), serialization::ErrorKind::Format);
+    }
+}
+
+#[rstest]
+fn store_round_trip(admin_id: user::Id, admin_created: user::Event) {
+    for algorithm in algorithms() {
+        let Serialization { serializer, deserializer } =
+            compressed(cbor_serialization::<user::Event>(), algorithm);
+        let metrics_serializer = serializer.clone();
+        let store = InmemStore::new(Serialization { serializer, deserializer });
+        block_on(
+            store
+                .write_stream(admin_id)
+                .commit_many_unconditionally([&admin_created, &long_rename()]),
+        )
+        .unwrap();
+
+        let mut read_stream = store.read_stream(admin_id);
+        let events: Vec<_> = block_on(async {
+            read_stream.read_all().await.unwrap().try_collect().await
+        })
+        .unwrap();
+
+        assert_eq!(events, [admin_created.clone(), long_rename()]);
+        let metrics = metrics_serializer.metrics();
+        assert_eq!((metrics.events(), metrics.compressed_events()), (2, 1));
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+
+use occur::revision::{Convert, OldOrNew};
+use occur::Revision;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Revision)]
+enum Event {
+    Unit,
+    Tuple(u32, String),
+    Struct {
+        value: u32,
+    },
+    #[revision(version = 3)]
+    Versioned,
+    #[revision(name = "Renamed")]
+    Named,
+    #[revision(name = "Renamed", version = 1)]
+    NamedAndVersioned {
+        value: u32,
+    },
+}
+
+#[test]
+fn revision_of_each_variant() {
+    assert_eq!(Event::Unit.revision(), ("Unit", 0));
+    assert_eq!(Event::Tuple(0, String::new()).revision(), ("Tuple", 0));
+    assert_eq!(Event::Struct { value: 0 }.revision(), ("Struct", 0));
+    assert_eq!(Event::Versioned.revision(), ("Versioned", 3));
+    assert_eq!(Event::Named.revision(), ("Renamed", 0));
+    assert_eq!(
+        Event::NamedAndVersioned { value: 0 }.revision(),
+        ("Renamed", 1)
+    );
+}
+
+#[test]
+fn revision_set_matches_variants() {
+    assert_eq!(
+        Event::revision_set(),
+        HashSet::from([
+            ("Unit", 0),
+            ("Tuple", 0),
+            ("Struct", 0),
+            ("Versioned", 3),
+            ("Renamed", 0),
+            ("Renamed", 1),
+        ])
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Revision)]
+enum NewEvent {
+    #[revision(version = 2)]
+    Renamed { first_name: String, last_name: String },
+}
+
+impl occur::Event for NewEvent {
+    type StreamId = u32;
+    type OldRevision = OldEvent;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Convert)]
+enum OldEvent {
+    #[revision(name = "Renamed", version = 0)]
+    #[convert(into = Self, with = renamed_v0)]
+    Renamed_V0(String),
+
+    #[revision(name = "Renamed", version = 1)]
+    #[convert(into = NewEvent, with = renamed_v1)]
+    Renamed_V1 { name: String },
+}
+
+fn renamed_v0(old: OldEvent) -> OldEvent {
+    let OldEvent::Renamed_V0(name) = old else { unreachable!() };
+    OldEvent::Renamed_V1 { name }
+}
+
+fn renamed_v1(old: OldEvent) -> NewEvent {
+    let OldEvent::Renamed_V1 { name } = old else { unreachable!() };
+    let (first_name, last_name) = name.split_once(' ').unwrap();
+    NewEvent::Renamed {
+        first_name: first_name.to_owned(),
+        last_name: last_name.to_owned(),
+    }
+}
+
+#[test]
+fn derived_convert_implements_revision() {
+    assert_eq!(OldEvent::Renamed_V0(String::new()).revision(), ("Renamed", 0));
+    assert_eq!(
+        OldEvent::revision_set(),
+        HashSet::from([("Renamed", 0), ("Renamed", 1)])
+    );
+}
+
+#[test]
+fn convert_one_revision_at_a_time() {
+    let v0 = OldEvent::Renamed_V0("John Doe".to_owned());
+
+    let OldOrNew::Old(v1) = v0.convert() else { panic!("expected old") };
+    assert_eq!(v1, OldEvent::Renamed_V1 { name: "John Doe".to_owned() });
+
+    let OldOrNew::New(v2) = v1.convert() else { panic!("expected new") };
+    assert_eq!(v2, NewEvent::Renamed {
+        first_name: "John".to_owned(),
+        last_name: "Doe".to_owned(),
+    });
+}
+
+#[test]
+fn convert_until_new() {
+    let v0 = OldEvent::Renamed_V0("John Doe".to_owned());
+    assert_eq!(v0.convert_until_new(), NewEvent::Renamed {
+        first_name: "John".to_owned(),
+        last_name: "Doe".to_owned(),
+    });
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/revision_*.rs");
+    t.compile_fail("tests/ui/convert_*.rs");
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Revision)]
+enum LoopingNewEvent {
+    #[revision(name = "Renamed", version = 2)]
+    Renamed,
+}
+
+impl occur::Event for LoopingNewEvent {
+    type StreamId = u32;
+    type OldRevision = LoopingEvent;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Convert)]
+enum LoopingEvent {
+    #[revision(name = "Renamed", version = 0)]
+    #[convert(into = Self, with = identity)]
+    Renamed_V0,
+
+    #[revision(name = "Renamed", version = 1)]
+    #[convert(into = LoopingNewEvent, with = looping_v1)]
+    Renamed_V1,
+}
+
+const fn identity(old: LoopingEvent) -> LoopingEvent { old }
+
+const fn looping_v1(_: LoopingEvent) -> LoopingNewEvent {
+    LoopingNewEvent::Renamed
+}
+
+#[test]
+#[should_panic = "did not produce a newer revision"]
+fn converting_into_an_older_revision_panics() {
+    assert_eq!(
+        LoopingEvent::Renamed_V1.convert_until_new(),
+        LoopingNewEvent::Renamed
+    );
+    let _ = LoopingEvent::Renamed_V0.convert_until_new();
+}
@@ -2,12 +2,19 @@
 
 use std::assert_matches::assert_matches;
 
+use futures::executor::block_on;
+use occur::entity::{self, Rehydrated};
+use occur::store::inmem::{self, InmemStore};
+use occur::store::{Store as _, WriteStream as _};
 use occur::Entity;
+use rstest::rstest;
 use uuid::Uuid;
 
 use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
 
 mod example;
+mod fixture;
 
 #[test]
 fn new_entity_with_non_creation_event_returns_none() {
@@ -50,3 +57,103 @@ fn new_entity() {
 
     assert_eq!(next_admin, admin);
 }
+
+#[rstest]
+fn load_empty_stream(admin_id: user::Id) {
+    let store = InmemStore::new(inmem::no_serialization());
+
+    let rehydrated: Rehydrated<user::Entity> =
+        block_on(entity::load(&store, admin_id)).unwrap();
+
+    assert_eq!(rehydrated, Rehydrated::default());
+    assert_eq!(rehydrated.next_commit_number(), 0);
+}
+
+#[rstest]
+fn load_folds_all_events(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let friend_id = user::Id(Uuid::now_v7());
+    block_on(store.write_stream(admin_id).commit_many_unconditionally([
+        &admin_created,
+        &user::Event::Renamed { new_name: "root".to_owned() },
+        &user::Event::Befriended { user: friend_id },
+    ]))
+    .unwrap();
+
+    let rehydrated: Rehydrated<user::Entity> =
+        block_on(entity::load(&store, admin_id)).unwrap();
+
+    assert_eq!(rehydrated.last_commit_number, Some(2));
+    assert_eq!(rehydrated.next_commit_number(), 3);
+    assert_eq!(
+        rehydrated.entity,
+        Some(user::Entity {
+            id: admin_id,
+            name: "root".to_owned(),
+            is_admin: true,
+            promoted_to_admin_by: None,
+            friends: vec![friend_id],
+            is_deactivated: false,
+            deactivation_reason: None,
+        })
+    );
+}
+
+#[rstest]
+fn load_counts_events_preceding_creation(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let renamed = user::Event::Renamed { new_name: "root".to_owned() };
+    block_on(store.write_stream(admin_id).commit_unconditionally(&renamed))
+        .unwrap();
+
+    let rehydrated: Rehydrated<user::Entity> =
+        block_on(entity::load(&store, admin_id)).unwrap();
+    assert_eq!(rehydrated.entity, None);
+    assert_eq!(rehydrated.last_commit_number, Some(0));
+
+    block_on(store.write_stream(admin_id).commit_as_number(&admin_created, 1))
+        .unwrap();
+
+    let rehydrated: Rehydrated<user::Entity> =
+        block_on(entity::load(&store, admin_id)).unwrap();
+    assert_eq!(rehydrated.entity.unwrap().name, "admin");
+    assert_eq!(rehydrated.last_commit_number, Some(1));
+}
+
+#[rstest]
+fn rehydrate_from_previous_state(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut write_stream = store.write_stream(admin_id);
+    let mut read_stream = store.read_stream(admin_id);
+
+    block_on(write_stream.commit_unconditionally(&admin_created)).unwrap();
+    let rehydrated: Rehydrated<user::Entity> =
+        block_on(entity::rehydrate(admin_id, &mut read_stream)).unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(0));
+
+    // nothing was committed since
+    let rehydrated = block_on(entity::rehydrate_from(
+        admin_id,
+        &mut read_stream,
+        rehydrated,
+    ))
+    .unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(0));
+
+    let deactivated = user::Event::Deactivated { reason: "bye".to_owned() };
+    block_on(write_stream.commit_as_number(&deactivated, 1)).unwrap();
+    let rehydrated = block_on(entity::rehydrate_from(
+        admin_id,
+        &mut read_stream,
+        rehydrated,
+    ))
+    .unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(1));
+    assert!(rehydrated.entity.unwrap().is_deactivated);
+}
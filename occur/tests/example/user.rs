@@ -1,18 +1,37 @@
-use std::collections::HashSet;
+use std::str::FromStr;
 
 use derive_more::Display;
 use uuid::Uuid;
 
 #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Id(pub Uuid);
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl FromStr for Id {
+    type Err = uuid::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { s.parse().map(Self) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
-    Created { name: String, is_admin: bool },
-    Renamed { new_name: String },
-    Befriended { user: Id },
-    PromotedToAdmin { by: Id },
-    Deactivated { reason: String },
+    Created {
+        name: String,
+        is_admin: bool,
+    },
+    Renamed {
+        new_name: String,
+    },
+    Befriended {
+        user: Id,
+    },
+    PromotedToAdmin {
+        by: Id,
+    },
+    #[revision(version = 1)]
+    Deactivated {
+        reason: String,
+    },
 }
 
 impl occur::Event for Event {
@@ -20,31 +39,8 @@ impl occur::Event for Event {
     type OldRevision = old::Revision;
 }
 
-impl occur::Revision for Event {
-    type Value = (&'static str, u8);
-
-    fn revision(&self) -> Self::Value {
-        match &self {
-            Event::Created { .. } => ("Created", 0),
-            Event::Renamed { .. } => ("Renamed", 0),
-            Event::Befriended { .. } => ("Befriended", 0),
-            Event::PromotedToAdmin { .. } => ("PromotedToAdmin", 0),
-            Event::Deactivated { .. } => ("Deactivated", 1),
-        }
-    }
-
-    fn revision_set() -> HashSet<Self::Value> {
-        HashSet::from([
-            ("Created", 0),
-            ("Renamed", 0),
-            ("Befriended", 0),
-            ("PromotedToAdmin", 0),
-            ("Deactivated", 1),
-        ])
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entity {
     pub id: Id,
     pub name: String,
@@ -104,42 +100,20 @@ impl occur::Entity<Event> for Entity {
 }
 
 pub mod old {
-    use std::collections::HashSet;
-
     use occur::revision;
-    use occur::revision::OldOrNew;
 
     use crate::example::user::Event;
 
     #[allow(non_camel_case_types)]
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, revision::Convert)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum Revision {
+        #[revision(name = "Deactivated", version = 0)]
+        #[convert(into = Event, with = deactivated_v0)]
         Deactivated_V0,
     }
 
-    impl occur::Revision for Revision {
-        type Value = (&'static str, u8);
-
-        fn revision(&self) -> Self::Value {
-            match &self {
-                Revision::Deactivated_V0 => ("Deactivated", 0),
-            }
-        }
-
-        fn revision_set() -> HashSet<Self::Value> {
-            HashSet::from([("Deactivated", 0)])
-        }
-    }
-
-    impl revision::Convert for Revision {
-        type Event = Event;
-
-        fn convert(self) -> OldOrNew<Self::Event> {
-            match self {
-                Self::Deactivated_V0 => {
-                    Event::Deactivated { reason: "".to_owned() }.into()
-                }
-            }
-        }
+    fn deactivated_v0(_: Revision) -> Event {
+        Event::Deactivated { reason: "".to_owned() }
     }
 }
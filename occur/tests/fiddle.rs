@@ -1,8 +1,6 @@
 #![allow(dead_code)]
 
-use std::collections::HashSet;
 use std::fmt::Debug;
-use std::sync::{Arc, Mutex};
 
 use futures::task::SpawnExt as _;
 use futures::{join, StreamExt};
@@ -23,7 +21,7 @@ use crate::TvShowTrackEvent::{Created, WatchedEpisode};
 #[derive(Clone, PartialEq, Eq, Hash)]
 struct TvShowTrackId(Uuid);
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Revision)]
 enum TvShowTrackEvent {
     Created { tv_show_name: String },
     WatchedEpisode { season: u64, episode: u64 },
@@ -34,21 +32,6 @@ impl occur::Event for TvShowTrackEvent {
     type OldRevision = revision::Empty<Self>;
 }
 
-impl Revision for TvShowTrackEvent {
-    type Value = (&'static str, u8);
-
-    fn revision(&self) -> Self::Value {
-        match &self {
-            Created { .. } => ("Created", 0),
-            WatchedEpisode { .. } => ("WatchedEpisode", 0),
-        }
-    }
-
-    fn revision_set() -> HashSet<Self::Value> {
-        HashSet::from([("Created", 0), ("WatchedEpisode", 0)])
-    }
-}
-
 #[test]
 fn fiddle() {
     println!("\n----------------------- [ ThreadPool read test ]");
@@ -61,7 +44,7 @@ fn fiddle() {
 
     let create_store = || InmemStore::new(inmem::no_serialization());
 
-    let mut store = create_store();
+    let store = create_store();
 
     // remove "thread-pool" feature from futures if not using thread-pool
     let pool = futures::executor::ThreadPool::new().unwrap();
@@ -90,14 +73,14 @@ fn fiddle() {
     let e2 = WatchedEpisode { season: 1, episode: 2 };
     let e3 = WatchedEpisode { season: 1, episode: 3 };
 
-    let store = Mutex::new(create_store());
+    let store = create_store();
 
     // remove "thread-pool" feature from futures if not using thread-pool
     let pool = futures::executor::ThreadPool::new().unwrap();
 
     let t = pool.spawn_with_handle(async move {
         {
-            let mut stream = store.lock().unwrap().write_stream(id.clone());
+            let mut stream = store.write_stream(id.clone());
             stream
                 .commit_many([&e0, &e1], write::Condition::None)
                 .await
@@ -105,7 +88,7 @@ fn fiddle() {
         }
 
         let f1 = async {
-            let mut stream = store.lock().unwrap().read_stream(id.clone());
+            let mut stream = store.read_stream(id.clone());
             let mut it = stream
                 .read_unconverted(read::Options {
                     position: read::Position::CommitNumber(1),
@@ -120,7 +103,7 @@ fn fiddle() {
         };
 
         let f2 = async {
-            let mut stream = store.lock().unwrap().write_stream(id.clone());
+            let mut stream = store.write_stream(id.clone());
             stream.commit_unconditionally(&e2).await.expect("wtf?");
             stream.commit_unconditionally(&e3).await.expect("wtf?");
         };
@@ -138,18 +121,18 @@ fn fiddle() {
     let e2 = WatchedEpisode { season: 1, episode: 2 };
     let e3 = WatchedEpisode { season: 1, episode: 3 };
 
-    let store = Arc::new(Mutex::new(create_store()));
+    let store = create_store();
 
     // remove "thread-pool" feature from futures if not using thread-pool
     let mut pool = futures::executor::LocalPool::new();
     let spawner = pool.spawner();
 
     let id2 = id.clone();
-    let store2 = Arc::clone(&store);
+    let store2 = store.clone();
 
     spawner
         .spawn(async move {
-            let mut stream = store2.lock().unwrap().write_stream(id2);
+            let mut stream = store2.write_stream(id2);
             stream.commit_unconditionally(&e0).await.expect("wtf?");
             stream.commit_unconditionally(&e1).await.expect("wtf?");
         })
@@ -158,11 +141,11 @@ fn fiddle() {
     pool.run();
 
     let id2 = id.clone();
-    let store2 = Arc::clone(&store);
+    let store2 = store.clone();
 
     spawner
         .spawn(async move {
-            let mut stream = store2.lock().unwrap().read_stream(id2);
+            let mut stream = store2.read_stream(id2);
             let mut it = stream
                 .read_unconverted(read::Options {
                     position: read::Position::Last,
@@ -171,7 +154,10 @@ fn fiddle() {
                 })
                 .await
                 .expect("wtf?");
-            while let Some(revision::OldOrNew::New(event)) = it.next().await {
+            while let Some(Ok(read::MaybeUnknown::Known(
+                revision::OldOrNew::New(event),
+            ))) = it.next().await
+            {
                 println!("subscriber read {:?}", event);
                 if let WatchedEpisode { episode, season: _ } = event {
                     if episode == 3 {
@@ -183,11 +169,11 @@ fn fiddle() {
         .expect("wtf?");
 
     let id2 = id.clone();
-    let store2 = Arc::clone(&store);
+    let store2 = store.clone();
 
     spawner
         .spawn(async move {
-            let mut stream = store2.lock().unwrap().write_stream(id2);
+            let mut stream = store2.write_stream(id2);
             stream.commit_unconditionally(&e2).await.expect("wtf?");
             stream.commit_unconditionally(&e3).await.expect("wtf?");
         })
@@ -0,0 +1,437 @@
+#![cfg(all(feature = "file", feature = "cbor"))]
+#![feature(assert_matches)]
+
+use std::assert_matches::assert_matches;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use futures::executor::block_on;
+use futures::{FutureExt as _, TryStreamExt as _};
+use occur::envelope::Envelope;
+use occur::store::file::{FileStore, OpenError};
+use occur::store::serialization::cbor::{
+    cbor_serialization,
+    CborDeserializer,
+    CborSerializer,
+};
+use occur::store::{
+    all,
+    write,
+    AllStream as _,
+    GlobalStore as _,
+    ReadStream as _,
+    Store as _,
+    WriteStream as _,
+};
+use occur_conformance::{IDEMPOTENCY_WINDOW, READ_PAGE_SIZE};
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+type Store = FileStore<
+    user::Event,
+    CborSerializer<user::Event>,
+    CborDeserializer<user::Event>,
+>;
+
+fn open(dir: &Path) -> Store {
+    FileStore::open(dir, cbor_serialization()).unwrap()
+}
+
+/// Returns a store in a new directory of its own.
+///
+/// The directory is kept once the test is done, as it must outlive the store,
+/// which the test owns. It's within the target directory, and is removed
+/// along with it.
+fn store() -> FileStore<
+    occur_conformance::Event,
+    CborSerializer<occur_conformance::Event>,
+    CborDeserializer<occur_conformance::Event>,
+> {
+    let dir = tempfile::tempdir_in(env!("CARGO_TARGET_TMPDIR")).unwrap();
+    FileStore::open(dir.keep(), cbor_serialization())
+        .unwrap()
+        .with_read_page_size(READ_PAGE_SIZE)
+        .with_idempotency_window(IDEMPOTENCY_WINDOW)
+}
+
+occur_conformance::tests!(store(), [
+    write,
+    read,
+    idempotency,
+    envelope,
+    all,
+    subscribe,
+]);
+
+fn renamed(i: usize) -> user::Event {
+    user::Event::Renamed { new_name: i.to_string() }
+}
+
+fn read_all(store: &Store, id: user::Id) -> Vec<user::Event> {
+    let mut stream = store.read_stream(id);
+    block_on(async { stream.read_all().await.unwrap().try_collect().await })
+        .unwrap()
+}
+
+fn read_all_items(store: &Store) -> Vec<all::Item<user::Id, user::Event>> {
+    let mut stream = store.all_stream();
+    block_on(async { stream.read_all().await.unwrap().try_collect().await })
+        .unwrap()
+}
+
+/// Returns the paths of the segments of the log in `dir`, in order.
+fn segments(dir: &Path) -> Vec<PathBuf> {
+    let mut segments: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    segments.sort();
+    segments
+}
+
+/// Returns a store in `dir` with `count` events committed to the stream of
+/// `id`, one commit each.
+fn store_with_events(dir: &Path, id: user::Id, count: usize) -> Store {
+    let store = open(dir);
+    let mut stream = store.write_stream(id);
+    block_on(async {
+        for i in 0..count {
+            stream.commit_unconditionally(&renamed(i)).await.unwrap();
+        }
+    });
+    store
+}
+
+#[rstest]
+fn commits_are_idempotent(admin_id: user::Id, admin_created: user::Event) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path());
+    let created = Envelope::new(&admin_created);
+    let (first, second) = (renamed(0), renamed(1));
+    let batch = [Envelope::new(&first), Envelope::new(&second)];
+    let mut stream = store.write_stream(admin_id);
+
+    let commit_created = |stream: &mut <Store as occur::Store>::WriteStream| {
+        block_on(
+            stream.commit_envelope(created.clone(), write::Condition::None),
+        )
+    };
+    assert_matches!(commit_created(&mut stream), Ok(0));
+    assert_matches!(commit_created(&mut stream), Ok(0));
+    let commit_batch = |stream: &mut <Store as occur::Store>::WriteStream| {
+        block_on(
+            stream.commit_many_envelopes(batch.clone(), write::Condition::None),
+        )
+    };
+    assert_matches!(commit_batch(&mut stream), Ok(Some(1)));
+    assert_matches!(commit_batch(&mut stream), Ok(Some(1)));
+
+    // the IDs of committed events are remembered when the store is reopened
+    drop((stream, store));
+    let store = open(dir.path());
+    let mut stream = store.write_stream(admin_id);
+    assert_matches!(commit_created(&mut stream), Ok(0));
+    assert_matches!(commit_batch(&mut stream), Ok(Some(1)));
+    assert_eq!(read_all(&store, admin_id), [admin_created, first, second]);
+}
+
+#[rstest]
+fn reopened_store_honors_idempotency_window(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let dir = tempfile::tempdir().unwrap();
+    let created = Envelope::new(&admin_created);
+    let commit_created = |store: &Store| {
+        block_on(
+            store
+                .write_stream(admin_id)
+                .commit_envelope(created.clone(), write::Condition::None),
+        )
+    };
+    assert_matches!(commit_created(&open(dir.path())), Ok(0));
+
+    // the IDs remembered when the store is opened are forgotten
+    let store = open(dir.path()).with_idempotency_window(0);
+    assert_matches!(commit_created(&store), Ok(1));
+    assert_eq!(read_all(&store, admin_id), [
+        admin_created.clone(),
+        admin_created
+    ]);
+}
+
+#[rstest]
+fn events_survive_reopening(admin_id: user::Id, admin_created: user::Event) {
+    let dir = tempfile::tempdir().unwrap();
+    let other_id = user::Id(Uuid::now_v7());
+    let store = open(dir.path());
+    block_on(async {
+        store
+            .write_stream(admin_id)
+            .commit_unconditionally(&admin_created)
+            .await
+            .unwrap();
+        store
+            .write_stream(other_id)
+            .commit_many_unconditionally([&renamed(0), &renamed(1)])
+            .await
+            .unwrap();
+    });
+    drop(store);
+
+    let store = open(dir.path());
+    assert_eq!(read_all(&store, admin_id), [admin_created]);
+    assert_eq!(read_all(&store, other_id), [renamed(0), renamed(1)]);
+
+    // commit numbers and global positions follow the reopened log
+    let commit_number = block_on(
+        store.write_stream(other_id).commit_unconditionally(&renamed(2)),
+    );
+    assert_matches!(commit_number, Ok(2));
+    let items: Vec<_> = read_all_items(&store)
+        .into_iter()
+        .map(|item| (item.stream_id, item.commit_number, item.global_position))
+        .collect();
+    assert_eq!(items, [
+        (admin_id, 0, 0),
+        (other_id, 0, 1),
+        (other_id, 1, 2),
+        (other_id, 2, 3),
+    ]);
+}
+
+#[rstest]
+fn segments_roll_over(admin_id: user::Id) {
+    let dir = tempfile::tempdir().unwrap();
+    // each segment is sealed after its first commit
+    let store = open(dir.path()).with_segment_size(1);
+    let mut stream = store.write_stream(admin_id);
+    block_on(async {
+        stream.commit_unconditionally(&renamed(0)).await.unwrap();
+        stream
+            .commit_many_unconditionally([&renamed(1), &renamed(2)])
+            .await
+            .unwrap();
+        stream.commit_unconditionally(&renamed(3)).await.unwrap();
+    });
+
+    let names: Vec<_> = segments(dir.path())
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(names, [
+        "00000000000000000000.log",
+        "00000000000000000001.log",
+        "00000000000000000003.log",
+    ]);
+    let expected: Vec<_> = (0..4).map(renamed).collect();
+    assert_eq!(read_all(&store, admin_id), expected);
+    drop((stream, store));
+    assert_eq!(read_all(&open(dir.path()), admin_id), expected);
+}
+
+#[rstest]
+fn log_is_locked_while_open(admin_id: user::Id, admin_created: user::Event) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path());
+    let clone = store.clone();
+    drop(store);
+
+    let result =
+        FileStore::<user::Event, _, _>::open(dir.path(), cbor_serialization());
+    assert_matches!(result.err(), Some(OpenError::Locked));
+
+    // the lock is released once all clones of the store are dropped
+    block_on(
+        clone.write_stream(admin_id).commit_unconditionally(&admin_created),
+    )
+    .unwrap();
+    drop(clone);
+    assert_eq!(read_all(&open(dir.path()), admin_id), [admin_created]);
+}
+
+/// Ways in which the last commit of a log could be torn by a crash.
+#[derive(Clone, Copy, Debug)]
+enum Tear {
+    /// Only a part of the commit was written.
+    Truncated,
+    /// The commit was entirely written, but a part of it never reached the
+    /// disk (e.g., as it was zero-filled).
+    Zeroed,
+    /// An incomplete header of another commit follows the last one.
+    Garbage,
+}
+
+/// Commits 3 events to the stream of `id` in `dir`, and then tears the last
+/// one.
+fn tear(dir: &Path, id: user::Id, tear: Tear) {
+    store_with_events(dir, id, 3);
+    let segment = segments(dir).pop().unwrap();
+    let len = fs::metadata(&segment).unwrap().len();
+    match tear {
+        Tear::Truncated => {
+            OpenOptions::new()
+                .write(true)
+                .open(&segment)
+                .unwrap()
+                .set_len(len - 3)
+                .unwrap();
+        }
+        Tear::Zeroed => {
+            let mut bytes = fs::read(&segment).unwrap();
+            let end = bytes.len();
+            bytes[end - 8..].fill(0);
+            fs::write(&segment, bytes).unwrap();
+        }
+        Tear::Garbage => {
+            OpenOptions::new()
+                .append(true)
+                .open(&segment)
+                .unwrap()
+                .write_all(&[42, 0, 0])
+                .unwrap();
+        }
+    }
+}
+
+#[rstest]
+fn torn_writes_are_truncated(
+    admin_id: user::Id,
+    #[values(Tear::Truncated, Tear::Zeroed, Tear::Garbage)] how: Tear,
+) {
+    let dir = tempfile::tempdir().unwrap();
+    tear(dir.path(), admin_id, how);
+
+    let store = open(dir.path());
+    let expected = match how {
+        Tear::Truncated | Tear::Zeroed => vec![renamed(0), renamed(1)],
+        Tear::Garbage => vec![renamed(0), renamed(1), renamed(2)],
+    };
+    assert_eq!(read_all(&store, admin_id), expected);
+
+    // the log can be appended to after the truncation
+    let commit_number = expected.len() as u32;
+    assert_matches!(
+        block_on(
+            store.write_stream(admin_id).commit_unconditionally(&renamed(9)),
+        ),
+        Ok(n) if n == commit_number
+    );
+    drop(store);
+    let expected: Vec<_> = expected.into_iter().chain([renamed(9)]).collect();
+    assert_eq!(read_all(&open(dir.path()), admin_id), expected);
+}
+
+#[rstest]
+fn corruption_before_the_end_fails_to_open(admin_id: user::Id) {
+    let dir = tempfile::tempdir().unwrap();
+    store_with_events(dir.path(), admin_id, 3);
+    let segment = segments(dir.path()).pop().unwrap();
+    let mut bytes = fs::read(&segment).unwrap();
+    // flips a bit within the body of the first record
+    bytes[20] ^= 1;
+    fs::write(&segment, bytes).unwrap();
+
+    let result =
+        FileStore::<user::Event, _, _>::open(dir.path(), cbor_serialization());
+
+    assert_matches!(
+        result.err(),
+        Some(OpenError::Corrupted { segment: path, offset: 0, .. })
+            if path == segment
+    );
+}
+
+#[rstest]
+fn streams_are_committed_to_concurrently(admin_id: user::Id) {
+    const THREADS: usize = 8;
+    const COMMITS_PER_THREAD: usize = 16;
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path()).with_segment_size(1024);
+
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                let own_id = user::Id(Uuid::now_v7());
+                for i in 0..COMMITS_PER_THREAD {
+                    block_on(async {
+                        store
+                            .write_stream(own_id)
+                            .commit_unconditionally(&renamed(i))
+                            .await
+                            .unwrap();
+                        store
+                            .write_stream(admin_id)
+                            .commit_unconditionally(&renamed(i))
+                            .await
+                            .unwrap();
+                    });
+                }
+            });
+        }
+    });
+    drop(store);
+
+    let store = open(dir.path());
+    assert_eq!(read_all(&store, admin_id).len(), THREADS * COMMITS_PER_THREAD);
+    let global_positions: Vec<_> = read_all_items(&store)
+        .into_iter()
+        .map(|item| item.global_position)
+        .collect();
+    let n_events = 2 * THREADS * COMMITS_PER_THREAD;
+    assert_eq!(global_positions, (0..n_events as u64).collect::<Vec<_>>());
+}
+
+#[rstest]
+fn dropped_commits_are_committed_entirely_or_not_at_all(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    const COMMITS: usize = 512;
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path()).with_read_page_size(1);
+    let mut stream = store.write_stream(admin_id);
+    block_on(stream.commit_unconditionally(&admin_created)).unwrap();
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        // reads contend for the lock of the global log, so that commits are
+        // dropped while waiting for it
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                read_all_items(&store);
+            }
+        });
+        for i in 0..COMMITS {
+            // drops the commit if it's still pending after a single poll
+            let _ = stream.commit_unconditionally(&renamed(i)).now_or_never();
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+    let events = read_all(&store, admin_id);
+    drop((stream, store));
+
+    let store = open(dir.path());
+    assert_eq!(read_all(&store, admin_id), events);
+    let global_positions: Vec<_> = read_all_items(&store)
+        .into_iter()
+        .map(|item| item.global_position)
+        .collect();
+    assert_eq!(global_positions, (0..events.len() as u64).collect::<Vec<_>>());
+    assert_matches!(
+        block_on(
+            store.write_stream(admin_id).commit_unconditionally(&renamed(0)),
+        ),
+        Ok(n) if n as usize == events.len()
+    );
+}
@@ -0,0 +1,184 @@
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use futures::executor::block_on;
+use futures::{StreamExt as _, TryStreamExt as _};
+use occur::envelope::{Envelope, Metadata, Recorded};
+use occur::store::clock::Clock;
+use occur::store::inmem::{self, InmemStore, NoSerializer};
+use occur::store::{
+    read,
+    write,
+    AllStream as _,
+    GlobalStore as _,
+    ReadStream as _,
+    Store as _,
+    Subscribe as _,
+    WriteStream as _,
+};
+use occur_conformance::{IDEMPOTENCY_WINDOW, READ_PAGE_SIZE};
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+const THREADS: usize = 8;
+const COMMITS_PER_THREAD: usize = 16;
+
+type Store = InmemStore<
+    occur_conformance::Event,
+    NoSerializer<occur_conformance::Event>,
+    NoSerializer<occur_conformance::Event>,
+>;
+
+fn store() -> Store {
+    InmemStore::new(inmem::no_serialization())
+        .with_read_page_size(READ_PAGE_SIZE)
+        .with_idempotency_window(IDEMPOTENCY_WINDOW)
+}
+
+occur_conformance::tests!(store(), [
+    write,
+    read,
+    idempotency,
+    envelope,
+    all,
+    subscribe,
+]);
+
+fn renamed(new_name: &str) -> user::Event {
+    user::Event::Renamed { new_name: new_name.to_owned() }
+}
+
+/// A clock that always returns the same time.
+struct FixedClock(SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime { self.0 }
+}
+
+#[rstest]
+fn streams_are_opened_concurrently(admin_id: user::Id) {
+    let store = InmemStore::new(inmem::no_serialization());
+
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                let own_id = user::Id(Uuid::now_v7());
+                for i in 0..COMMITS_PER_THREAD {
+                    let event = renamed(&i.to_string());
+                    block_on(async {
+                        store
+                            .write_stream(own_id)
+                            .commit_unconditionally(&event)
+                            .await
+                            .unwrap();
+                        store
+                            .write_stream(admin_id)
+                            .commit_unconditionally(&event)
+                            .await
+                            .unwrap();
+                    });
+                }
+            });
+        }
+    });
+
+    let mut read_stream = store.read_stream(admin_id);
+    let admin_events =
+        block_on(async { read_stream.read_all().await.unwrap().count().await });
+    assert_eq!(admin_events, THREADS * COMMITS_PER_THREAD);
+
+    let mut all_stream = store.all_stream();
+    let all_events =
+        block_on(async { all_stream.read_all().await.unwrap().count().await });
+    assert_eq!(all_events, 2 * THREADS * COMMITS_PER_THREAD);
+}
+
+#[rstest]
+fn clones_share_events(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let clone = store.clone();
+    let event = admin_created.clone();
+
+    let handle = thread::spawn(move || {
+        block_on(clone.write_stream(admin_id).commit_unconditionally(&event))
+            .unwrap();
+    });
+    handle.join().unwrap();
+
+    let mut read_stream = store.read_stream(admin_id);
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().try_collect().await
+    })
+    .unwrap();
+    assert_eq!(events, [admin_created]);
+}
+
+#[rstest]
+fn commits_are_timestamped_by_the_clock(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let store =
+        InmemStore::new(inmem::no_serialization()).with_clock(FixedClock(now));
+    let metadata = Metadata::new();
+    block_on(store.write_stream(admin_id).commit_envelope(
+        Envelope::new(&admin_created).with_metadata(metadata.clone()),
+        write::Condition::None,
+    ))
+    .unwrap();
+
+    let mut read_stream = store.read_stream(admin_id);
+    let recorded: Vec<_> = block_on(async {
+        read_stream
+            .read_recorded(read::Options {
+                position: read::Position::First,
+                direction: read::Direction::Forward,
+                limit: None,
+            })
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+    })
+    .unwrap();
+    assert_eq!(recorded, [Recorded {
+        event: admin_created,
+        metadata,
+        timestamp: now,
+    }]);
+}
+
+#[rstest]
+fn subscriber_is_woken_by_commit_on_another_thread(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut write_stream = store.write_stream(admin_id);
+    let mut read_stream = store.read_stream(admin_id);
+    let subscription =
+        block_on(read_stream.subscribe(read::Position::First)).unwrap();
+
+    let writer =
+        thread::spawn(move || {
+            block_on(write_stream.commit_many_unconditionally([
+                &admin_created,
+                &renamed("root"),
+            ]))
+            .unwrap();
+        });
+
+    let events: Vec<_> =
+        block_on(subscription.map(Result::unwrap).take(2).collect());
+    writer.join().unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1], (1, renamed("root")));
+}
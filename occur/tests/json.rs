@@ -0,0 +1,152 @@
+#![cfg(feature = "serde")]
+
+use std::time::SystemTime;
+
+use futures::executor::block_on;
+use futures::TryStreamExt as _;
+use occur::envelope::{Metadata, Recorded};
+use occur::revision::{OldOrNew, OldOrNewRef};
+use occur::store::inmem::InmemStore;
+use occur::store::serialization::json::{
+    json_serialization,
+    JsonDeserializer,
+    JsonSerializer,
+};
+use occur::store::serialization::{self, Serialization};
+use occur::store::write::Condition;
+use occur::store::{
+    read,
+    Deserializer as _,
+    ReadStream as _,
+    Serializer,
+    Store as _,
+    WriteStream as _,
+};
+use occur::ErrorWithKind as _;
+use rstest::rstest;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+fn recorded<E>(event: E) -> Recorded<E> {
+    Recorded { event, metadata: Metadata::new(), timestamp: SystemTime::now() }
+}
+
+#[rstest]
+fn new_events_round_trip(admin_created: user::Event) {
+    let recorded = recorded(admin_created);
+
+    let json = JsonSerializer::new()
+        .serialize(recorded.as_ref().map(OldOrNewRef::New))
+        .unwrap();
+    let deserialized = JsonDeserializer::new().deserialize(json).unwrap();
+
+    assert_eq!(deserialized, recorded.map(OldOrNew::New));
+}
+
+#[rstest]
+fn old_revisions_round_trip() {
+    let recorded = recorded(user::old::Revision::Deactivated_V0);
+
+    let json = JsonSerializer::<user::Event>::new()
+        .serialize(recorded.as_ref().map(OldOrNewRef::Old))
+        .unwrap();
+    let deserialized =
+        JsonDeserializer::<user::Event>::new().deserialize(json).unwrap();
+
+    assert_eq!(deserialized, recorded.map(OldOrNew::Old));
+}
+
+#[rstest]
+fn revision_is_recorded(admin_created: user::Event) {
+    let json = JsonSerializer::<user::Event>::new()
+        .serialize(recorded(OldOrNewRef::New(&admin_created)))
+        .unwrap();
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["revision"], serde_json::json!(["Created", 0]));
+}
+
+#[rstest]
+fn unknown_revisions_fail(admin_created: user::Event) {
+    let json = JsonSerializer::<user::Event>::new()
+        .serialize(recorded(OldOrNewRef::New(&admin_created)))
+        .unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["revision"] = serde_json::json!(["Created", 7]);
+
+    let result =
+        JsonDeserializer::<user::Event>::new().deserialize(value.to_string());
+
+    assert_eq!(
+        result.unwrap_err().kind(),
+        serialization::ErrorKind::UnknownRevision,
+    );
+}
+
+#[rstest]
+fn malformed_events_fail() {
+    let result =
+        JsonDeserializer::<user::Event>::new().deserialize("{".to_owned());
+
+    assert_eq!(result.unwrap_err().kind(), serialization::ErrorKind::Format);
+}
+
+/// Serializes every event as malformed JSON.
+#[derive(Clone)]
+struct MalformedSerializer;
+
+impl Serializer for MalformedSerializer {
+    type Event = user::Event;
+    type SerializedEvent = String;
+
+    fn serialize(
+        &self,
+        _recorded: Recorded<OldOrNewRef<Self::Event>>,
+    ) -> Result<Self::SerializedEvent, serialization::Error> {
+        Ok("{".to_owned())
+    }
+}
+
+#[rstest]
+fn malformed_events_fail_to_read(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(Serialization {
+        serializer: MalformedSerializer,
+        deserializer: JsonDeserializer::new(),
+    });
+    block_on(
+        store.write_stream(admin_id).commit_unconditionally(&admin_created),
+    )
+    .unwrap();
+
+    let mut read_stream = store.read_stream(admin_id);
+    let mut events = block_on(read_stream.read_all()).unwrap();
+
+    let err = block_on(events.try_next()).unwrap_err();
+    assert_eq!(err.kind(), read::ErrorKind::Serialization);
+}
+
+#[rstest]
+fn old_revisions_are_converted_when_read(admin_id: user::Id) {
+    let store = InmemStore::new(json_serialization::<user::Event>());
+    let mut write_stream = store.write_stream(admin_id);
+    let old = user::old::Revision::Deactivated_V0;
+    block_on(
+        write_stream.commit_old_or_new(OldOrNewRef::Old(&old), Condition::None),
+    )
+    .unwrap();
+
+    let mut read_stream = store.read_stream(admin_id);
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().try_collect().await
+    })
+    .unwrap();
+
+    assert_eq!(events, [user::Event::Deactivated { reason: String::new() }]);
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use futures::executor::block_on;
+use futures::FutureExt as _;
+use occur::projection::{Lag, Projection, ProjectionRunner};
+use occur::store::inmem::{self, InmemStore};
+use occur::store::{
+    CommitNumber,
+    GlobalStore as _,
+    Store as _,
+    WriteStream as _,
+};
+use occur::subscription::InmemCheckpointStore;
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+/// Holds the current name of each user.
+#[derive(Default)]
+struct UserNames(HashMap<user::Id, String>);
+
+impl Projection<user::Event> for UserNames {
+    fn name(&self) -> &str { "user_names" }
+
+    fn apply(&mut self, id: &user::Id, _: CommitNumber, event: user::Event) {
+        match event {
+            user::Event::Created { name, .. }
+            | user::Event::Renamed { new_name: name } => {
+                self.0.insert(*id, name);
+            }
+            _ => {}
+        }
+    }
+
+    fn reset(&mut self) { self.0.clear(); }
+}
+
+/// Counts the number of applied events.
+#[derive(Default)]
+struct EventCount(u64);
+
+impl Projection<user::Event> for EventCount {
+    fn name(&self) -> &str { "event_count" }
+
+    fn apply(&mut self, _: &user::Id, _: CommitNumber, _: user::Event) {
+        self.0 += 1;
+    }
+
+    fn reset(&mut self) { self.0 = 0; }
+}
+
+fn renamed(new_name: &str) -> user::Event {
+    user::Event::Renamed { new_name: new_name.to_owned() }
+}
+
+#[rstest]
+fn catch_up_projections(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let other_id = user::Id(Uuid::now_v7());
+    let mut runner =
+        ProjectionRunner::new(store.all_stream(), InmemCheckpointStore::new());
+    let mut names = UserNames::default();
+    let mut count = EventCount::default();
+
+    // nothing to catch up on
+    block_on(runner.catch_up(&mut [&mut names, &mut count])).unwrap();
+    assert_eq!(block_on(runner.lag(&count)).unwrap(), Lag {
+        checkpoint: None,
+        head: None,
+    });
+
+    block_on(
+        store
+            .write_stream(admin_id)
+            .commit_many_unconditionally([&admin_created, &renamed("root")]),
+    )
+    .unwrap();
+    block_on(runner.catch_up(&mut [&mut names])).unwrap();
+    assert_eq!(names.0[&admin_id], "root");
+
+    block_on(store.write_stream(other_id).commit_unconditionally(
+        &user::Event::Created { name: "other".to_owned(), is_admin: false },
+    ))
+    .unwrap();
+    let lag = block_on(runner.lag(&count)).unwrap();
+    assert_eq!(lag, Lag { checkpoint: None, head: Some(2) });
+    assert_eq!(lag.events_behind(), 3);
+    assert_eq!(block_on(runner.lag(&names)).unwrap().events_behind(), 1);
+
+    // each projection resumes from its own checkpoint
+    block_on(runner.catch_up(&mut [&mut names, &mut count])).unwrap();
+    assert_eq!(names.0.len(), 2);
+    assert_eq!(names.0[&other_id], "other");
+    assert_eq!(count.0, 3);
+    assert_eq!(block_on(runner.lag(&names)).unwrap().events_behind(), 0);
+    assert_eq!(block_on(runner.lag(&count)).unwrap().events_behind(), 0);
+}
+
+#[rstest]
+fn rebuild_projection(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    block_on(
+        store
+            .write_stream(admin_id)
+            .commit_many_unconditionally([&admin_created, &renamed("root")]),
+    )
+    .unwrap();
+    let mut runner =
+        ProjectionRunner::new(store.all_stream(), InmemCheckpointStore::new());
+    let mut count = EventCount::default();
+
+    block_on(runner.catch_up(&mut [&mut count])).unwrap();
+    assert_eq!(count.0, 2);
+
+    // the projection's state was lost, e.g., after a restart
+    count.0 = 0;
+    block_on(runner.catch_up(&mut [&mut count])).unwrap();
+    assert_eq!(count.0, 0);
+
+    block_on(runner.rebuild(&mut count)).unwrap();
+    assert_eq!(count.0, 2);
+}
+
+#[rstest]
+fn run_projections(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut write_stream = store.write_stream(admin_id);
+    block_on(write_stream.commit_unconditionally(&admin_created)).unwrap();
+    let mut runner =
+        ProjectionRunner::new(store.all_stream(), InmemCheckpointStore::new());
+    let mut names = UserNames::default();
+
+    {
+        let mut projections: [&mut dyn Projection<_>; 1] = [&mut names];
+        let mut run = Box::pin(runner.run(&mut projections));
+        assert!(run.as_mut().now_or_never().is_none());
+
+        block_on(write_stream.commit_unconditionally(&renamed("root")))
+            .unwrap();
+        assert!(run.as_mut().now_or_never().is_none());
+    }
+
+    assert_eq!(names.0[&admin_id], "root");
+    assert_eq!(block_on(runner.lag(&names)).unwrap().events_behind(), 0);
+}
@@ -0,0 +1,160 @@
+#![feature(assert_matches)]
+
+use std::assert_matches::assert_matches;
+
+use futures::executor::block_on;
+use futures::FutureExt as _;
+use occur::repository::{EntityRepository, Error, MaxAttempts, NoRetry};
+use occur::store::inmem::{self, InmemStore};
+use occur::store::{Store as _, WriteStream as _};
+use rstest::rstest;
+
+use crate::example::user;
+use crate::fixture::user::admin_id;
+
+mod example;
+mod fixture;
+
+#[derive(Clone, Debug)]
+enum Command {
+    Create { name: String },
+    Rename { new_name: String },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Rejection {
+    AlreadyExists,
+    NotFound,
+}
+
+fn decide(
+    user: &Option<user::Entity>,
+    command: Command,
+) -> Result<Vec<user::Event>, Rejection> {
+    match (user, command) {
+        (None, Command::Create { name }) => {
+            Ok(vec![user::Event::Created { name, is_admin: false }])
+        }
+        (Some(_), Command::Create { .. }) => Err(Rejection::AlreadyExists),
+        (None, Command::Rename { .. }) => Err(Rejection::NotFound),
+        (Some(user), Command::Rename { new_name }) => {
+            if user.name == new_name {
+                Ok(vec![])
+            } else {
+                Ok(vec![user::Event::Renamed { new_name }])
+            }
+        }
+    }
+}
+
+#[rstest]
+fn handle_commands(admin_id: user::Id) {
+    let mut repository: EntityRepository<_, user::Entity> =
+        EntityRepository::new(InmemStore::new(inmem::no_serialization()));
+
+    let outcome = block_on(repository.handle(
+        admin_id,
+        Command::Create { name: "admin".to_owned() },
+        decide,
+    ))
+    .unwrap();
+    assert_eq!(outcome.events, vec![user::Event::Created {
+        name: "admin".to_owned(),
+        is_admin: false,
+    }]);
+    assert_eq!(outcome.rehydrated.last_commit_number, Some(0));
+    assert_eq!(outcome.rehydrated.entity.unwrap().name, "admin");
+
+    let outcome = block_on(repository.handle(
+        admin_id,
+        Command::Rename { new_name: "root".to_owned() },
+        decide,
+    ))
+    .unwrap();
+    assert_eq!(outcome.rehydrated.last_commit_number, Some(1));
+    assert_eq!(outcome.rehydrated.entity.unwrap().name, "root");
+
+    let rehydrated = block_on(repository.load(admin_id)).unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(1));
+    assert_eq!(rehydrated.entity.unwrap().name, "root");
+}
+
+#[rstest]
+fn handle_command_without_events(admin_id: user::Id) {
+    let mut repository: EntityRepository<_, user::Entity> =
+        EntityRepository::new(InmemStore::new(inmem::no_serialization()));
+
+    let create = Command::Create { name: "admin".to_owned() };
+    block_on(repository.handle(admin_id, create, decide)).unwrap();
+
+    let rename = Command::Rename { new_name: "admin".to_owned() };
+    let outcome = block_on(repository.handle(admin_id, rename, decide));
+    let outcome = outcome.unwrap();
+    assert!(outcome.events.is_empty());
+    assert_eq!(outcome.rehydrated.last_commit_number, Some(0));
+}
+
+#[rstest]
+fn rejected_command(admin_id: user::Id) {
+    let mut repository: EntityRepository<_, user::Entity> =
+        EntityRepository::new(InmemStore::new(inmem::no_serialization()));
+
+    let rename = Command::Rename { new_name: "root".to_owned() };
+    let result = block_on(repository.handle(admin_id, rename, decide));
+
+    assert_matches!(result, Err(Error::Rejected(Rejection::NotFound)));
+}
+
+#[rstest]
+fn retry_on_concurrent_commit(admin_id: user::Id) {
+    let mut repository = EntityRepository::<_, user::Entity>::new(
+        InmemStore::new(inmem::no_serialization()),
+    )
+    .with_retry_policy(MaxAttempts(2));
+    let mut concurrent_stream = repository.store().write_stream(admin_id);
+
+    let mut attempts = 0;
+    let create = Command::Create { name: "admin".to_owned() };
+    let outcome = block_on(repository.handle(admin_id, create, |user, cmd| {
+        attempts += 1;
+        if attempts == 1 {
+            // simulate another writer creating the user in the meantime
+            let created = user::Event::Created {
+                name: "other".to_owned(),
+                is_admin: false,
+            };
+            concurrent_stream
+                .commit_unconditionally(&created)
+                .now_or_never()
+                .unwrap()
+                .unwrap();
+        }
+        decide(user, cmd)
+    }));
+
+    // the second attempt sees the concurrently created user
+    assert_eq!(attempts, 2);
+    assert_matches!(outcome, Err(Error::Rejected(Rejection::AlreadyExists)));
+}
+
+#[rstest]
+fn give_up_on_concurrent_commit(admin_id: user::Id) {
+    let mut repository = EntityRepository::<_, user::Entity>::new(
+        InmemStore::new(inmem::no_serialization()),
+    )
+    .with_retry_policy(NoRetry);
+    let mut concurrent_stream = repository.store().write_stream(admin_id);
+
+    let rename = user::Event::Renamed { new_name: "other".to_owned() };
+    let create = Command::Create { name: "admin".to_owned() };
+    let outcome = block_on(repository.handle(admin_id, create, |user, cmd| {
+        concurrent_stream
+            .commit_unconditionally(&rename)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        decide(user, cmd)
+    }));
+
+    assert_matches!(outcome, Err(Error::Conflict { attempts: 1, .. }));
+}
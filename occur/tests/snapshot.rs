@@ -0,0 +1,204 @@
+use futures::executor::block_on;
+use occur::store::inmem::{
+    self,
+    InmemSnapshotStore,
+    InmemStore,
+    NoSnapshotSerializer,
+};
+use occur::store::snapshot::{
+    self,
+    EveryNEvents,
+    OnDemand,
+    Snapshot,
+    SnapshotStore as _,
+};
+use occur::store::{Store as _, WriteStream as _};
+use occur::Entity as _;
+use rstest::rstest;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+fn renamed(new_name: &str) -> user::Event {
+    user::Event::Renamed { new_name: new_name.to_owned() }
+}
+
+#[rstest]
+fn load_takes_snapshot_every_n_events(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut snapshots = InmemSnapshotStore::new(NoSnapshotSerializer::new());
+    let mut stream = store.write_stream(admin_id);
+    let policy = EveryNEvents(2);
+
+    block_on(stream.commit_unconditionally(&admin_created)).unwrap();
+    let rehydrated: user::Entity =
+        block_on(snapshot::load(&store, &mut snapshots, &policy, admin_id))
+            .unwrap()
+            .entity
+            .unwrap();
+    assert_eq!(rehydrated.name, "admin");
+    assert_eq!(block_on(snapshots.load(&admin_id)).unwrap(), None);
+
+    block_on(stream.commit_unconditionally(&renamed("root"))).unwrap();
+    block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &policy,
+        admin_id,
+    ))
+    .unwrap();
+    let snapshot = block_on(snapshots.load(&admin_id)).unwrap().unwrap();
+    assert_eq!(snapshot.commit_number, 1);
+    assert_eq!(snapshot.schema_version, 0);
+    assert_eq!(snapshot.entity.name, "root");
+
+    // only one event was committed since the last snapshot
+    block_on(stream.commit_unconditionally(&renamed("superuser"))).unwrap();
+    block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &policy,
+        admin_id,
+    ))
+    .unwrap();
+    let snapshot = block_on(snapshots.load(&admin_id)).unwrap().unwrap();
+    assert_eq!(snapshot.commit_number, 1);
+}
+
+#[rstest]
+fn load_starts_from_snapshot(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut snapshots = InmemSnapshotStore::new(NoSnapshotSerializer::new());
+    let mut stream = store.write_stream(admin_id);
+    block_on(stream.commit_many_unconditionally([
+        &admin_created,
+        &renamed("root"),
+        &renamed("superuser"),
+    ]))
+    .unwrap();
+
+    // a snapshot that doesn't match the events, to prove they aren't folded
+    let mut entity = user::Entity::new(admin_id, admin_created).unwrap();
+    entity.name = "from_snapshot".to_owned();
+    let snapshot = Snapshot { commit_number: 2, schema_version: 0, entity };
+    block_on(snapshots.save(&admin_id, &snapshot)).unwrap();
+
+    let rehydrated = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &OnDemand,
+        admin_id,
+    ))
+    .unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(2));
+    assert_eq!(rehydrated.entity.unwrap().name, "from_snapshot");
+
+    // events committed after the snapshot are folded
+    block_on(stream.commit_unconditionally(&renamed("after_snapshot")))
+        .unwrap();
+    let rehydrated = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &OnDemand,
+        admin_id,
+    ))
+    .unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(3));
+    assert_eq!(rehydrated.entity.unwrap().name, "after_snapshot");
+}
+
+#[rstest]
+fn load_discards_incompatible_snapshot(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut snapshots = InmemSnapshotStore::new(NoSnapshotSerializer::new());
+    let mut stream = store.write_stream(admin_id);
+    block_on(
+        stream.commit_many_unconditionally([&admin_created, &renamed("root")]),
+    )
+    .unwrap();
+
+    let mut entity = user::Entity::new(admin_id, admin_created).unwrap();
+    entity.name = "from_snapshot".to_owned();
+    let snapshot = Snapshot { commit_number: 1, schema_version: 42, entity };
+    block_on(snapshots.save(&admin_id, &snapshot)).unwrap();
+
+    let rehydrated = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &OnDemand,
+        admin_id,
+    ))
+    .unwrap();
+    assert_eq!(rehydrated.entity.unwrap().name, "root");
+    assert_eq!(block_on(snapshots.load(&admin_id)).unwrap(), None);
+}
+
+#[rstest]
+fn take_snapshot_on_demand(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut snapshots = InmemSnapshotStore::new(NoSnapshotSerializer::new());
+    let mut stream = store.write_stream(admin_id);
+    block_on(stream.commit_unconditionally(&admin_created)).unwrap();
+
+    let rehydrated = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &OnDemand,
+        admin_id,
+    ))
+    .unwrap();
+    assert_eq!(block_on(snapshots.load(&admin_id)).unwrap(), None);
+
+    block_on(snapshot::take(&mut snapshots, &admin_id, &rehydrated)).unwrap();
+    let snapshot = block_on(snapshots.load(&admin_id)).unwrap().unwrap();
+    assert_eq!(snapshot.commit_number, 0);
+    assert_eq!(Some(snapshot.entity), rehydrated.entity);
+}
+
+#[cfg(feature = "serde")]
+#[rstest]
+fn snapshots_round_trip_through_serializer(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    use occur::store::serialization::json::JsonSnapshotSerializer;
+
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut snapshots = InmemSnapshotStore::new(JsonSnapshotSerializer::new());
+    let mut stream = store.write_stream(admin_id);
+    block_on(
+        stream.commit_many_unconditionally([&admin_created, &renamed("root")]),
+    )
+    .unwrap();
+
+    let rehydrated = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &EveryNEvents(2),
+        admin_id,
+    ))
+    .unwrap();
+    let snapshot = block_on(snapshots.load(&admin_id)).unwrap().unwrap();
+    assert_eq!(snapshot.commit_number, 1);
+    assert_eq!(snapshot.schema_version, 0);
+    assert_eq!(Some(snapshot.entity), rehydrated.entity);
+
+    // the snapshot is deserialized when loading the entity again
+    let reloaded = block_on(snapshot::load::<user::Entity, _, _, _>(
+        &store,
+        &mut snapshots,
+        &EveryNEvents(2),
+        admin_id,
+    ))
+    .unwrap();
+    assert_eq!(reloaded, rehydrated);
+}
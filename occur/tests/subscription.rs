@@ -0,0 +1,193 @@
+use std::convert::Infallible;
+use std::ops::ControlFlow;
+
+use futures::executor::block_on;
+use futures::future::ready;
+use futures::FutureExt as _;
+use occur::store::inmem::{self, InmemStore};
+use occur::store::{GlobalStore as _, Store as _, WriteStream as _};
+use occur::subscription::{
+    CheckpointStore as _,
+    FileCheckpointStore,
+    Filter,
+    InmemCheckpointStore,
+    Subscription,
+};
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+fn renamed(new_name: &str) -> user::Event {
+    user::Event::Renamed { new_name: new_name.to_owned() }
+}
+
+#[rstest]
+fn resume_from_checkpoint(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let mut write_stream = store.write_stream(admin_id);
+    block_on(write_stream.commit_many_unconditionally([
+        &admin_created,
+        &renamed("root"),
+        &renamed("superuser"),
+    ]))
+    .unwrap();
+
+    let mut subscription = Subscription::new(
+        "names",
+        store.all_stream(),
+        InmemCheckpointStore::new(),
+    );
+
+    // handle two events, then stop
+    let mut handled = Vec::new();
+    block_on(subscription.run(|item| {
+        handled.push(item.event);
+        let flow = if handled.len() == 2 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        };
+        ready(Ok::<_, Infallible>(flow))
+    }))
+    .unwrap();
+    assert_eq!(handled, [admin_created, renamed("root")]);
+    assert_eq!(block_on(subscription.checkpoint()).unwrap(), Some(1));
+
+    // resume right after the checkpoint, then tail new events
+    let mut handled = Vec::new();
+    let mut run = Box::pin(subscription.run(|item| {
+        handled.push(item.global_position);
+        let flow = if item.global_position == 3 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        };
+        ready(Ok::<_, Infallible>(flow))
+    }));
+    assert!(run.as_mut().now_or_never().is_none());
+    block_on(write_stream.commit_unconditionally(&renamed("admin"))).unwrap();
+    block_on(run).unwrap();
+    assert_eq!(handled, [2, 3]);
+    assert_eq!(block_on(subscription.checkpoint()).unwrap(), Some(3));
+}
+
+#[rstest]
+fn failed_event_is_handled_again(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    block_on(
+        store
+            .write_stream(admin_id)
+            .commit_many_unconditionally([&admin_created, &renamed("root")]),
+    )
+    .unwrap();
+
+    let mut subscription = Subscription::new(
+        "failing",
+        store.all_stream(),
+        InmemCheckpointStore::new(),
+    );
+
+    let result = block_on(subscription.run(|item| {
+        ready(if item.global_position == 1 {
+            Err("failed")
+        } else {
+            Ok(ControlFlow::Continue(()))
+        })
+    }));
+    assert!(matches!(
+        result,
+        Err(occur::subscription::Error::Handler("failed"))
+    ));
+    assert_eq!(block_on(subscription.checkpoint()).unwrap(), Some(0));
+
+    let mut handled = Vec::new();
+    block_on(subscription.run(|item| {
+        handled.push(item.global_position);
+        ready(Ok::<_, Infallible>(ControlFlow::Break(())))
+    }))
+    .unwrap();
+    assert_eq!(handled, [1]);
+}
+
+#[rstest]
+fn filter_events(admin_id: user::Id, admin_created: user::Event) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let other_id = user::Id(Uuid::now_v7());
+    block_on(async {
+        store
+            .write_stream(admin_id)
+            .commit_many_unconditionally([&admin_created, &renamed("root")])
+            .await
+            .unwrap();
+        store
+            .write_stream(other_id)
+            .commit_many_unconditionally([&admin_created, &renamed("other")])
+            .await
+            .unwrap();
+    });
+
+    let filter = Filter::all()
+        .revisions([("Renamed", 0)])
+        .stream_id(move |id| *id == other_id);
+    let mut subscription = Subscription::new(
+        "filtered",
+        store.all_stream(),
+        InmemCheckpointStore::new(),
+    )
+    .with_filter(filter);
+
+    let mut handled = Vec::new();
+    block_on(subscription.run(|item| {
+        handled.push((item.global_position, item.event));
+        ready(Ok::<_, Infallible>(ControlFlow::Break(())))
+    }))
+    .unwrap();
+    assert_eq!(handled, [(3, renamed("other"))]);
+}
+
+#[test]
+fn file_checkpoint_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut checkpoints = FileCheckpointStore::new(dir.path().join("nested"));
+
+    assert_eq!(block_on(checkpoints.load("projection")).unwrap(), None);
+
+    block_on(checkpoints.save("projection", 7)).unwrap();
+    block_on(checkpoints.save("projection", 42)).unwrap();
+
+    // checkpoints persist across instances
+    let mut checkpoints = FileCheckpointStore::new(dir.path().join("nested"));
+    assert_eq!(block_on(checkpoints.load("projection")).unwrap(), Some(42));
+    assert_eq!(block_on(checkpoints.load("other")).unwrap(), None);
+
+    block_on(checkpoints.delete("projection")).unwrap();
+    block_on(checkpoints.delete("projection")).unwrap();
+    assert_eq!(block_on(checkpoints.load("projection")).unwrap(), None);
+}
+
+#[test]
+fn file_checkpoint_store_encodes_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let checkpoints_dir = dir.path().join("nested");
+    let mut checkpoints = FileCheckpointStore::new(&checkpoints_dir);
+
+    let names = ["../escaped", "a/b", "a%2Fb", "", "."];
+    for (position, name) in (0..).zip(names) {
+        block_on(checkpoints.save(name, position)).unwrap();
+    }
+
+    // each name has a file of its own, within the directory
+    for (position, name) in (0..).zip(names) {
+        assert_eq!(block_on(checkpoints.load(name)).unwrap(), Some(position));
+    }
+    assert_eq!(std::fs::read_dir(&checkpoints_dir).unwrap().count(), 5);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
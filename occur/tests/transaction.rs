@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use futures::executor::block_on;
+use futures::{StreamExt as _, TryStreamExt as _};
+use occur::envelope::Envelope;
+use occur::store::clock::Clock;
+use occur::store::inmem::{self, InmemStore};
+use occur::store::transaction::Error as _;
+use occur::store::write::{self, Condition, Error as _};
+use occur::store::{
+    read,
+    AllStream as _,
+    GlobalStore as _,
+    ReadStream as _,
+    Store as _,
+    Transaction,
+    TransactionalStore as _,
+    WriteStream as _,
+};
+use occur::ErrorWithKind as _;
+use rstest::{fixture, rstest};
+use uuid::Uuid;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+type Store = InmemStore<
+    user::Event,
+    inmem::NoSerializer<user::Event>,
+    inmem::NoSerializer<user::Event>,
+>;
+
+#[fixture]
+#[allow(unused_braces)]
+fn other_id() -> user::Id { user::Id(Uuid::now_v7()) }
+
+/// A clock that advances by a second every time it's read.
+#[derive(Default)]
+struct TickingClock(AtomicU64);
+
+impl Clock for TickingClock {
+    fn now(&self) -> SystemTime {
+        let secs = self.0.fetch_add(1, Ordering::Relaxed);
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+}
+
+fn read_all(store: &Store, id: user::Id) -> Vec<user::Event> {
+    let mut stream = store.read_stream(id);
+    block_on(async { stream.read_all().await.unwrap().try_collect().await })
+        .unwrap()
+}
+
+#[rstest]
+fn commits_to_many_streams(
+    admin_id: user::Id,
+    other_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    block_on(
+        store.write_stream(admin_id).commit_unconditionally(&admin_created),
+    )
+    .unwrap();
+    let befriended_other = user::Event::Befriended { user: other_id };
+    let befriended_admin = user::Event::Befriended { user: admin_id };
+
+    let transaction = Transaction::new()
+        .append(admin_id, [&befriended_other], Condition::LastCommitNumber(0))
+        .append(other_id, [&befriended_admin], Condition::None);
+    let commit_numbers = block_on(store.commit_transaction(transaction));
+
+    assert_eq!(commit_numbers.unwrap(), [Some(1), Some(0)]);
+    assert_eq!(read_all(&store, admin_id), [admin_created, befriended_other]);
+    assert_eq!(read_all(&store, other_id), [befriended_admin]);
+}
+
+#[rstest]
+fn commits_none_when_a_condition_is_not_met(
+    admin_id: user::Id,
+    other_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    block_on(
+        store.write_stream(admin_id).commit_unconditionally(&admin_created),
+    )
+    .unwrap();
+    let befriended_other = user::Event::Befriended { user: other_id };
+    let befriended_admin = user::Event::Befriended { user: admin_id };
+
+    let transaction = Transaction::new()
+        .append(other_id, [&befriended_admin], Condition::None)
+        .append(admin_id, [&befriended_other], Condition::NoStream);
+    let err = block_on(store.commit_transaction(transaction)).unwrap_err();
+
+    assert_eq!(err.kind(), write::ErrorKind::ConditionNotMet);
+    assert_eq!(err.append_index(), Some(1));
+    assert_eq!(err.last_commit_number(), Some(0));
+    assert_eq!(read_all(&store, admin_id), [admin_created]);
+    assert!(block_on(store.read_stream(other_id).read_all()).is_err());
+    let mut all_stream = store.all_stream();
+    let n_events =
+        block_on(async { all_stream.read_all().await.unwrap().count().await });
+    assert_eq!(n_events, 1);
+}
+
+#[rstest]
+fn appends_to_the_same_stream_are_applied_in_order(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let renamed = user::Event::Renamed { new_name: "root".to_owned() };
+
+    let transaction = Transaction::new()
+        .append(admin_id, [&admin_created], Condition::NoStream)
+        .append(admin_id, [&renamed], Condition::LastCommitNumber(0));
+    let commit_numbers = block_on(store.commit_transaction(transaction));
+
+    assert_eq!(commit_numbers.unwrap(), [Some(0), Some(1)]);
+    assert_eq!(read_all(&store, admin_id), [admin_created, renamed]);
+}
+
+#[rstest]
+fn transactions_are_idempotent(
+    admin_id: user::Id,
+    other_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization());
+    let befriended_admin = user::Event::Befriended { user: admin_id };
+    let created = Envelope::new(&admin_created);
+    let befriended = Envelope::new(&befriended_admin);
+    let transaction = || {
+        Transaction::new()
+            .append_envelopes(admin_id, [created.clone()], Condition::NoStream)
+            .append_envelopes(other_id, [befriended.clone()], Condition::None)
+    };
+
+    let first = block_on(store.commit_transaction(transaction())).unwrap();
+    let second = block_on(store.commit_transaction(transaction())).unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(read_all(&store, admin_id), [admin_created]);
+    assert_eq!(read_all(&store, other_id), [befriended_admin]);
+}
+
+#[rstest]
+fn concurrent_transactions_do_not_deadlock(
+    admin_id: user::Id,
+    other_id: user::Id,
+) {
+    const THREADS: usize = 8;
+    const TRANSACTIONS_PER_THREAD: usize = 16;
+
+    let store = InmemStore::new(inmem::no_serialization());
+    let befriended_other = user::Event::Befriended { user: other_id };
+    let befriended_admin = user::Event::Befriended { user: admin_id };
+
+    thread::scope(|scope| {
+        for i in 0..THREADS {
+            let store = &store;
+            let (befriended_other, befriended_admin) =
+                (&befriended_other, &befriended_admin);
+            scope.spawn(move || {
+                for _ in 0..TRANSACTIONS_PER_THREAD {
+                    // half of the threads stage the streams in reverse order
+                    let (first, second) = if i % 2 == 0 {
+                        (
+                            (admin_id, befriended_other),
+                            (other_id, befriended_admin),
+                        )
+                    } else {
+                        (
+                            (other_id, befriended_admin),
+                            (admin_id, befriended_other),
+                        )
+                    };
+                    let transaction = Transaction::new()
+                        .append(first.0, [first.1], Condition::None)
+                        .append(second.0, [second.1], Condition::None);
+                    block_on(store.commit_transaction(transaction)).unwrap();
+                }
+            });
+        }
+    });
+
+    let n_commits = THREADS * TRANSACTIONS_PER_THREAD;
+    assert_eq!(read_all(&store, admin_id).len(), n_commits);
+    assert_eq!(read_all(&store, other_id).len(), n_commits);
+}
+
+#[rstest]
+fn events_share_a_single_timestamp(
+    admin_id: user::Id,
+    other_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = InmemStore::new(inmem::no_serialization())
+        .with_clock(TickingClock::default());
+    let befriended_admin = user::Event::Befriended { user: admin_id };
+
+    let transaction = Transaction::new()
+        .append(admin_id, [&admin_created], Condition::NoStream)
+        .append(other_id, [&befriended_admin], Condition::NoStream);
+    block_on(store.commit_transaction(transaction)).unwrap();
+
+    let timestamps: Vec<_> = [admin_id, other_id]
+        .into_iter()
+        .flat_map(|id| {
+            let mut stream = store.read_stream(id);
+            block_on(async {
+                stream
+                    .read_recorded(read::Options {
+                        position: read::Position::First,
+                        direction: read::Direction::Forward,
+                        limit: None,
+                    })
+                    .await
+                    .unwrap()
+                    .map_ok(|recorded| recorded.timestamp)
+                    .try_collect::<Vec<_>>()
+                    .await
+            })
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(timestamps, [SystemTime::UNIX_EPOCH; 2]);
+}
@@ -0,0 +1,28 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    #[revision(version = 1)]
+    Renamed,
+}
+
+impl occur::Event for Event {
+    type StreamId = u32;
+    type OldRevision = OldEvent;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::revision::Convert)]
+enum OldEvent {
+    #[revision(name = "Renamed", version = 0)]
+    #[convert(into = Self, with = renamed_v0)]
+    Renamed_V0,
+
+    #[revision(name = "Renamed", version = 1)]
+    #[convert(into = Event, with = renamed_v1)]
+    Renamed_V1,
+}
+
+fn renamed_v0(_: OldEvent) -> OldEvent { OldEvent::Renamed_V1 }
+
+fn renamed_v1(_: OldEvent) -> Event { Event::Renamed }
+
+fn main() {}
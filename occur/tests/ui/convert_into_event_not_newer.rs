@@ -0,0 +1,29 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    Created,
+    #[revision(version = 1)]
+    Renamed,
+}
+
+impl occur::Event for Event {
+    type StreamId = u32;
+    type OldRevision = OldEvent;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::revision::Convert)]
+enum OldEvent {
+    #[revision(name = "Renamed", version = 3)]
+    #[convert(into = Event, with = renamed)]
+    Renamed_V3,
+
+    #[revision(name = "Deleted", version = 0)]
+    #[convert(into = Event, with = deleted)]
+    Deleted_V0,
+}
+
+fn renamed(_: OldEvent) -> Event { Event::Renamed }
+
+fn deleted(_: OldEvent) -> Event { Event::Created }
+
+fn main() {}
@@ -0,0 +1,23 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    #[revision(version = 2)]
+    Renamed,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::revision::Convert)]
+enum OldEvent {
+    #[revision(name = "Renamed", version = 0)]
+    #[convert(into = Event, with = to_event)]
+    Renamed_V0,
+
+    #[revision(name = "Renamed", version = 1)]
+    #[convert(into = Self, with = to_self)]
+    Renamed_V1,
+}
+
+fn to_event(_: OldEvent) -> Event { Event::Renamed }
+
+fn to_self(_: OldEvent) -> OldEvent { OldEvent::Renamed_V0 }
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    #[revision(version = 1)]
+    Renamed,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::revision::Convert)]
+enum OldEvent {
+    #[revision(name = "Renamed", version = 0)]
+    Renamed_V0,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    Created,
+    #[revision(name = "Created")]
+    AlsoCreated,
+    #[revision(version = 1)]
+    Renamed,
+    #[revision(name = "Renamed", version = 1)]
+    AlsoRenamed,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+struct Event {
+    name: String,
+}
+
+fn main() {}
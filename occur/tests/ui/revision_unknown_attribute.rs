@@ -0,0 +1,7 @@
+#[derive(Debug, Clone, PartialEq, Eq, Hash, occur::Revision)]
+enum Event {
+    #[revision(revision = 1)]
+    Created,
+}
+
+fn main() {}
@@ -0,0 +1,223 @@
+#![feature(assert_matches)]
+
+use std::assert_matches::assert_matches;
+use std::sync::{Arc, Mutex};
+
+use futures::executor::block_on;
+use futures::{StreamExt as _, TryStreamExt as _};
+use occur::entity::{self, Rehydrated};
+use occur::envelope::Recorded;
+use occur::revision::OldOrNew;
+use occur::store::inmem::{self, InmemStore};
+use occur::store::read::{self, MaybeUnknown, UnknownRevisionPolicy};
+use occur::store::serialization::{self, Serialization};
+use occur::store::{
+    AllStream as _,
+    Deserializer,
+    GlobalStore as _,
+    ReadStream as _,
+    Store as _,
+    WriteStream as _,
+};
+use occur::ErrorWithKind as _;
+use rstest::rstest;
+
+use crate::example::user;
+use crate::fixture::user::{admin_created, admin_id};
+
+mod example;
+mod fixture;
+
+type Payload = Recorded<OldOrNew<user::Event>>;
+
+/// Deserializes events as a reader that doesn't know of `Renamed` events yet
+/// (e.g., an older deployment, during a rolling upgrade).
+#[derive(Clone)]
+struct OutdatedDeserializer;
+
+impl Deserializer for OutdatedDeserializer {
+    type Event = user::Event;
+    type SerializedEvent = Payload;
+
+    fn deserialize(
+        &self,
+        event: Self::SerializedEvent,
+    ) -> Result<Recorded<OldOrNew<Self::Event>>, serialization::Error> {
+        match event.event {
+            OldOrNew::New(user::Event::Renamed { .. }) => {
+                Err(serialization::Error::unknown_revision(&"Renamed"))
+            }
+            _ => Ok(event),
+        }
+    }
+}
+
+type Store = InmemStore<
+    user::Event,
+    inmem::NoSerializer<user::Event>,
+    OutdatedDeserializer,
+>;
+
+fn renamed(new_name: &str) -> user::Event {
+    user::Event::Renamed { new_name: new_name.to_owned() }
+}
+
+fn deactivated() -> user::Event {
+    user::Event::Deactivated { reason: "spam".to_owned() }
+}
+
+/// Returns a store holding a stream whose second event is of an unknown
+/// revision.
+fn store(admin_id: user::Id, admin_created: &user::Event) -> Store {
+    let store = InmemStore::new(Serialization {
+        serializer: inmem::NoSerializer::new(),
+        deserializer: OutdatedDeserializer,
+    });
+    block_on(store.write_stream(admin_id).commit_many_unconditionally([
+        admin_created,
+        &renamed("root"),
+        &deactivated(),
+    ]))
+    .unwrap();
+    store
+}
+
+#[rstest]
+fn fail_by_default(admin_id: user::Id, admin_created: user::Event) {
+    let store = store(admin_id, &admin_created);
+    let mut read_stream = store.read_stream(admin_id);
+
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().collect().await
+    });
+
+    assert_eq!(events.len(), 3);
+    let err = events[1].as_ref().unwrap_err();
+    assert_eq!(err.kind(), read::ErrorKind::UnknownRevision);
+}
+
+#[rstest]
+fn skip_and_report(admin_id: user::Id, admin_created: user::Event) {
+    let store = store(admin_id, &admin_created);
+    let mut read_stream = store.read_stream(admin_id);
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let report = reported.clone();
+    read_stream.set_unknown_revision_policy(UnknownRevisionPolicy::skip(
+        move |unknown| {
+            let payload = unknown.payload::<Payload>().unwrap();
+            report.lock().unwrap().push(payload.event.clone());
+        },
+    ));
+
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().try_collect().await
+    })
+    .unwrap();
+
+    assert_eq!(events, [admin_created, deactivated()]);
+    assert_eq!(*reported.lock().unwrap(), [OldOrNew::New(renamed("root"))]);
+}
+
+#[rstest]
+fn surface(admin_id: user::Id, admin_created: user::Event) {
+    let store = store(admin_id, &admin_created);
+    let mut read_stream = store.read_stream(admin_id);
+    read_stream.set_unknown_revision_policy(UnknownRevisionPolicy::Surface);
+
+    let events: Vec<_> = block_on(async {
+        read_stream
+            .read_unconverted(read::Options {
+                position: read::Position::First,
+                direction: read::Direction::Forward,
+                limit: None,
+            })
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+    })
+    .unwrap();
+
+    assert_eq!(events.len(), 3);
+    let MaybeUnknown::Unknown(unknown) = &events[1] else {
+        panic!("expected an unknown event, got {:?}", events[1]);
+    };
+    let payload = unknown.payload::<Payload>().unwrap();
+    assert_eq!(payload.event, OldOrNew::New(renamed("root")));
+    assert_matches!(
+        &events[2],
+        MaybeUnknown::Known(OldOrNew::New(event)) if *event == deactivated()
+    );
+}
+
+#[rstest]
+fn surface_is_skipped_by_converted_reads(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = store(admin_id, &admin_created);
+    let mut read_stream = store.read_stream(admin_id);
+    read_stream.set_unknown_revision_policy(UnknownRevisionPolicy::Surface);
+
+    let events: Vec<_> = block_on(async {
+        read_stream.read_all().await.unwrap().try_collect().await
+    })
+    .unwrap();
+
+    assert_eq!(events, [admin_created, deactivated()]);
+}
+
+#[rstest]
+fn rehydrating_fails_across_skipped_event(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = store(admin_id, &admin_created);
+    let mut read_stream = store.read_stream(admin_id);
+    read_stream
+        .set_unknown_revision_policy(UnknownRevisionPolicy::skip(|_| {}));
+
+    let err = block_on(entity::rehydrate::<user::Entity, _>(
+        admin_id,
+        &mut read_stream,
+    ))
+    .unwrap_err();
+    assert_eq!(err.kind(), read::ErrorKind::UnknownRevision);
+
+    // the stream's own policy is restored afterwards
+    assert_matches!(
+        read_stream.unknown_revision_policy(),
+        UnknownRevisionPolicy::Skip(_)
+    );
+    let rehydrated: Rehydrated<user::Entity> = block_on(
+        entity::rehydrate_from(admin_id, &mut read_stream, Rehydrated {
+            entity: None,
+            last_commit_number: Some(1),
+        }),
+    )
+    .unwrap();
+    assert_eq!(rehydrated.last_commit_number, Some(2));
+}
+
+#[rstest]
+fn store_policy_applies_to_all_stream(
+    admin_id: user::Id,
+    admin_created: user::Event,
+) {
+    let store = store(admin_id, &admin_created)
+        .with_unknown_revision_policy(UnknownRevisionPolicy::skip(|_| {}));
+
+    let events: Vec<_> = block_on(async {
+        store
+            .all_stream()
+            .read_all()
+            .await
+            .unwrap()
+            .map_ok(|item| item.event)
+            .try_collect()
+            .await
+    })
+    .unwrap();
+
+    assert_eq!(events, [admin_created, deactivated()]);
+}